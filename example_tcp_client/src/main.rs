@@ -60,7 +60,8 @@ fn print_usage() {
     {}
     ",
         serde_json::to_string(&ServerMessage::LayerChange {
-            new: "newly-changed-to-layer".into()
+            new: "newly-changed-to-layer".into(),
+            metadata: Default::default(),
         })
         .expect("deserializable"),
         serde_json::to_string(&ClientMessage::ChangeLayer {
@@ -95,7 +96,18 @@ fn init_logger(args: &Args) {
 
 fn write_to_kanata(mut s: TcpStream) {
     log::info!("writer starting");
+    let hello = serde_json::to_string(&ClientRequest {
+        id: Some(0),
+        msg: ClientMessage::Hello {},
+    })
+    .expect("deserializable");
+    s.write_all(hello.as_bytes()).expect("stream writable");
     log::info!("writer: type layer name then press enter to send a change layer request to kanata");
+    log::info!(
+        "writer: type sub:<topics> with comma-separated topics, e.g. sub:InputEvents,KeyOutput, \
+         to subscribe to events"
+    );
+    log::info!("writer: type dm:<macro ID> to print a recorded dynamic macro as a macro action");
     let mut layer = String::new();
     loop {
         stdin().read_line(&mut layer).expect("stdin is readable");
@@ -112,6 +124,38 @@ fn write_to_kanata(mut s: TcpStream) {
             layer.clear();
             continue;
         }
+        if new.starts_with("sub:") {
+            let topics = new
+                .trim_start_matches("sub:")
+                .split(',')
+                .filter(|topic| !topic.is_empty())
+                .filter_map(|topic| {
+                    serde_json::from_str::<SubscriptionTopic>(&format!("\"{topic}\""))
+                        .map_err(|_| log::warn!("writer: unknown topic \"{topic}\""))
+                        .ok()
+                })
+                .collect::<Vec<_>>();
+            log::info!("writer: subscribing to {topics:?}");
+            let msg = serde_json::to_string(&ClientMessage::Subscribe { topics })
+                .expect("deserializable");
+            s.write_all(msg.as_bytes()).expect("stream writable");
+            layer.clear();
+            continue;
+        }
+        if let Some(macro_id) = new.strip_prefix("dm:") {
+            match macro_id.parse() {
+                Ok(macro_id) => {
+                    let msg = serde_json::to_string(&ClientMessage::RequestDynamicMacroConfig {
+                        macro_id,
+                    })
+                    .expect("deserializable");
+                    s.write_all(msg.as_bytes()).expect("stream writable");
+                }
+                Err(_) => log::warn!("writer: invalid macro ID \"{macro_id}\""),
+            }
+            layer.clear();
+            continue;
+        }
         log::info!("writer: telling kanata to change layer to \"{new}\"");
         let msg =
             serde_json::to_string(&ClientMessage::ChangeLayer { new }).expect("deserializable");
@@ -135,8 +179,32 @@ fn read_from_kanata(s: TcpStream) {
             }
         };
         match parsed_msg {
-            ServerMessage::LayerChange { new } => {
-                log::info!("reader: kanata changed layers to \"{new}\"");
+            ServerMessage::LayerChange { new, metadata } => {
+                log::info!("reader: kanata changed layers to \"{new}\" {metadata:?}");
+            }
+            ServerMessage::Hello {
+                version,
+                protocol_version,
+                features,
+                ..
+            } => {
+                log::info!(
+                    "reader: kanata v{version}, protocol v{protocol_version}, features: {features:?}"
+                );
+            }
+            ServerMessage::DynamicMacroConfig {
+                macro_id, config, ..
+            } => {
+                log::info!("reader: dynamic macro {macro_id}: {config}");
+            }
+            ServerMessage::Event(EventMessage::HoldTapDecision { decision, .. }) => {
+                log::info!(
+                    "reader: hold-tap {}: {} ({}) after {}ms",
+                    decision.position.name.unwrap_or_default(),
+                    decision.outcome,
+                    decision.reason,
+                    decision.elapsed_ms,
+                );
             }
             msg => {
                 log::info!("got msg: {msg:?}");
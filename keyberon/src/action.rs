@@ -1,7 +1,7 @@
 //! The different actions that can be executed via any given key.
 
 use crate::key_code::KeyCode;
-use crate::layout::{QueuedIter, WaitingAction};
+use crate::layout::{HoldTapReason, QueuedIter, WaitingAction};
 use core::fmt::Debug;
 
 pub mod switch;
@@ -77,7 +77,8 @@ pub enum HoldTapConfig<'a> {
     /// corresponding key was pressed/released, i.e. the first event is the
     /// event first received after the HoldTap action key is pressed.
     ///
-    /// The return value should be the intended action that should be used. A
+    /// The return value should be the intended action that should be used,
+    /// along with the reason for it that is reported in [HoldTapDecision]. A
     /// [Some] value will cause one of: [WaitingAction::Tap] for the configured
     /// tap action, [WaitingAction::Hold] for the hold action, and
     /// [WaitingAction::NoOp] to drop handling of the key press. A [None]
@@ -87,9 +88,12 @@ pub enum HoldTapConfig<'a> {
     /// The bool value defines if the timeout check should be skipped at the
     /// next tick. This should generally be false. This is used by `tap-hold-
     /// except-keys` to handle presses even when the timeout has been reached.
-    Custom(&'a (dyn Fn(QueuedIter) -> (Option<WaitingAction>, bool) + Send + Sync)),
+    Custom(&'a (dyn Fn(QueuedIter) -> CustomHoldTapDecision + Send + Sync)),
 }
 
+/// Return value of a [HoldTapConfig::Custom] handler.
+pub type CustomHoldTapDecision = (Option<(WaitingAction, HoldTapReason)>, bool);
+
 impl Debug for HoldTapConfig<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
@@ -167,6 +171,12 @@ where
     ///
     /// To deactivate the functionality, set this to 0.
     pub tap_hold_interval: u16,
+    /// If any key was pressed less than `require_prior_idle` ticks before this key, the tap
+    /// action is activated right away instead of waiting to decide between tap and hold.
+    /// This avoids accidental holds while typing quickly.
+    ///
+    /// To deactivate the functionality, set this to 0.
+    pub require_prior_idle: u16,
 }
 
 /// Define one shot key behaviour.
@@ -215,6 +225,9 @@ where
     /// activate. Tapping the tap-dance key once will activate the action in index 0, three
     /// times will activate the action in index 2.
     pub actions: &'a [&'a Action<'a, T>],
+    /// Hold actions corresponding by index to `actions`. Only used by
+    /// `TapDanceConfig::LazyHold`; empty otherwise.
+    pub hold_actions: &'a [&'a Action<'a, T>],
     /// Timeout after which a tap will expire and become an action. A new tap for the same
     /// tap-dance key will reset this timeout.
     pub timeout: u16,
@@ -229,6 +242,9 @@ where
 pub enum TapDanceConfig {
     Lazy,
     Eager,
+    /// Like `Lazy`, but if the key is still held when the timeout expires after the final tap,
+    /// the hold action for that number of taps activates instead of the tap action.
+    LazyHold,
 }
 
 /// A group of chords (actions mapped to a combination of multiple physical keys pressed together).
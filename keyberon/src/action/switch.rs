@@ -49,6 +49,10 @@ const INPUT_VAL: u16 = 851;
 const HISTORICAL_INPUT_VAL: u16 = 852;
 const LAYER_VAL: u16 = 853;
 const BASE_LAYER_VAL: u16 = 854;
+const INPUT_DEVICE_VAL: u16 = 855;
+const CONTEXT_VAL: u16 = 856;
+// Unlike the other values above, this opcode has size 3.
+const VARIABLE_VAL: u16 = 857;
 
 // Binary values:
 // 0b0100 ...
@@ -87,6 +91,9 @@ enum OpCodeType {
     TicksSinceGreaterThan(TicksSinceNthKey),
     Layer(u16),
     BaseLayer(u16),
+    InputDevice(u8),
+    Context(u16),
+    Variable(VariableComparison),
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -112,6 +119,61 @@ struct HistoricalInput {
     how_far_back: u8,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// An op that compares the value of a variable with a constant.
+struct VariableComparison {
+    index: u16,
+    comparator: Comparator,
+    value: i16,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Comparison of a variable with a constant, in the form of: `variable <comparator> constant`.
+pub enum Comparator {
+    Equal,
+    NotEqual,
+    LessThan,
+    GreaterThan,
+    LessThanOrEqual,
+    GreaterThanOrEqual,
+}
+
+impl Comparator {
+    fn compare(self, lhs: i16, rhs: i16) -> bool {
+        match self {
+            Comparator::Equal => lhs == rhs,
+            Comparator::NotEqual => lhs != rhs,
+            Comparator::LessThan => lhs < rhs,
+            Comparator::GreaterThan => lhs > rhs,
+            Comparator::LessThanOrEqual => lhs <= rhs,
+            Comparator::GreaterThanOrEqual => lhs >= rhs,
+        }
+    }
+
+    fn to_bits(self) -> u16 {
+        match self {
+            Comparator::Equal => 0,
+            Comparator::NotEqual => 1,
+            Comparator::LessThan => 2,
+            Comparator::GreaterThan => 3,
+            Comparator::LessThanOrEqual => 4,
+            Comparator::GreaterThanOrEqual => 5,
+        }
+    }
+
+    fn from_bits(bits: u16) -> Self {
+        match bits {
+            0 => Comparator::Equal,
+            1 => Comparator::NotEqual,
+            2 => Comparator::LessThan,
+            3 => Comparator::GreaterThan,
+            4 => Comparator::LessThanOrEqual,
+            5 => Comparator::GreaterThanOrEqual,
+            _ => unreachable!("invalid comparator bits {bits}"),
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 struct TicksSinceNthKey {
     nth_key: u8,
@@ -131,7 +193,16 @@ impl<'a, T> Switch<'a, T> {
     /// the currently active keys, and historically pressed keys.
     ///
     /// The `historical_keys` parameter should iterate in the order of most-recent-first.
-    pub fn actions<A1, A2, H1, H2, L>(
+    ///
+    /// The `input_device` parameter identifies the input device of the key press that activated
+    /// the switch.
+    ///
+    /// The `contexts` parameter lists the ids of the context conditions that currently match the
+    /// externally provided context, e.g. the active application.
+    ///
+    /// The `variables` parameter iterates over the values of the variables, in index order.
+    #[allow(clippy::too_many_arguments)]
+    pub fn actions<A1, A2, H1, H2, L, C, V>(
         &self,
         active_keys: A1,
         active_positions: A2,
@@ -139,13 +210,18 @@ impl<'a, T> Switch<'a, T> {
         historical_positions: H2,
         layers: L,
         default_layer: u16,
-    ) -> SwitchActions<'a, T, A1, A2, H1, H2, L>
+        input_device: u8,
+        contexts: C,
+        variables: V,
+    ) -> SwitchActions<'a, T, A1, A2, H1, H2, L, C, V>
     where
         A1: Iterator<Item = KeyCode> + Clone,
         A2: Iterator<Item = KCoord> + Clone,
         H1: Iterator<Item = HistoricalEvent<KeyCode>> + Clone,
         H2: Iterator<Item = HistoricalEvent<KCoord>> + Clone,
         L: Iterator<Item = u16> + Clone,
+        C: Iterator<Item = u16> + Clone,
+        V: Iterator<Item = i16> + Clone,
     {
         SwitchActions {
             cases: self.cases,
@@ -155,6 +231,9 @@ impl<'a, T> Switch<'a, T> {
             historical_positions,
             layers,
             default_layer,
+            input_device,
+            contexts,
+            variables,
             case_index: 0,
         }
     }
@@ -162,13 +241,15 @@ impl<'a, T> Switch<'a, T> {
 
 #[derive(Debug, Clone)]
 /// Iterator returned by `Switch::actions`.
-pub struct SwitchActions<'a, T, A1, A2, H1, H2, L>
+pub struct SwitchActions<'a, T, A1, A2, H1, H2, L, C, V>
 where
     A1: Iterator<Item = KeyCode> + Clone,
     A2: Iterator<Item = KCoord> + Clone,
     H1: Iterator<Item = HistoricalEvent<KeyCode>> + Clone,
     H2: Iterator<Item = HistoricalEvent<KCoord>> + Clone,
     L: Iterator<Item = u16> + Clone,
+    C: Iterator<Item = u16> + Clone,
+    V: Iterator<Item = i16> + Clone,
 {
     cases: &'a [(&'a [OpCode], &'a Action<'a, T>, BreakOrFallthrough)],
     active_keys: A1,
@@ -177,16 +258,21 @@ where
     historical_positions: H2,
     layers: L,
     default_layer: u16,
+    input_device: u8,
+    contexts: C,
+    variables: V,
     case_index: usize,
 }
 
-impl<'a, T, A1, A2, H1, H2, L> Iterator for SwitchActions<'a, T, A1, A2, H1, H2, L>
+impl<'a, T, A1, A2, H1, H2, L, C, V> Iterator for SwitchActions<'a, T, A1, A2, H1, H2, L, C, V>
 where
     A1: Iterator<Item = KeyCode> + Clone,
     A2: Iterator<Item = KCoord> + Clone,
     H1: Iterator<Item = HistoricalEvent<KeyCode>> + Clone,
     H2: Iterator<Item = HistoricalEvent<KCoord>> + Clone,
     L: Iterator<Item = u16> + Clone,
+    C: Iterator<Item = u16> + Clone,
+    V: Iterator<Item = i16> + Clone,
 {
     type Item = &'a Action<'a, T>;
 
@@ -201,6 +287,9 @@ where
                 self.historical_positions.clone(),
                 self.layers.clone(),
                 self.default_layer,
+                self.input_device,
+                self.contexts.clone(),
+                self.variables.clone(),
             ) {
                 let ret_ac = case.1;
                 match case.2 {
@@ -315,8 +404,29 @@ impl OpCode {
         (Self(BASE_LAYER_VAL), Self(base_layer))
     }
 
+    /// Return OpCodes specifying an input device check.
+    pub fn new_input_device(device: u8) -> (Self, Self) {
+        (Self(INPUT_DEVICE_VAL), Self(u16::from(device)))
+    }
+
+    /// Return OpCodes specifying a context check. The id refers to a context condition that is
+    /// tracked outside of keyberon.
+    pub fn new_context(id: u16) -> (Self, Self) {
+        (Self(CONTEXT_VAL), Self(id))
+    }
+
+    /// Return OpCodes specifying a comparison of the variable at `index` with `value`.
+    pub fn new_variable(index: u16, comparator: Comparator, value: i16) -> (Self, Self, Self) {
+        assert!(index <= MAX_OPCODE_LEN);
+        (
+            Self(VARIABLE_VAL),
+            Self((comparator.to_bits() << 12) | index),
+            Self(value as u16),
+        )
+    }
+
     /// Return the interpretation of this `OpCode`.
-    fn opcode_type(self, next: Option<OpCode>) -> OpCodeType {
+    fn opcode_type(self, next: Option<OpCode>, next2: Option<OpCode>) -> OpCodeType {
         if self.0 < KEY_MAX {
             OpCodeType::KeyCode(self.0)
         } else if self.0 <= MAX_OPCODE_LEN {
@@ -329,6 +439,16 @@ impl OpCode {
                 }),
                 LAYER_VAL => OpCodeType::Layer(op2.0),
                 BASE_LAYER_VAL => OpCodeType::BaseLayer(op2.0),
+                INPUT_DEVICE_VAL => OpCodeType::InputDevice(op2.0 as u8),
+                CONTEXT_VAL => OpCodeType::Context(op2.0),
+                VARIABLE_VAL => {
+                    let op3 = next2.expect("next2 should be some for opcode {self:?}");
+                    OpCodeType::Variable(VariableComparison {
+                        index: op2.0 & MAX_OPCODE_LEN,
+                        comparator: Comparator::from_bits(op2.0 >> 12),
+                        value: op3.0 as i16,
+                    })
+                }
                 _ => unreachable!("unexpected opcode {self:?}"),
             }
         } else {
@@ -366,6 +486,7 @@ impl From<u16> for OperatorAndEndIndex {
 }
 
 /// Evaluate the return value of an expression evaluated on the given key codes.
+#[allow(clippy::too_many_arguments)]
 fn evaluate_boolean(
     bool_expr: &[OpCode],
     key_codes: impl Iterator<Item = KeyCode> + Clone,
@@ -374,6 +495,9 @@ fn evaluate_boolean(
     historical_inputs: impl Iterator<Item = HistoricalEvent<KCoord>> + Clone,
     layers: impl Iterator<Item = u16> + Clone,
     default_layer: u16,
+    input_device: u8,
+    contexts: impl Iterator<Item = u16> + Clone,
+    variables: impl Iterator<Item = i16> + Clone,
 ) -> bool {
     let mut ret = true;
     let mut current_index = 0;
@@ -403,7 +527,10 @@ fn evaluate_boolean(
                 continue;
             }
         }
-        match bool_expr[current_index].opcode_type(bool_expr.get(current_index + 1).copied()) {
+        match bool_expr[current_index].opcode_type(
+            bool_expr.get(current_index + 1).copied(),
+            bool_expr.get(current_index + 2).copied(),
+        ) {
             OpCodeType::BooleanOp(operator) => {
                 let res = stack.push_back(OperatorAndEndIndex {
                     op: current_op,
@@ -466,6 +593,25 @@ fn evaluate_boolean(
                 current_index += 1;
                 ret = default_layer == base_layer;
             }
+            OpCodeType::InputDevice(device) => {
+                // opcode has size 2
+                current_index += 1;
+                ret = input_device == device;
+            }
+            OpCodeType::Context(id) => {
+                // opcode has size 2
+                current_index += 1;
+                ret = contexts.clone().any(|c| c == id);
+            }
+            OpCodeType::Variable(vc) => {
+                // opcode has size 3
+                current_index += 2;
+                ret = variables
+                    .clone()
+                    .nth(usize::from(vc.index))
+                    .map(|v| vc.comparator.compare(v, vc.value))
+                    .unwrap_or(false);
+            }
         };
         if current_op == Not {
             ret = !ret;
@@ -494,6 +640,9 @@ fn evaluate_bool_test(opcodes: &[OpCode], keycodes: impl Iterator<Item = KeyCode
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     )
 }
 
@@ -753,6 +902,9 @@ fn switch_fallthrough() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     );
     assert_eq!(actions.next(), Some(&Action::<()>::KeyCode(KeyCode::A)));
     assert_eq!(actions.next(), Some(&Action::<()>::KeyCode(KeyCode::B)));
@@ -774,6 +926,9 @@ fn switch_break() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     );
     assert_eq!(actions.next(), Some(&Action::<()>::KeyCode(KeyCode::A)));
     assert_eq!(actions.next(), None);
@@ -802,6 +957,9 @@ fn switch_no_actions() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     );
     assert_eq!(actions.next(), None);
 }
@@ -862,6 +1020,9 @@ fn switch_historical_1() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     ));
     assert!(evaluate_boolean(
         opcode_true2.as_slice(),
@@ -871,6 +1032,9 @@ fn switch_historical_1() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     ));
     assert!(!evaluate_boolean(
         opcode_false.as_slice(),
@@ -880,6 +1044,9 @@ fn switch_historical_1() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     ));
     assert!(!evaluate_boolean(
         opcode_false2.as_slice(),
@@ -889,6 +1056,9 @@ fn switch_historical_1() {
         [].iter().copied(),
         [].iter().copied(),
         0,
+        0,
+        [].iter().copied(),
+        [].iter().copied(),
     ));
 }
 
@@ -974,6 +1144,9 @@ fn switch_historical_bools() {
                 [].iter().copied(),
                 [].iter().copied(),
                 0,
+                0,
+                [].iter().copied(),
+                [].iter().copied(),
             ),
             expectation
         );
@@ -1090,6 +1263,9 @@ fn switch_historical_ticks_since() {
                 [].iter().copied(),
                 [].iter().copied(),
                 0,
+                0,
+                [].iter().copied(),
+                [].iter().copied(),
             ),
             expectation
         );
@@ -1324,6 +1500,9 @@ fn switch_inputs() {
                 [].iter().copied(),
                 [].iter().copied(),
                 0,
+                0,
+                [].iter().copied(),
+                [].iter().copied(),
             ),
             expectation
         );
@@ -1392,6 +1571,9 @@ fn switch_historical_inputs() {
                 historical_inputs.iter().copied(),
                 [].iter().copied(),
                 0,
+                0,
+                [].iter().copied(),
+                [].iter().copied(),
             ),
             expectation
         );
@@ -1403,3 +1585,113 @@ fn switch_historical_inputs() {
     test(&opcodes_true_or1, true);
     test(&opcodes_true_or2, true);
 }
+
+#[test]
+fn switch_input_device() {
+    let (op1, op2) = OpCode::new_input_device(2);
+    let opcodes_device = [op1, op2];
+    let (op3, op4) = OpCode::new_input_device(3);
+    let opcodes_or = [OpCode::new_bool(Or, 5), op1, op2, op3, op4];
+    let opcodes_not = [OpCode::new_bool(Not, 3), op1, op2];
+    let test = |opcodes: &[OpCode], input_device: u8, expectation: bool| {
+        assert_eq!(
+            evaluate_boolean(
+                opcodes,
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                0,
+                input_device,
+                [].iter().copied(),
+                [].iter().copied(),
+            ),
+            expectation
+        );
+    };
+    test(&opcodes_device, 2, true);
+    test(&opcodes_device, 0, false);
+    test(&opcodes_device, 3, false);
+    test(&opcodes_or, 3, true);
+    test(&opcodes_or, 1, false);
+    test(&opcodes_not, 2, false);
+    test(&opcodes_not, 0, true);
+}
+
+#[test]
+fn switch_context() {
+    let (op1, op2) = OpCode::new_context(0);
+    let opcodes_ctx = [op1, op2];
+    let (op3, op4) = OpCode::new_context(1);
+    let opcodes_and = [OpCode::new_bool(And, 5), op1, op2, op3, op4];
+    let opcodes_not = [OpCode::new_bool(Not, 3), op1, op2];
+    let test = |opcodes: &[OpCode], contexts: &[u16], expectation: bool| {
+        assert_eq!(
+            evaluate_boolean(
+                opcodes,
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                0,
+                0,
+                contexts.iter().copied(),
+                [].iter().copied(),
+            ),
+            expectation
+        );
+    };
+    test(&opcodes_ctx, &[0], true);
+    test(&opcodes_ctx, &[1, 0], true);
+    test(&opcodes_ctx, &[], false);
+    test(&opcodes_ctx, &[1], false);
+    test(&opcodes_and, &[0, 1], true);
+    test(&opcodes_and, &[1], false);
+    test(&opcodes_not, &[0], false);
+    test(&opcodes_not, &[], true);
+}
+
+#[test]
+fn switch_variable() {
+    let (op1, op2, op3) = OpCode::new_variable(1, Comparator::GreaterThan, 2);
+    let opcodes_gt = [op1, op2, op3];
+    let (op4, op5, op6) = OpCode::new_variable(0, Comparator::Equal, -1);
+    let opcodes_and = [OpCode::new_bool(And, 7), op1, op2, op3, op4, op5, op6];
+    let opcodes_not = [OpCode::new_bool(Not, 4), op4, op5, op6];
+    let test = |opcodes: &[OpCode], variables: &[i16], expectation: bool| {
+        assert_eq!(
+            evaluate_boolean(
+                opcodes,
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                [].iter().copied(),
+                0,
+                0,
+                [].iter().copied(),
+                variables.iter().copied(),
+            ),
+            expectation
+        );
+    };
+    test(&opcodes_gt, &[0, 3], true);
+    test(&opcodes_gt, &[0, 2], false);
+    test(&opcodes_gt, &[5], false);
+    test(&opcodes_and, &[-1, 3], true);
+    test(&opcodes_and, &[0, 3], false);
+    test(&opcodes_not, &[-1], false);
+    test(&opcodes_not, &[1], true);
+    for cmp in [
+        Comparator::Equal,
+        Comparator::NotEqual,
+        Comparator::LessThan,
+        Comparator::GreaterThan,
+        Comparator::LessThanOrEqual,
+        Comparator::GreaterThanOrEqual,
+    ] {
+        assert_eq!(Comparator::from_bits(cmp.to_bits()), cmp);
+    }
+}
@@ -9,7 +9,7 @@ use rustc_hash::FxHashMap;
 use crate::{
     action::Action,
     key_code::KEY_MAX,
-    layout::{Event, Queue, Queued, QueuedAction},
+    layout::{Event, Queue, Queued, QueuedAction, REAL_KEY_ROW},
 };
 
 // Macro to help with this boilerplate.
@@ -43,10 +43,27 @@ pub struct ChordV2<'a, T> {
     pub pending_duration: u16,
     /// The layers on which this chord is disabled.
     pub disabled_layers: &'a [u16],
+    /// If non-empty, the only layers on which this chord is enabled.
+    pub enabled_layers: &'a [u16],
+    /// The minimum number of ticks without any key press before the first participant is
+    /// pressed for this chord to be able to activate.
+    pub min_idle: u16,
     /// When should the action for this chord be released.
     pub release_behaviour: ReleaseBehaviour,
 }
 
+impl<T> ChordV2<'_, T> {
+    /// Returns whether this chord can activate on the given layer.
+    pub fn is_enabled_on_layer(&self, layer: u16) -> bool {
+        !self.disabled_layers.contains(&layer)
+            && (self.enabled_layers.is_empty() || self.enabled_layers.contains(&layer))
+    }
+
+    fn can_activate(&self, layer: u16, prior_idle: u16) -> bool {
+        self.is_enabled_on_layer(layer) && self.min_idle <= prior_idle
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ChordsForKey<'a, T> {
     /// Chords that this key participates in.
@@ -139,6 +156,16 @@ pub struct ChordsV2<'a, T> {
     prev_queue_len: u8,
     /// Virtual coordinate for use in the layout state.
     next_coord: Cell<u16>,
+    /// Number of chords activated since the last call to `take_hits_and_misses_chv2`.
+    hits: u32,
+    /// Number of presses of chord keys that did not activate a chord since the last call to
+    /// `take_hits_and_misses_chv2`.
+    misses: u32,
+    /// Ticks since the most recent press of any real key.
+    ticks_since_last_press: u16,
+    /// Ticks without a key press before the first press currently in the queue.
+    /// Compared against the `min_idle` of chords.
+    prior_idle: u16,
 }
 
 impl<T> std::fmt::Debug for ChordsV2<'_, T> {
@@ -160,6 +187,10 @@ impl<'a, T> ChordsV2<'a, T> {
             prev_active_layer: u16::MAX,
             prev_queue_len: u8::MAX,
             next_coord: Cell::new(KEY_MAX + 1),
+            hits: 0,
+            misses: 0,
+            ticks_since_last_press: u16::MAX,
+            prior_idle: u16::MAX,
         }
     }
 
@@ -171,7 +202,31 @@ impl<'a, T> ChordsV2<'a, T> {
         self.ticks_to_ignore_chord == 0
     }
 
+    /// Coordinates of pressed inputs that have not yet been determined to activate a chord or
+    /// not.
+    pub fn pending_inputs_chv2(&self) -> impl Iterator<Item = u16> + '_ {
+        self.queue
+            .iter()
+            .filter(|q| q.event().is_press())
+            .map(|q| q.event().coord().1)
+    }
+
+    /// Returns the number of chord activations and of chord keys that ended up not activating a
+    /// chord, then resets both to zero.
+    pub fn take_hits_and_misses_chv2(&mut self) -> (u32, u32) {
+        let counts = (self.hits, self.misses);
+        self.hits = 0;
+        self.misses = 0;
+        counts
+    }
+
     pub fn push_back_chv2(&mut self, item: Queued) -> Option<Queued> {
+        if let Event::Press(REAL_KEY_ROW, _) = item.event {
+            if !self.queue.iter().any(|q| q.event().is_press()) {
+                self.prior_idle = self.ticks_since_last_press;
+            }
+            self.ticks_since_last_press = 0;
+        }
         self.queue.push_back(item)
     }
 
@@ -201,6 +256,7 @@ impl<'a, T> ChordsV2<'a, T> {
     pub(crate) fn tick_chv2(&mut self, active_layer: u16) -> SmolQueue {
         let mut q = SmolQueue::new();
         self.queue.iter_mut().for_each(Queued::tick_qd);
+        self.ticks_since_last_press = self.ticks_since_last_press.saturating_add(1);
         let prev_active_chord_len = self.active_chords.len();
         self.active_chords.iter_mut().for_each(tick_ach);
         self.drain_inputs(&mut q, active_layer);
@@ -270,7 +326,7 @@ impl<'a, T> ChordsV2<'a, T> {
             match qd.event {
                 // Only row 0 is real inputs.
                 // Drain other rows (at the time of writing should only be index 1).
-                Event::Press(0, _) | Event::Release(0, _) => true,
+                Event::Press(REAL_KEY_ROW, _) | Event::Release(REAL_KEY_ROW, _) => true,
                 _ => {
                     let overflow = drainq.push_back(*qd);
                     assert!(overflow.is_none(), "oops overflowed drain queue");
@@ -352,6 +408,7 @@ impl<'a, T> ChordsV2<'a, T> {
         let mut timed_out_chord = Option::<(&ChordV2<'a, T>, u8)>::default();
         let mut prev_count = usize::MAX;
         let mut min_timeout;
+        let prior_idle = self.prior_idle;
 
         assert!(!presses.is_empty());
         let since = self.queue.iter().next().unwrap().since;
@@ -375,7 +432,7 @@ impl<'a, T> ChordsV2<'a, T> {
                 possible_chords
                     .chords
                     .iter()
-                    .filter(|pch| !pch.disabled_layers.contains(&active_layer))
+                    .filter(|pch| pch.can_activate(active_layer, prior_idle))
                     .filter(|pch| {
                         if accumulated_presses
                             .iter()
@@ -430,7 +487,7 @@ impl<'a, T> ChordsV2<'a, T> {
                     let completed_chord = possible_chords
                         .chords
                         .iter()
-                        .filter(|pch| !pch.disabled_layers.contains(&active_layer))
+                        .filter(|pch| pch.can_activate(active_layer, prior_idle))
                         .find(
                             // Ensure the two lists have the same set of keys
                             |pch| {
@@ -466,7 +523,7 @@ impl<'a, T> ChordsV2<'a, T> {
                 possible_chords
                     .chords
                     .iter()
-                    .filter(|pch| !pch.disabled_layers.contains(&active_layer))
+                    .filter(|pch| pch.can_activate(active_layer, prior_idle))
                     .find(
                         // Ensure the two lists have the same set of keys
                         |pch| {
@@ -482,7 +539,7 @@ impl<'a, T> ChordsV2<'a, T> {
             } else {
                 chord_candidates
                     .iter()
-                    .filter(|pch| !pch.disabled_layers.contains(&active_layer))
+                    .filter(|pch| pch.can_activate(active_layer, prior_idle))
                     .find(
                         // Ensure the two lists have the same set of keys
                         |pch| {
@@ -511,20 +568,22 @@ impl<'a, T> ChordsV2<'a, T> {
 
         // Clear presses from the queue if they were consumed by a chord.
         if self.active_chords.len() > prev_active_chords_len {
+            self.hits = self.hits.saturating_add(1);
             self.queue.retain(|qd| match qd.event {
                 Event::Press(_, j) => !accumulated_presses.contains(&j),
                 _ => true,
             });
+        } else if self.ticks_to_ignore_chord > 0 {
+            // The ignore timer is always zero at the start of processing presses, so being set
+            // now means that the chord keys were let through without activating a chord.
+            self.misses = self.misses.saturating_add(1);
         }
     }
 
     fn clear_released_chords(&mut self, drainq: &mut SmolQueue) {
         self.active_chords.retain(|ach| {
             if ach.status == Released {
-                let overflow = drainq.push_back(Queued {
-                    event: Event::Release(0, ach.coordinate),
-                    since: 0,
-                });
+                let overflow = drainq.push_back(Queued::new_release(0, ach.coordinate));
                 assert!(overflow.is_none(), "oops overflowed drain queue");
                 false
             } else {
@@ -763,10 +763,10 @@ pub enum KeyCode {
     MWD = 746,
     MWL = 747,
     MWR = 748,
-    K749 = 749,
-    K750 = 750,
-    K751 = 751,
-    K752 = 752,
+    MMU = 749,
+    MMD = 750,
+    MML = 751,
+    MMR = 752,
     K753 = 753,
     K754 = 754,
     K755 = 755,
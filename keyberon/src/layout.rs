@@ -76,10 +76,17 @@ type ActionQueue<'a, T> =
 type Delay = u16;
 pub(crate) type QueuedAction<'a, T> = Option<(KCoord, Delay, &'a Action<'a, T>)>;
 
-const REAL_KEY_ROW: u8 = 0;
+pub(crate) const REAL_KEY_ROW: u8 = 0;
 
 const HISTORICAL_EVENT_LEN: usize = 8;
 const EXTRA_WAITING_LEN: usize = 8;
+const HOLD_TAP_DECISIONS_LEN: usize = 16;
+/// Maximum number of queued events kept in a `HoldTapDecision`.
+pub const HOLD_TAP_DECISION_QUEUED_LEN: usize = 8;
+/// Maximum number of simultaneously active context conditions.
+pub const MAX_ACTIVE_CONTEXTS: usize = 32;
+/// Maximum number of variables available to `Switch` actions.
+pub const MAX_VARIABLES: usize = 256;
 #[test]
 fn extra_waiting_size_constraint() {
     assert!(EXTRA_WAITING_LEN < i8::MAX as usize);
@@ -111,6 +118,24 @@ where
     pub historical_inputs: History<KCoord>,
     pub quick_tap_hold_timeout: bool,
     pub chords_v2: Option<ChordsV2<'a, T>>,
+    /// Layer that key presses from an input device should resolve from, indexed by the device
+    /// identifier. Device identifier 0 is used for events without a known device.
+    pub input_device_layers: &'a [Option<u16>],
+    /// Device identifier of the most recently processed key press.
+    pub input_device: u8,
+    /// Identifiers of the context conditions that currently hold, as determined by the user of
+    /// the layout. Used by the `context` condition of `Switch` actions.
+    pub active_contexts: Vec<u16, MAX_ACTIVE_CONTEXTS>,
+    /// Values of the variables that `Switch` actions can compare, indexed by variable. The user
+    /// of the layout is responsible for changing them.
+    pub variables: Vec<i16, MAX_VARIABLES>,
+    /// Layers that stay active after the keys holding them are released, oldest first. See
+    /// [`Layout::toggle_layer_lock`].
+    pub locked_layers: Vec<u16, MAX_ACTIVE_LAYERS>,
+    /// Recent decisions of `HoldTap` actions, oldest first. The user of the layout may drain
+    /// this to observe how hold-taps were resolved; old decisions are dropped if it is not.
+    pub hold_tap_decisions:
+        ArrayDeque<HoldTapDecision, HOLD_TAP_DECISIONS_LEN, arraydeque::behavior::Wrapping>,
     rpt_multikey_key_buffer: MultiKeyBuffer<'a, T>,
     trans_resolution_behavior_v2: bool,
     delegate_to_first_layer: bool,
@@ -390,6 +415,7 @@ impl<'a, T: 'a> State<'a, T> {
 #[derive(Copy, Clone, Debug)]
 struct TapDanceState<'a, T: 'a> {
     actions: &'a [&'a Action<'a, T>],
+    hold_actions: &'a [&'a Action<'a, T>],
     timeout: u16,
     num_taps: u16,
 }
@@ -422,6 +448,14 @@ impl<T> TapDanceEagerState<'_, T> {
     }
 }
 
+/// The kind of action that a [`WaitingState`] is deciding on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WaitingKind {
+    HoldTap,
+    TapDance,
+    Chord,
+}
+
 #[derive(Debug)]
 enum WaitingConfig<'a, T: 'a + std::fmt::Debug> {
     HoldTap(HoldTapConfig<'a>),
@@ -441,6 +475,15 @@ pub struct WaitingState<'a, T: 'a + std::fmt::Debug> {
     config: WaitingConfig<'a, T>,
     layer_stack: LayerStack,
     prev_queue_len: QueueLen,
+    /// Configured hold timeout, for `HoldTap`.
+    hold_timeout: u16,
+    /// Ticks between the key press and the start of waiting.
+    press_delay: u16,
+    /// Whether the timeout was shortened by waiting on a previous action, due to
+    /// `quick_tap_hold_timeout`.
+    concurrent: bool,
+    /// Why the waiting state was resolved, for `HoldTap`.
+    reason: Option<HoldTapReason>,
 }
 
 /// Actions that can be triggered for a key configured for HoldTap.
@@ -456,7 +499,78 @@ pub enum WaitingAction {
     NoOp,
 }
 
+/// Reasons for a `HoldTap` action to resolve.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HoldTapReason {
+    /// The key was released before the hold timeout.
+    Release,
+    /// The key was released, but the release was queued after the hold timeout expired.
+    ReleaseAfterTimeout,
+    /// The hold timeout expired with the key still held.
+    Timeout,
+    /// The hold timeout expired with the key still held, where the timeout was shortened by the
+    /// time spent waiting on a previous hold-tap, due to `quick_tap_hold_timeout`.
+    ConcurrentTimeout,
+    /// Another key was pressed, for `HoldTapConfig::HoldOnOtherKeyPress`.
+    OtherKeyPress,
+    /// Another key was pressed and released, for `HoldTapConfig::PermissiveHold`.
+    PermissiveHold,
+    /// The function of `HoldTapConfig::Custom` decided without a more specific reason.
+    Custom,
+    /// A key that the function of `HoldTapConfig::Custom` taps on was pressed.
+    TapKeysPress,
+    /// The queue of events waiting on the decision overflowed, which holds right away.
+    QueueOverflow,
+    /// A key of the opposite hand was pressed, for a custom function that decides by hand.
+    OppositeHand,
+    /// A key of the same hand was pressed, for a custom function that decides by hand.
+    SameHand,
+    /// The key was pressed again within the `tap_hold_interval` of the previous press, which
+    /// taps right away.
+    QuickTap,
+    /// Another key was pressed within `require_prior_idle` before the key, which taps right
+    /// away.
+    RequirePriorIdle,
+}
+
+/// Diagnostic record of how a `HoldTap` action was resolved.
+#[derive(Debug, Clone)]
+pub struct HoldTapDecision {
+    pub coord: KCoord,
+    pub outcome: WaitingAction,
+    pub reason: HoldTapReason,
+    /// Ticks from the key press until the decision.
+    pub ticks: u16,
+    /// Configured hold timeout in ticks.
+    pub timeout: u16,
+    /// Events that were queued at the time of the decision, oldest first.
+    pub queued: Vec<Queued, HOLD_TAP_DECISION_QUEUED_LEN>,
+}
+
 impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
+    /// Coordinate of the key that is waiting.
+    pub fn coord(&self) -> KCoord {
+        self.coord
+    }
+
+    /// Number of ticks that the key has been waiting for.
+    pub fn ticks(&self) -> u16 {
+        self.ticks
+    }
+
+    /// Number of ticks remaining until the waiting times out.
+    pub fn timeout(&self) -> u16 {
+        self.timeout
+    }
+
+    pub fn kind(&self) -> WaitingKind {
+        match self.config {
+            WaitingConfig::HoldTap(_) => WaitingKind::HoldTap,
+            WaitingConfig::TapDance(_) => WaitingKind::TapDance,
+            WaitingConfig::Chord(_) => WaitingKind::Chord,
+        }
+    }
+
     fn tick_wt(
         &mut self,
         queued: &mut Queue,
@@ -468,14 +582,21 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
         let (ret, cfg_change) = match self.config {
             WaitingConfig::HoldTap(htc) => (self.handle_hold_tap(htc, queued), None),
             WaitingConfig::TapDance(ref tds) => {
-                let (ret, num_taps) =
-                    self.handle_tap_dance(tds.num_taps, tds.actions.len(), queued);
+                let (ret, num_taps) = self.handle_tap_dance(
+                    tds.num_taps,
+                    tds.actions.len(),
+                    !tds.hold_actions.is_empty(),
+                    queued,
+                );
                 self.prev_queue_len = queued.len() as u8;
                 // Due to ownership issues, handle_tap_dance can't contain all of the necessary
                 // logic.
                 if ret.is_some() {
                     let idx = core::cmp::min(num_taps.into(), tds.actions.len()).saturating_sub(1);
                     self.tap = tds.actions[idx];
+                    if let Some(hold) = tds.hold_actions.get(idx) {
+                        self.hold = hold;
+                    }
                 }
                 if num_taps > tds.num_taps {
                     self.timeout = tds.timeout;
@@ -502,6 +623,16 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
     }
 
     fn handle_hold_tap(&mut self, cfg: HoldTapConfig, queued: &Queue) -> Option<WaitingAction> {
+        let (action, reason) = self.decide_hold_tap(cfg, queued)?;
+        self.reason = Some(reason);
+        Some(action)
+    }
+
+    fn decide_hold_tap(
+        &mut self,
+        cfg: HoldTapConfig,
+        queued: &Queue,
+    ) -> Option<(WaitingAction, HoldTapReason)> {
         if queued.len() as u8 == self.prev_queue_len && self.timeout > 0 {
             // Fast path: nothing has changed since last tick and we haven't timed out yet.
             return None;
@@ -512,7 +643,7 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
             HoldTapConfig::Default => (),
             HoldTapConfig::HoldOnOtherKeyPress => {
                 if queued.iter().any(|s| s.event.is_press()) {
-                    return Some(WaitingAction::Hold);
+                    return Some((WaitingAction::Hold, HoldTapReason::OtherKeyPress));
                 }
             }
             HoldTapConfig::PermissiveHold => {
@@ -522,15 +653,18 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
                         let (i, j) = q.event.coord();
                         let target = Event::Release(i, j);
                         if queued.clone().any(|q| q.event == target) {
-                            return Some(WaitingAction::Hold);
+                            return Some((WaitingAction::Hold, HoldTapReason::PermissiveHold));
                         }
                     }
                 }
             }
             HoldTapConfig::Custom(func) => {
-                let (waiting_action, local_skip) = (func)(QueuedIter(queued.iter()));
-                if waiting_action.is_some() {
-                    return waiting_action;
+                let (waiting_action, local_skip) = (func)(QueuedIter {
+                    iter: queued.iter(),
+                    waiting_coord: self.coord,
+                });
+                if let Some(decision) = waiting_action {
+                    return Some(decision);
                 }
                 skip_timeout = local_skip;
             }
@@ -540,12 +674,16 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
             .find(|s| self.is_corresponding_release(&s.event))
         {
             if self.timeout > self.delay.saturating_sub(since) {
-                Some(WaitingAction::Tap)
+                Some((WaitingAction::Tap, HoldTapReason::Release))
             } else {
-                Some(WaitingAction::Timeout)
+                Some((WaitingAction::Timeout, HoldTapReason::ReleaseAfterTimeout))
             }
         } else if self.timeout == 0 && (!skip_timeout) {
-            Some(WaitingAction::Timeout)
+            if self.concurrent {
+                Some((WaitingAction::Timeout, HoldTapReason::ConcurrentTimeout))
+            } else {
+                Some((WaitingAction::Timeout, HoldTapReason::Timeout))
+            }
         } else {
             None
         }
@@ -555,6 +693,7 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
         &self,
         num_taps: u16,
         max_taps: usize,
+        has_hold_actions: bool,
         queued: &mut Queue,
     ) -> (Option<WaitingAction>, u16) {
         if queued.len() as u8 == self.prev_queue_len && self.timeout > 0 {
@@ -580,9 +719,25 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
                 do_retain
             });
         };
+        // With hold actions, the final tap only resolves to a tap once its key is released.
+        let is_held = || {
+            let presses = queued
+                .iter()
+                .filter(|s| self.is_corresponding_press(&s.event))
+                .count();
+            let releases = queued
+                .iter()
+                .filter(|s| self.is_corresponding_release(&s.event))
+                .count();
+            has_hold_actions && presses + 1 > releases
+        };
         if self.timeout == 0 {
+            let action = match is_held() {
+                true => WaitingAction::Hold,
+                false => WaitingAction::Tap,
+            };
             evict_same_coord_events(num_taps, queued);
-            return (Some(WaitingAction::Tap), num_taps);
+            return (Some(action), num_taps);
         }
         // Get the number of sequential taps for this tap-dance key. If a different key was
         // pressed, activate a tap-dance action.
@@ -596,6 +751,9 @@ impl<'a, T: std::fmt::Debug> WaitingState<'a, T> {
             }
         }) {
             Ok(num_taps) if usize::from(num_taps) >= max_taps => {
+                if is_held() {
+                    return (None, num_taps);
+                }
                 evict_same_coord_events(num_taps, queued);
                 (Some(WaitingAction::Tap), num_taps)
             }
@@ -875,7 +1033,7 @@ pub const MAX_ACTIVE_LAYERS: usize = 12;
 /// Because we only need a read-only stack and efficient iteration over contained
 /// items, LayerStack items are in reverse order over usual back-to-front order
 /// of items in array-based stack implementations.
-type LayerStack = Vec<u16, MAX_ACTIVE_LAYERS>;
+pub type LayerStack = Vec<u16, MAX_ACTIVE_LAYERS>;
 
 /// Contains the state of one shot keys that are currently active.
 pub struct OneShotState {
@@ -997,15 +1155,25 @@ impl OneShotState {
 ///
 /// Events can be retrieved by iterating over this struct and calling [Queued::event].
 #[derive(Clone)]
-pub struct QueuedIter<'a>(arraydeque::Iter<'a, Queued>);
+pub struct QueuedIter<'a> {
+    iter: arraydeque::Iter<'a, Queued>,
+    waiting_coord: KCoord,
+}
+
+impl QueuedIter<'_> {
+    /// Returns the coordinates of the key whose action is waiting for the events.
+    pub fn waiting_coord(&self) -> KCoord {
+        self.waiting_coord
+    }
+}
 
 impl<'a> Iterator for QueuedIter<'a> {
     type Item = &'a Queued;
     fn next(&mut self) -> Option<Self::Item> {
-        self.0.next()
+        self.iter.next()
     }
     fn size_hint(&self) -> (usize, Option<usize>) {
-        self.0.size_hint()
+        self.iter.size_hint()
     }
 }
 
@@ -1014,10 +1182,15 @@ impl<'a> Iterator for QueuedIter<'a> {
 pub struct Queued {
     pub(crate) event: Event,
     pub(crate) since: u16,
+    pub(crate) device: u8,
 }
 impl From<Event> for Queued {
     fn from(event: Event) -> Self {
-        Queued { event, since: 0 }
+        Queued {
+            event,
+            since: 0,
+            device: 0,
+        }
     }
 }
 impl Queued {
@@ -1025,6 +1198,7 @@ impl Queued {
         Self {
             since: 0,
             event: Event::Press(i, j),
+            device: 0,
         }
     }
 
@@ -1032,6 +1206,7 @@ impl Queued {
         Self {
             since: 0,
             event: Event::Release(i, j),
+            device: 0,
         }
     }
 
@@ -1043,6 +1218,16 @@ impl Queued {
     pub fn event(&self) -> Event {
         self.event
     }
+
+    /// Get the number of ticks that the event has been queued for.
+    pub fn since(&self) -> u16 {
+        self.since
+    }
+
+    /// Get the identifier of the input device that generated the event.
+    pub fn device(&self) -> u8 {
+        self.device
+    }
 }
 
 #[derive(Default)]
@@ -1095,6 +1280,12 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
             historical_inputs: History::new(),
             rpt_multikey_key_buffer: unsafe { MultiKeyBuffer::new() },
             quick_tap_hold_timeout: false,
+            input_device_layers: &[],
+            input_device: 0,
+            active_contexts: Vec::new(),
+            variables: Vec::new(),
+            locked_layers: Vec::new(),
+            hold_tap_decisions: ArrayDeque::new(),
             trans_resolution_behavior_v2: true,
             delegate_to_first_layer: false,
             chords_v2: None,
@@ -1130,7 +1321,9 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                 WaitingConfig::HoldTap(..) | WaitingConfig::Chord(_) => w.delay + w.ticks,
                 WaitingConfig::TapDance(_) => 0,
             };
+            let decision = self.hold_tap_decision(w, WaitingAction::Hold);
             let layer_stack = w.layer_stack.clone();
+            self.hold_tap_decisions.extend(decision);
             if idx < 0 {
                 self.waiting = None;
             } else {
@@ -1161,7 +1354,9 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                 WaitingConfig::HoldTap(..) | WaitingConfig::Chord(_) => w.delay + w.ticks,
                 WaitingConfig::TapDance(_) => 0,
             };
+            let decision = self.hold_tap_decision(w, WaitingAction::Tap);
             let layer_stack = w.layer_stack.clone();
+            self.hold_tap_decisions.extend(decision);
             if idx < 0 {
                 self.waiting = None;
             } else {
@@ -1242,7 +1437,9 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                 WaitingConfig::HoldTap(..) | WaitingConfig::Chord(_) => w.delay + w.ticks,
                 WaitingConfig::TapDance(_) => 0,
             };
+            let decision = self.hold_tap_decision(w, WaitingAction::Timeout);
             let layer_stack = w.layer_stack.clone();
+            self.hold_tap_decisions.extend(decision);
             if idx < 0 {
                 self.waiting = None;
             } else {
@@ -1262,6 +1459,37 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
             CustomEvent::NoEvent
         }
     }
+    fn hold_tap_decision(
+        &self,
+        w: &WaitingState<'a, T>,
+        outcome: WaitingAction,
+    ) -> Option<HoldTapDecision> {
+        let (WaitingConfig::HoldTap(..), Some(reason)) = (&w.config, w.reason) else {
+            return None;
+        };
+        Some(HoldTapDecision {
+            coord: w.coord,
+            outcome,
+            reason,
+            ticks: w.press_delay.saturating_add(w.ticks),
+            timeout: w.hold_timeout,
+            queued: self
+                .queue
+                .iter()
+                .copied()
+                .take(HOLD_TAP_DECISION_QUEUED_LEN)
+                .collect(),
+        })
+    }
+    /// Ticks between the previous real key press and a key press that was queued `delay` ticks
+    /// ago, if the previous press is still in the history.
+    fn prior_press_idle_ticks(&self, delay: u16) -> Option<u16> {
+        self.historical_inputs
+            .iter_hevents()
+            .filter(|hevent| hevent.event.0 == REAL_KEY_ROW)
+            .find(|hevent| hevent.ticks_since_occurrence > delay)
+            .map(|hevent| hevent.ticks_since_occurrence - delay)
+    }
     fn drop_waiting(&mut self) -> CustomEvent<'a, T> {
         self.waiting = None;
         CustomEvent::NoEvent
@@ -1309,10 +1537,7 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
         let mut custom = CustomEvent::NoEvent;
         if let Some(released_keys) = self.oneshot.tick_osh() {
             for key in released_keys.iter() {
-                custom.update(self.dequeue(Queued {
-                    event: Event::Release(key.0, key.1),
-                    since: 0,
-                }));
+                custom.update(self.dequeue(Queued::new_release(key.0, key.1)));
             }
         }
 
@@ -1397,12 +1622,10 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                             self.oneshot.handle_release((0, 0));
                             self.states.retain(|s| s.seq_release(keycode).is_some());
                         }
-                        Some(SequenceEvent::Delay { duration }) => {
-                            // Setup a delay that will be decremented once per tick until 0
-                            if duration > 0 {
-                                // -1 to start since this tick counts
-                                seq.delay = duration - 1;
-                            }
+                        // Setup a delay that will be decremented once per tick until 0
+                        Some(SequenceEvent::Delay { duration }) if duration > 0 => {
+                            // -1 to start since this tick counts
+                            seq.delay = duration - 1;
                         }
                         Some(SequenceEvent::Custom(custom)) => {
                             let _ = self.states.push(State::SeqCustomPending(custom));
@@ -1509,7 +1732,8 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
             }
 
             Press(i, j) => {
-                let mut layer_stack = self.trans_resolution_layer_order().into_iter();
+                self.input_device = queue.device;
+                let mut layer_stack = self.input_layer_order(queue.device).into_iter();
                 if let Some(tde) = self.tap_dance_eager {
                     if (i, j) == self.last_press_tracker.coord && !tde.is_expired() {
                         let custom = self.do_action(
@@ -1539,15 +1763,32 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
     }
     /// Register a key event.
     pub fn event(&mut self, event: Event) {
+        self.event_from_device(event, 0)
+    }
+    /// Register a key event that originated from the input device with the given identifier.
+    pub fn event_from_device(&mut self, event: Event, device: u8) {
         if let Event::Press(x, y) = event {
             self.historical_inputs.push_front((x, y));
         }
+        let queued = Queued {
+            event,
+            since: 0,
+            device,
+        };
         if let Some(overflow) = if let Some(ch) = self.chords_v2.as_mut() {
-            ch.push_back_chv2(event.into())
+            ch.push_back_chv2(queued)
         } else {
-            self.queue.push_back(event.into())
+            self.queue.push_back(queued)
         } {
             for i in -1..(EXTRA_WAITING_LEN as i8) {
+                let waiting = if i < 0 {
+                    self.waiting.as_mut()
+                } else {
+                    self.extra_waiting.get_mut(i as usize)
+                };
+                if let Some(w) = waiting {
+                    w.reason = Some(HoldTapReason::QueueOverflow);
+                }
                 self.waiting_into_hold(i);
             }
             self.dequeue(overflow);
@@ -1653,12 +1894,17 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                 timeout_action,
                 config,
                 tap_hold_interval,
+                require_prior_idle,
             }) => {
                 let mut custom = CustomEvent::NoEvent;
-                if *tap_hold_interval == 0
-                    || coord != self.last_press_tracker.coord
-                    || self.last_press_tracker.tap_hold_timeout == 0
-                {
+                let typing = *require_prior_idle > 0
+                    && self
+                        .prior_press_idle_ticks(delay)
+                        .is_some_and(|idle| idle < *require_prior_idle);
+                let quick_tap = *tap_hold_interval != 0
+                    && coord == self.last_press_tracker.coord
+                    && self.last_press_tracker.tap_hold_timeout != 0;
+                if !typing && !quick_tap {
                     let waiting: WaitingState<T> = WaitingState {
                         coord,
                         timeout: if self.quick_tap_hold_timeout {
@@ -1679,6 +1925,12 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                         config: WaitingConfig::HoldTap(*config),
                         layer_stack: layer_stack.collect(),
                         prev_queue_len: QueueLen::MAX,
+                        hold_timeout: *timeout,
+                        press_delay: delay,
+                        // A press that nothing held back is processed one tick after it is
+                        // queued; any longer was spent waiting on a previous action.
+                        concurrent: self.quick_tap_hold_timeout && delay > 1,
+                        reason: None,
                     };
                     if self.waiting.is_some() {
                         self.extra_waiting.push_back(waiting);
@@ -1687,7 +1939,27 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                     }
                     self.last_press_tracker.tap_hold_timeout = *tap_hold_interval;
                 } else {
-                    self.last_press_tracker.tap_hold_timeout = 0;
+                    let reason = if quick_tap {
+                        self.last_press_tracker.tap_hold_timeout = 0;
+                        HoldTapReason::QuickTap
+                    } else {
+                        self.last_press_tracker.tap_hold_timeout = *tap_hold_interval;
+                        HoldTapReason::RequirePriorIdle
+                    };
+                    let decision = HoldTapDecision {
+                        coord,
+                        outcome: WaitingAction::Tap,
+                        reason,
+                        ticks: 0,
+                        timeout: *timeout,
+                        queued: self
+                            .queue
+                            .iter()
+                            .copied()
+                            .take(HOLD_TAP_DECISION_QUEUED_LEN)
+                            .collect(),
+                    };
+                    self.hold_tap_decisions.push_back(decision);
                     custom.update(self.do_action(tap, coord, delay, is_oneshot, layer_stack));
                 }
                 // Need to set tap_hold_tracker coord AFTER the checks.
@@ -1718,7 +1990,7 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
             &TapDance(td) => {
                 self.last_press_tracker.update_coord(coord);
                 match td.config {
-                    TapDanceConfig::Lazy => {
+                    TapDanceConfig::Lazy | TapDanceConfig::LazyHold => {
                         self.waiting = Some(WaitingState {
                             coord,
                             timeout: td.timeout,
@@ -1729,11 +2001,16 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                             timeout_action: &Action::NoOp,
                             config: WaitingConfig::TapDance(TapDanceState {
                                 actions: td.actions,
+                                hold_actions: td.hold_actions,
                                 timeout: td.timeout,
                                 num_taps: 1,
                             }),
                             layer_stack: layer_stack.collect(),
                             prev_queue_len: QueueLen::MAX,
+                            hold_timeout: 0,
+                            press_delay: delay,
+                            concurrent: false,
+                            reason: None,
                         });
                     }
                     TapDanceConfig::Eager => {
@@ -1776,6 +2053,10 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                     config: WaitingConfig::Chord(chords),
                     layer_stack: layer_stack.collect(),
                     prev_queue_len: QueueLen::MAX,
+                    hold_timeout: 0,
+                    press_delay: delay,
+                    concurrent: false,
+                    reason: None,
                 });
             }
             &KeyCode(keycode) => {
@@ -1934,6 +2215,7 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
             }
             DefaultLayer(value) => {
                 self.last_press_tracker.update_coord(coord);
+                self.locked_layers.clear();
                 self.set_default_layer(*value);
                 if !is_oneshot {
                     self.oneshot
@@ -1993,6 +2275,9 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
                     // Note on truncating cast: I expect default layer to be in range by other
                     // assertions.
                     self.default_layer as u16,
+                    self.input_device,
+                    self.active_contexts.iter().copied(),
+                    self.variables.iter().copied(),
                 ) {
                     action_queue.push_back(Some((coord, 0, ac)));
                 }
@@ -2008,18 +2293,43 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
 
     /// Obtain the index of the current active layer
     pub fn current_layer(&self) -> usize {
-        self.states
-            .iter()
-            .rev()
-            .find_map(State::get_layer)
+        self.active_held_layers()
+            .next()
+            .map(usize::from)
             .unwrap_or(self.default_layer)
     }
 
+    /// Returns the layers that are held by keys, most recent first, followed by the locked layers
+    /// that are not also held by keys.
     pub fn active_held_layers(&self) -> impl Iterator<Item = u16> + Clone + '_ {
-        self.states
+        let key_held_layers = self
+            .states
             .iter()
             .filter_map(|s| State::get_layer(s).map(|l| l as u16))
-            .rev()
+            .rev();
+        key_held_layers.clone().chain(
+            self.locked_layers
+                .iter()
+                .rev()
+                .copied()
+                .filter(move |l| !key_held_layers.clone().any(|held| held == *l)),
+        )
+    }
+
+    /// Locks the layers that are currently held by keys so that they stay active after the keys
+    /// are released. If every held layer is already locked, e.g. because no layer is held, all
+    /// layer locks are released instead.
+    pub fn toggle_layer_lock(&mut self) {
+        let mut locked_any = false;
+        for layer in self.states.iter().filter_map(State::get_layer) {
+            let layer = layer as u16;
+            if !self.locked_layers.contains(&layer) && self.locked_layers.push(layer).is_ok() {
+                locked_any = true;
+            }
+        }
+        if !locked_any {
+            self.locked_layers.clear();
+        }
     }
 
     /// Returns a list indices of layers that should be used for [`Action::Trans`] resolution.
@@ -2042,6 +2352,43 @@ impl<'a, const C: usize, const R: usize, T: 'a + Copy + std::fmt::Debug> Layout<
         }
     }
 
+    /// Returns the layer order used for resolving a key press from the given input device. If the
+    /// device is bound to a layer, that layer takes the place of the default layer for the
+    /// device's key presses, so held layers still take priority over it. The device layer only
+    /// applies while the default layer is the first layer, so that switching the default layer
+    /// also switches it for the device.
+    pub fn input_layer_order(&self, device: u8) -> LayerStack {
+        let layer_order = self.trans_resolution_layer_order();
+        if self.default_layer != 0 {
+            return layer_order;
+        }
+        let Some(device_layer) = self
+            .input_device_layers
+            .get(usize::from(device))
+            .copied()
+            .flatten()
+        else {
+            return layer_order;
+        };
+        let held_layer_count = self.active_held_layers().count();
+        let device_layer_pos = if self.trans_resolution_behavior_v2 {
+            held_layer_count
+        } else {
+            held_layer_count.min(1)
+        };
+        let mut v = LayerStack::new();
+        for (i, layer) in layer_order.iter().copied().enumerate() {
+            if i == device_layer_pos {
+                let _ = v.push(device_layer);
+            }
+            let _ = v.push(layer);
+        }
+        if device_layer_pos >= layer_order.len() {
+            let _ = v.push(device_layer);
+        }
+        v
+    }
+
     /// Sets the default layer for the layout
     pub fn set_default_layer(&mut self, value: usize) {
         if value < self.layers.len() {
@@ -2055,8 +2402,8 @@ mod test {
     extern crate std;
     use super::{Event::*, Layout, *};
     use crate::action::Action::*;
-    use crate::action::HoldTapConfig;
     use crate::action::{k, l};
+    use crate::action::{CustomHoldTapDecision, HoldTapConfig};
     use crate::key_code::KeyCode;
     use crate::key_code::KeyCode::*;
     use std::collections::BTreeSet;
@@ -2079,6 +2426,7 @@ mod test {
                     timeout_action: k(RShift),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 0,
+                    require_prior_idle: 0,
                 }),
                 HoldTap(&HoldTapAction {
                     timeout: 200,
@@ -2087,6 +2435,7 @@ mod test {
                     tap: k(Enter),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 0,
+                    require_prior_idle: 0,
                 }),
             ]],
             [[Trans, MultipleKeyCodes(&[LCtrl, Enter].as_slice())]],
@@ -2131,6 +2480,7 @@ mod test {
                     timeout_action: l(1),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 0,
+                    require_prior_idle: 0,
                 }),
                 HoldTap(&HoldTapAction {
                     timeout: 200,
@@ -2139,6 +2489,7 @@ mod test {
                     tap: k(Enter),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 0,
+                    require_prior_idle: 0,
                 }),
             ]],
             [[Trans, MultipleKeyCodes(&[LCtrl, Enter].as_slice())]],
@@ -2182,6 +2533,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 20,
@@ -2190,6 +2542,7 @@ mod test {
                 tap: k(Enter),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
         ]]];
         let mut layout = Layout::new(LAYERS);
@@ -2231,6 +2584,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::HoldOnOtherKeyPress,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             k(Enter),
         ]]];
@@ -2279,6 +2633,129 @@ mod test {
         assert_keys(&[], layout.keycodes());
     }
 
+    #[test]
+    fn hold_tap_decisions() {
+        static LAYERS: Layers<2, 1> = &[[[
+            HoldTap(&HoldTapAction {
+                timeout: 200,
+                hold: k(LAlt),
+                timeout_action: k(LAlt),
+                tap: k(Space),
+                config: HoldTapConfig::HoldOnOtherKeyPress,
+                tap_hold_interval: 0,
+                require_prior_idle: 0,
+            }),
+            k(Enter),
+        ]]];
+        let mut layout = Layout::new(LAYERS);
+
+        layout.event(Press(0, 0));
+        for _ in 0..10 {
+            layout.tick();
+        }
+        layout.event(Press(0, 1));
+        layout.tick();
+        assert_keys(&[LAlt], layout.keycodes());
+        let decision = layout.hold_tap_decisions.pop_front().unwrap();
+        assert_eq!((0, 0), decision.coord);
+        assert_eq!(WaitingAction::Hold, decision.outcome);
+        assert_eq!(HoldTapReason::OtherKeyPress, decision.reason);
+        assert_eq!(11, decision.ticks);
+        assert_eq!(200, decision.timeout);
+        assert_eq!(1, decision.queued.len());
+        assert_eq!(Press(0, 1), decision.queued[0].event());
+        layout.event(Release(0, 1));
+        layout.event(Release(0, 0));
+        for _ in 0..3 {
+            layout.tick();
+        }
+
+        layout.event(Press(0, 0));
+        layout.tick();
+        layout.event(Release(0, 0));
+        layout.tick();
+        assert_keys(&[Space], layout.keycodes());
+        let decision = layout.hold_tap_decisions.pop_front().unwrap();
+        assert_eq!(WaitingAction::Tap, decision.outcome);
+        assert_eq!(HoldTapReason::Release, decision.reason);
+
+        layout.tick();
+        layout.event(Press(0, 0));
+        for _ in 0..201 {
+            layout.tick();
+        }
+        assert_keys(&[LAlt], layout.keycodes());
+        let decision = layout.hold_tap_decisions.pop_front().unwrap();
+        assert_eq!(WaitingAction::Timeout, decision.outcome);
+        assert_eq!(HoldTapReason::Timeout, decision.reason);
+        assert!(layout.hold_tap_decisions.is_empty());
+        layout.event(Release(0, 0));
+        for _ in 0..3 {
+            layout.tick();
+        }
+
+        layout.event(Press(0, 0));
+        layout.tick();
+        for _ in 0..=QUEUE_SIZE {
+            layout.event(Release(0, 1));
+        }
+        let decision = layout.hold_tap_decisions.pop_front().unwrap();
+        assert_eq!(WaitingAction::Hold, decision.outcome);
+        assert_eq!(HoldTapReason::QueueOverflow, decision.reason);
+    }
+
+    #[test]
+    fn require_prior_idle() {
+        static LAYERS: Layers<2, 1> = &[[[
+            HoldTap(&HoldTapAction {
+                timeout: 200,
+                hold: k(LAlt),
+                timeout_action: k(LAlt),
+                tap: k(Space),
+                config: HoldTapConfig::Default,
+                tap_hold_interval: 0,
+                require_prior_idle: 100,
+            }),
+            k(Enter),
+        ]]];
+        let mut layout = Layout::new(LAYERS);
+
+        // Pressed soon after another key: tap right away, even when held.
+        layout.event(Press(0, 1));
+        layout.tick();
+        layout.event(Release(0, 1));
+        layout.tick();
+        for _ in 0..50 {
+            layout.tick();
+        }
+        layout.event(Press(0, 0));
+        layout.tick();
+        assert_keys(&[Space], layout.keycodes());
+        let decision = layout.hold_tap_decisions.pop_front().unwrap();
+        assert_eq!(WaitingAction::Tap, decision.outcome);
+        assert_eq!(HoldTapReason::RequirePriorIdle, decision.reason);
+        for _ in 0..250 {
+            layout.tick();
+        }
+        assert_keys(&[Space], layout.keycodes());
+        layout.event(Release(0, 0));
+        layout.tick();
+        assert_keys(&[], layout.keycodes());
+
+        // Pressed after being idle: decide as usual.
+        for _ in 0..100 {
+            layout.tick();
+        }
+        layout.event(Press(0, 0));
+        for _ in 0..201 {
+            layout.tick();
+        }
+        assert_keys(&[LAlt], layout.keycodes());
+        layout.event(Release(0, 0));
+        layout.tick();
+        assert_keys(&[], layout.keycodes());
+    }
+
     #[test]
     fn permissive_hold() {
         static LAYERS: Layers<2, 1> = &[[[
@@ -2289,6 +2766,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::PermissiveHold,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             k(Enter),
         ]]];
@@ -2329,6 +2807,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2337,6 +2816,7 @@ mod test {
                 tap: k(A),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2345,6 +2825,7 @@ mod test {
                 tap: k(A),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
         ]]];
         let mut layout = Layout::new(LAYERS);
@@ -2489,16 +2970,16 @@ mod test {
 
     #[test]
     fn custom_handler() {
-        fn always_tap(_: QueuedIter) -> (Option<WaitingAction>, bool) {
-            (Some(WaitingAction::Tap), false)
+        fn always_tap(_: QueuedIter) -> CustomHoldTapDecision {
+            (Some((WaitingAction::Tap, HoldTapReason::Custom)), false)
         }
-        fn always_hold(_: QueuedIter) -> (Option<WaitingAction>, bool) {
-            (Some(WaitingAction::Hold), false)
+        fn always_hold(_: QueuedIter) -> CustomHoldTapDecision {
+            (Some((WaitingAction::Hold, HoldTapReason::Custom)), false)
         }
-        fn always_nop(_: QueuedIter) -> (Option<WaitingAction>, bool) {
-            (Some(WaitingAction::NoOp), false)
+        fn always_nop(_: QueuedIter) -> CustomHoldTapDecision {
+            (Some((WaitingAction::NoOp, HoldTapReason::Custom)), false)
         }
-        fn always_none(_: QueuedIter) -> (Option<WaitingAction>, bool) {
+        fn always_none(_: QueuedIter) -> CustomHoldTapDecision {
             (None, false)
         }
         static LAYERS: Layers<4, 1> = &[[[
@@ -2509,6 +2990,7 @@ mod test {
                 tap: k(Kb0),
                 config: HoldTapConfig::Custom(&always_tap),
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2517,6 +2999,7 @@ mod test {
                 tap: k(Kb2),
                 config: HoldTapConfig::Custom(&always_hold),
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2525,6 +3008,7 @@ mod test {
                 tap: k(Kb4),
                 config: HoldTapConfig::Custom(&always_nop),
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2533,6 +3017,7 @@ mod test {
                 tap: k(Kb6),
                 config: HoldTapConfig::Custom(&always_none),
                 tap_hold_interval: 0,
+                require_prior_idle: 0,
             }),
         ]]];
         let mut layout = Layout::new(LAYERS);
@@ -2606,6 +3091,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 200,
+                require_prior_idle: 0,
             }),
             k(Enter),
         ]]];
@@ -2661,6 +3147,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 200,
+                require_prior_idle: 0,
             }),
             k(Enter),
             HoldTap(&HoldTapAction {
@@ -2670,6 +3157,7 @@ mod test {
                 tap: k(Enter),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 200,
+                require_prior_idle: 0,
             }),
         ]]];
         let mut layout = Layout::new(LAYERS);
@@ -2783,6 +3271,7 @@ mod test {
             tap: k(Space),
             config: HoldTapConfig::Default,
             tap_hold_interval: 200,
+            require_prior_idle: 0,
         })]]];
         let mut layout = Layout::new(LAYERS);
 
@@ -2826,6 +3315,7 @@ mod test {
                 tap: k(Space),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 200,
+                require_prior_idle: 0,
             }),
             HoldTap(&HoldTapAction {
                 timeout: 200,
@@ -2834,6 +3324,7 @@ mod test {
                 tap: k(Enter),
                 config: HoldTapConfig::Default,
                 tap_hold_interval: 200,
+                require_prior_idle: 0,
             }),
         ]]];
         let mut layout = Layout::new(LAYERS);
@@ -3351,6 +3842,7 @@ mod test {
                     tap: k(Space),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 0,
+                    require_prior_idle: 0,
                 }),
                 NoOp,
             ]],
@@ -3416,8 +3908,10 @@ mod test {
                             tap: k(Space),
                             config: HoldTapConfig::Default,
                             tap_hold_interval: 0,
+                            require_prior_idle: 0,
                         }),
                     ],
+                    hold_actions: &[],
                     config: TapDanceConfig::Lazy,
                 }),
                 k(A),
@@ -3536,6 +4030,77 @@ mod test {
         assert_keys(&[], layout.keycodes());
     }
 
+    #[test]
+    fn tap_dance_lazy_hold() {
+        static LAYERS: Layers<2, 1> = &[[[
+            TapDance(&crate::action::TapDance {
+                timeout: 100,
+                actions: &[&k(A), &k(B)],
+                hold_actions: &[&k(LCtrl), &k(LAlt)],
+                config: TapDanceConfig::LazyHold,
+            }),
+            k(C),
+        ]]];
+        let mut layout = Layout::new(LAYERS);
+
+        // Test: tap once, timeout
+        layout.event(Press(0, 0));
+        layout.event(Release(0, 0));
+        for _ in 0..100 {
+            assert_eq!(CustomEvent::NoEvent, layout.tick());
+            assert_keys(&[], layout.keycodes());
+        }
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[A], layout.keycodes());
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[], layout.keycodes());
+
+        // Test: hold past the timeout
+        layout.event(Press(0, 0));
+        for _ in 0..100 {
+            assert_eq!(CustomEvent::NoEvent, layout.tick());
+            assert_keys(&[], layout.keycodes());
+        }
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[LCtrl], layout.keycodes());
+        layout.event(Press(0, 1));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[LCtrl, C], layout.keycodes());
+        layout.event(Release(0, 1));
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[], layout.keycodes());
+
+        // Test: tap then hold, max taps reached
+        layout.event(Press(0, 0));
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event(Press(0, 0));
+        for _ in 0..100 {
+            assert_eq!(CustomEvent::NoEvent, layout.tick());
+            assert_keys(&[], layout.keycodes());
+        }
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[LAlt], layout.keycodes());
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[], layout.keycodes());
+
+        // Test: tap twice, max taps reached, resolves on release
+        layout.event(Press(0, 0));
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event(Press(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[], layout.keycodes());
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[B], layout.keycodes());
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[], layout.keycodes());
+    }
+
     #[test]
     fn tap_dance_eager() {
         static LAYERS: Layers<2, 2> = &[[
@@ -3543,6 +4108,7 @@ mod test {
                 TapDance(&crate::action::TapDance {
                     timeout: 100,
                     actions: &[&k(Kb1), &k(Kb2), &k(Kb3)],
+                    hold_actions: &[],
                     config: TapDanceConfig::Eager,
                 }),
                 k(A),
@@ -3856,6 +4422,7 @@ mod test {
                         tap: k(Kb1),
                         config: HoldTapConfig::Default,
                         tap_hold_interval: 0,
+                        require_prior_idle: 0,
                     }),
                 ),
                 (
@@ -3867,6 +4434,7 @@ mod test {
                         tap: k(Kb2),
                         config: HoldTapConfig::Default,
                         tap_hold_interval: 0,
+                        require_prior_idle: 0,
                     }),
                 ),
             ],
@@ -4083,6 +4651,102 @@ mod test {
         assert_keys(&[], layout.keycodes());
     }
 
+    #[test]
+    fn test_layer_lock() {
+        static LAYERS: Layers<3, 1> =
+            &[[[Layer(1), k(A), DefaultLayer(0)]], [[Trans, k(B), Trans]]];
+        let mut layout = Layout::new(LAYERS);
+
+        // no layer is held so there is nothing to lock
+        layout.toggle_layer_lock();
+        assert!(layout.locked_layers.is_empty());
+        // lock the held layer, which stays active after its key is released
+        layout.event(Press(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.toggle_layer_lock();
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_eq!(layout.current_layer(), 1);
+        // holding the locked layer again does not list it twice
+        layout.event(Press(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_eq!(
+            layout.active_held_layers().collect::<std::vec::Vec<_>>(),
+            [1]
+        );
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        // toggling without a newly held layer unlocks
+        layout.toggle_layer_lock();
+        assert_eq!(layout.current_layer(), 0);
+        // switching the default layer releases the locks
+        layout.event(Press(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.toggle_layer_lock();
+        layout.event(Release(0, 0));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event(Press(0, 2));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_eq!(layout.current_layer(), 0);
+        layout.event(Release(0, 2));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event(Press(0, 1));
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[A], layout.keycodes());
+    }
+
+    #[test]
+    fn test_input_device_layer() {
+        static LAYERS: Layers<3, 1> = &[
+            [[Layer(2), k(A), k(B)]],
+            [[Trans, k(X), Trans]],
+            [[NoOp, k(Y), Trans]],
+        ];
+        let mut layout = Layout::new(LAYERS);
+        layout.input_device_layers = &[None, Some(1)];
+
+        // device without a layer uses the default layer
+        layout.event_from_device(Press(0, 1), 0);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[A], layout.keycodes());
+        layout.event_from_device(Release(0, 1), 0);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        // device with a layer uses that layer
+        layout.event_from_device(Press(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[X], layout.keycodes());
+        assert_eq!(layout.input_device, 1);
+        layout.event_from_device(Release(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        // transparent key in the device layer falls through to the default layer
+        layout.event_from_device(Press(0, 2), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[B], layout.keycodes());
+        layout.event_from_device(Release(0, 2), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        // held layers take priority over the device layer
+        layout.event_from_device(Press(0, 0), 0);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event_from_device(Press(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[Y], layout.keycodes());
+        layout.event_from_device(Release(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.event_from_device(Release(0, 0), 0);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        // switching the default layer applies to the device too
+        layout.set_default_layer(2);
+        layout.event_from_device(Press(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[Y], layout.keycodes());
+        layout.event_from_device(Release(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        layout.set_default_layer(0);
+        layout.event_from_device(Press(0, 1), 1);
+        assert_eq!(CustomEvent::NoEvent, layout.tick());
+        assert_keys(&[X], layout.keycodes());
+    }
+
     #[test]
     fn test_trans_in_action_on_first_layer() {
         static DEFSRC_LAYER: [Action; 2] = [NoOp, k(X)];
@@ -4118,6 +4782,7 @@ mod test {
                     tap: Trans,
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 200,
+                    require_prior_idle: 0,
                 }),
             ]],
         ];
@@ -4165,6 +4830,7 @@ mod test {
                     tap: k(Space),
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 200,
+                    require_prior_idle: 0,
                 }),
             ]],
         ];
@@ -4201,6 +4867,7 @@ mod test {
                 TapDance(&crate::action::TapDance {
                     timeout: 100,
                     actions: &[&Trans, &k(X)],
+                    hold_actions: &[],
                     config: TapDanceConfig::Lazy,
                 }),
             ]],
@@ -4240,6 +4907,7 @@ mod test {
                 TapDance(&crate::action::TapDance {
                     timeout: 100,
                     actions: &[&Trans, &k(X)],
+                    hold_actions: &[],
                     config: TapDanceConfig::Eager,
                 }),
             ]],
@@ -4399,6 +5067,7 @@ mod test {
                     tap: Trans,
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 200,
+                    require_prior_idle: 0,
                 }),
             ]],
             [[
@@ -4412,6 +5081,7 @@ mod test {
                     tap: Trans,
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 200,
+                    require_prior_idle: 0,
                 }),
             ]],
             [[
@@ -4425,6 +5095,7 @@ mod test {
                     tap: Trans,
                     config: HoldTapConfig::Default,
                     tap_hold_interval: 200,
+                    require_prior_idle: 0,
                 }),
             ]],
         ];
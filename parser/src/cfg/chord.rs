@@ -19,7 +19,7 @@ pub(crate) fn parse_defchordv2(
         );
     }
 
-    let mut chunks = exprs[1..].chunks_exact(5);
+    let chunks = split_chord_entries(&exprs[1..], s)?;
     let mut chords_container = ChordsForKeys::<'static, KanataCustom> {
         mapping: FxHashMap::default(),
     };
@@ -27,7 +27,7 @@ pub(crate) fn parse_defchordv2(
     let mut all_participating_key_sets = FxHashSet::default();
 
     let all_chords = chunks
-        .by_ref()
+        .into_iter()
         .flat_map(|chunk| match chunk[0] {
             // Match a line like
             // (include filename.txt) () 100 all-released (layer1 layer2)
@@ -41,6 +41,7 @@ pub(crate) fn parse_defchordv2(
                     &chunk[2],
                     &chunk[3],
                     &chunk[4],
+                    chunk.get(5),
                     &s.layers[0][0],
                 );
                 let chord_definitions = parse_chord_file(file_name).unwrap();
@@ -78,17 +79,49 @@ pub(crate) fn parse_defchordv2(
                 .push(s.a.sref(chord.clone()));
         }
     }
-    let rem = chunks.remainder();
-    if !rem.is_empty() {
-        bail_expr!(
-            rem.last().unwrap(),
-            "Incomplete chord entry. Each chord entry must have 5 items:\n\
-        particpating-keys, action, timeout, release-type, disabled-layers"
-        );
-    }
     Ok(chords_container)
 }
 
+/// Split the chord entries into 5-tuples,
+/// or 6-tuples when followed by a list of per-chord options.
+fn split_chord_entries<'a>(exprs: &'a [SExpr], s: &ParserState) -> Result<Vec<&'a [SExpr]>> {
+    let mut entries = vec![];
+    let mut rem = exprs;
+    while !rem.is_empty() {
+        if rem.len() < 5 {
+            bail_expr!(
+                rem.last().unwrap(),
+                "Incomplete chord entry. Each chord entry must have 5 items:\n\
+            particpating-keys, action, timeout, release-type, disabled-layers"
+            );
+        }
+        let len = match rem.get(5) {
+            Some(expr) if is_chord_options(expr, s) => 6,
+            _ => 5,
+        };
+        let (entry, next) = rem.split_at(len);
+        entries.push(entry);
+        rem = next;
+    }
+    Ok(entries)
+}
+
+/// Options are recognized by their names so that an empty options list is not mistaken for the
+/// participating keys of the next chord.
+fn is_chord_options(expr: &SExpr, s: &ParserState) -> bool {
+    let Some(options) = expr.list(s.vars()) else {
+        return false;
+    };
+    match options.first() {
+        None => true,
+        Some(option) => option
+            .list(s.vars())
+            .and_then(|items| items.first())
+            .and_then(|name| name.atom(s.vars()))
+            .is_some_and(|name| CHORD_OPTION_NAMES.contains(&name)),
+    }
+}
+
 fn parse_single_chord(
     chunk: &[SExpr],
     s: &ParserState,
@@ -101,15 +134,38 @@ fn parse_single_chord(
             "Duplicate participating-keys, key sets may be used only once."
         );
     }
-    let action = parse_action(&chunk[1], s)?;
+    let mut action = parse_action(&chunk[1], s)?;
     let timeout = parse_timeout(&chunk[2], s)?;
     let release_behaviour = parse_release_behaviour(&chunk[3], s)?;
     let disabled_layers = parse_disabled_layers(&chunk[4], s)?;
+    let options = match chunk.get(5) {
+        Some(options) => parse_chord_options(options, s)?,
+        None => ChordOptions::default(),
+    };
+    if let Some((hold_timeout, hold_action)) = options.hold {
+        if matches!(action, Action::HoldTap { .. }) {
+            bail_expr!(
+                &chunk[1],
+                "tap-hold does not work in the action of a chord with a hold option"
+            );
+        }
+        action = s.a.sref(Action::HoldTap(s.a.sref(HoldTapAction {
+            config: HoldTapConfig::Default,
+            tap_hold_interval: 0,
+            require_prior_idle: 0,
+            timeout: hold_timeout,
+            tap: *action,
+            hold: *hold_action,
+            timeout_action: *hold_action,
+        })));
+    }
     let chord: ChordV2<'static, KanataCustom> = ChordV2 {
         action,
         participating_keys: s.a.sref_vec(participants.clone()),
         pending_duration: timeout,
         disabled_layers: s.a.sref_vec(disabled_layers),
+        enabled_layers: s.a.sref_vec(options.enabled_layers),
+        min_idle: options.min_idle,
         release_behaviour,
     };
     Ok(s.a.sref(chord).clone())
@@ -170,17 +226,7 @@ fn parse_release_behaviour(
 fn parse_disabled_layers(disabled_layers: &SExpr, s: &ParserState) -> Result<Vec<u16>> {
     let disabled_layers = disabled_layers
         .list(s.vars())
-        .map(|dl| {
-            dl.iter()
-                .try_fold(vec![], |mut layers, layer| -> Result<Vec<u16>> {
-                    let l_idx = layer
-                        .atom(s.vars())
-                        .and_then(|l| s.layer_idxs.get(l))
-                        .ok_or_else(|| anyhow_expr!(layer, "Not a known layer name."))?;
-                    layers.push((*l_idx) as u16);
-                    Ok(layers)
-                })
-        })
+        .map(|dl| parse_layer_names(dl, s))
         .ok_or_else(|| {
             anyhow_expr!(
                 disabled_layers,
@@ -190,6 +236,70 @@ fn parse_disabled_layers(disabled_layers: &SExpr, s: &ParserState) -> Result<Vec
     Ok(disabled_layers)
 }
 
+fn parse_layer_names(names: &[SExpr], s: &ParserState) -> Result<Vec<u16>> {
+    names
+        .iter()
+        .try_fold(vec![], |mut layers, layer| -> Result<Vec<u16>> {
+            let l_idx = layer
+                .atom(s.vars())
+                .and_then(|l| s.layer_idxs.get(l))
+                .ok_or_else(|| anyhow_expr!(layer, "Not a known layer name."))?;
+            layers.push((*l_idx) as u16);
+            Ok(layers)
+        })
+}
+
+#[derive(Default)]
+struct ChordOptions {
+    enabled_layers: Vec<u16>,
+    min_idle: u16,
+    hold: Option<(u16, &'static KanataAction)>,
+}
+
+const CHORD_OPTION_NAMES: [&str; 3] = ["enabled-layers", "min-idle", "hold"];
+
+const CHORD_OPTIONS_ERR: &str = "Chord options must be a list of lists, each one of:\n\
+    (enabled-layers <layer-name>...)\n\
+    (min-idle <ms>)\n\
+    (hold <timeout> <action>)";
+
+fn parse_chord_options(options: &SExpr, s: &ParserState) -> Result<ChordOptions> {
+    let mut parsed = ChordOptions::default();
+    let options = options
+        .list(s.vars())
+        .ok_or_else(|| anyhow_expr!(options, "{CHORD_OPTIONS_ERR}"))?;
+    let mut seen = FxHashSet::default();
+    for option in options {
+        let Some(items) = option.list(s.vars()) else {
+            bail_expr!(option, "{CHORD_OPTIONS_ERR}");
+        };
+        let Some(name) = items.first().and_then(|name| name.atom(s.vars())) else {
+            bail_expr!(option, "{CHORD_OPTIONS_ERR}");
+        };
+        if !seen.insert(name) {
+            bail_expr!(option, "Chord option {name} is defined more than once");
+        }
+        match (name, &items[1..]) {
+            ("enabled-layers", layers) => {
+                if layers.is_empty() {
+                    bail_expr!(option, "enabled-layers expects at least one layer name");
+                }
+                parsed.enabled_layers = parse_layer_names(layers, s)?;
+            }
+            ("min-idle", [ms]) => {
+                parsed.min_idle = parse_u16(ms, s, "min-idle")?;
+            }
+            ("hold", [timeout, action]) => {
+                let timeout = parse_non_zero_u16(timeout, s, "hold timeout")?;
+                let action = parse_action(action, s)?;
+                parsed.hold = Some((timeout, action));
+            }
+            _ => bail_expr!(option, "{CHORD_OPTIONS_ERR}"),
+        }
+    }
+    Ok(parsed)
+}
+
 fn parse_chord_file(file_name: &str) -> Result<Vec<ChordDefinition>> {
     let input_data = fs::read_to_string(file_name)
         .unwrap_or_else(|_| panic!("Unable to read file {}", file_name));
@@ -230,6 +340,7 @@ struct ChordTranslation<'a> {
     timeout: &'a SExpr,
     release_behaviour: &'a SExpr,
     disabled_layers: &'a SExpr,
+    options: Option<&'a SExpr>,
 }
 
 impl<'a> ChordTranslation<'a> {
@@ -238,6 +349,7 @@ impl<'a> ChordTranslation<'a> {
         timeout: &'a SExpr,
         release_behaviour: &'a SExpr,
         disabled_layers: &'a SExpr,
+        options: Option<&'a SExpr>,
         first_layer: &[Action<'static, &&[&CustomAction]>],
     ) -> Self {
         let postprocess_map: FxHashMap<String, String> = [
@@ -278,6 +390,7 @@ impl<'a> ChordTranslation<'a> {
             timeout,
             release_behaviour,
             disabled_layers,
+            options,
         }
     }
 
@@ -336,6 +449,7 @@ impl<'a> ChordTranslation<'a> {
             self.release_behaviour.clone(),
             self.disabled_layers.clone(),
         ]);
+        participant_action.extend(self.options.cloned());
         participant_action
     }
 }
@@ -0,0 +1,137 @@
+//! Parsing of context conditions. Context is arbitrary key/value state provided by a program
+//! outside of kanata, e.g. the class of the focused window sent by a window manager watcher over
+//! the TCP server. Conditions on the context are used by the `context` item of `switch` and by
+//! `defcontextlayers`, which changes the base layer automatically.
+//!
+//! Example:
+//!
+//! (defcontextlayers
+//!   (app firefox) browser
+//!   (app "org.gnome.Terminal") terminal
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail, bail_expr};
+
+/// The maximum number of distinct context conditions in a configuration.
+pub const MAX_CONTEXT_CONDITIONS: u16 = 1024;
+
+/// Context conditions used in the configuration, along with the layer activation rules of
+/// `defcontextlayers`.
+#[derive(Debug, Clone, Default)]
+pub struct ContextConditions {
+    /// The key and the value of each condition, indexed by condition id.
+    conditions: Vec<(String, String)>,
+    /// Condition id and the layer to switch to, in configuration order.
+    layer_rules: Vec<(u16, usize)>,
+}
+
+impl ContextConditions {
+    /// Returns the ids of the conditions that hold for the given context values.
+    pub fn matching<'a>(
+        &'a self,
+        context: &'a HashMap<String, String>,
+    ) -> impl Iterator<Item = u16> + 'a {
+        self.conditions
+            .iter()
+            .enumerate()
+            .filter(|(_, (k, v))| context.get(k).is_some_and(|cv| cv == v))
+            .map(|(id, _)| id as u16)
+    }
+
+    /// Returns the layer of the first `defcontextlayers` rule whose condition is active.
+    pub fn layer_for(&self, active: &[u16]) -> Option<usize> {
+        self.layer_rules
+            .iter()
+            .find(|(id, _)| active.contains(id))
+            .map(|(_, layer)| *layer)
+    }
+
+    /// Returns true if there are no context rules to activate layers.
+    pub fn has_layer_rules(&self) -> bool {
+        !self.layer_rules.is_empty()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.conditions.is_empty()
+    }
+}
+
+/// Returns the id of the condition testing that `key` has the value `value`, adding a new
+/// condition if it does not exist yet.
+pub(crate) fn context_condition_id(
+    expr: &SExpr,
+    key: &str,
+    value: &str,
+    s: &ParserState,
+) -> Result<u16> {
+    let mut conditions = s.context_conditions.borrow_mut();
+    if let Some(id) = conditions.iter().position(|(k, v)| k == key && v == value) {
+        return Ok(id as u16);
+    }
+    if conditions.len() >= usize::from(MAX_CONTEXT_CONDITIONS) {
+        bail_expr!(
+            expr,
+            "Exceeded the maximum of {MAX_CONTEXT_CONDITIONS} distinct context conditions"
+        );
+    }
+    conditions.push((key.to_string(), value.to_string()));
+    Ok((conditions.len() - 1) as u16)
+}
+
+/// Parse the key and value of a context condition, given the list items after its first item.
+pub(crate) fn parse_context_condition(
+    expr: &SExpr,
+    params: &[SExpr],
+    s: &ParserState,
+) -> Result<u16> {
+    const ERR_MSG: &str = "context must have 2 parameters: key value";
+    if params.len() != 2 {
+        bail_expr!(expr, "{ERR_MSG}");
+    }
+    let key = params[0]
+        .atom(s.vars())
+        .ok_or_else(|| anyhow_expr!(&params[0], "{ERR_MSG}\nThe key must not be a list"))?
+        .trim_atom_quotes();
+    let value = params[1]
+        .atom(s.vars())
+        .ok_or_else(|| anyhow_expr!(&params[1], "{ERR_MSG}\nThe value must not be a list"))?
+        .trim_atom_quotes();
+    context_condition_id(expr, key, value, s)
+}
+
+pub(crate) fn parse_context_layers(expr: &[SExpr], s: &ParserState) -> Result<Vec<(u16, usize)>> {
+    const ERR_MSG: &str = "defcontextlayers expects pairs of: (key value) layer-name";
+    let mut exprs = check_first_expr(expr.iter(), "defcontextlayers")?;
+    let mut rules = vec![];
+    while let Some(cond) = exprs.next() {
+        let Some(layer) = exprs.next() else {
+            bail_expr!(cond, "{ERR_MSG}\nFound a condition missing a layer name");
+        };
+        let Some(params) = cond.list(s.vars()) else {
+            bail_expr!(cond, "{ERR_MSG}\nThe condition must be a list");
+        };
+        let id = parse_context_condition(cond, params, s)?;
+        let layer = layer
+            .atom(s.vars())
+            .and_then(|l| s.layer_idxs.get(l))
+            .copied()
+            .ok_or_else(|| anyhow_expr!(layer, "not a known layer name"))?;
+        rules.push((id, layer));
+    }
+    if rules.is_empty() {
+        bail!("{ERR_MSG}\nFound no rules");
+    }
+    Ok(rules)
+}
+
+pub(crate) fn build_context_conditions(
+    layer_rules: Vec<(u16, usize)>,
+    s: &ParserState,
+) -> ContextConditions {
+    ContextConditions {
+        conditions: s.context_conditions.take(),
+        layer_rules,
+    }
+}
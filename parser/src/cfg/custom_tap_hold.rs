@@ -1,8 +1,10 @@
-use kanata_keyberon::layout::{Event, QueuedIter, WaitingAction};
+use kanata_keyberon::action::CustomHoldTapDecision;
+use kanata_keyberon::layout::{Event, HoldTapReason, QueuedIter, WaitingAction};
 
 use crate::keys::OsCode;
 
 use super::alloc::Allocations;
+use super::{Hand, Hands, NORMAL_KEY_ROW};
 
 /// Returns a closure that can be used in `HoldTapConfig::Custom`, which will return early with a
 /// Tap action in the case that any of `keys` are pressed. Otherwise it behaves as
@@ -10,49 +12,94 @@ use super::alloc::Allocations;
 pub(crate) fn custom_tap_hold_release(
     keys: &[OsCode],
     a: &Allocations,
-) -> &'static (dyn Fn(QueuedIter) -> (Option<WaitingAction>, bool) + Send + Sync) {
+) -> &'static (dyn Fn(QueuedIter) -> CustomHoldTapDecision + Send + Sync) {
     let keys = a.sref_vec(Vec::from_iter(keys.iter().copied()));
-    a.sref(
-        move |mut queued: QueuedIter| -> (Option<WaitingAction>, bool) {
-            while let Some(q) = queued.next() {
-                if q.event().is_press() {
-                    let (i, j) = q.event().coord();
-                    // If any key matches the input, do a tap right away.
-                    if keys.iter().copied().map(u16::from).any(|j2| j2 == j) {
-                        return (Some(WaitingAction::Tap), false);
-                    }
-                    // Otherwise do the PermissiveHold algorithm.
-                    let target = Event::Release(i, j);
-                    if queued.clone().copied().any(|q| q.event() == target) {
-                        return (Some(WaitingAction::Hold), false);
-                    }
+    a.sref(move |mut queued: QueuedIter| -> CustomHoldTapDecision {
+        while let Some(q) = queued.next() {
+            if q.event().is_press() {
+                let (i, j) = q.event().coord();
+                // If any key matches the input, do a tap right away.
+                if keys.iter().copied().map(u16::from).any(|j2| j2 == j) {
+                    return (
+                        Some((WaitingAction::Tap, HoldTapReason::TapKeysPress)),
+                        false,
+                    );
+                }
+                // Otherwise do the PermissiveHold algorithm.
+                let target = Event::Release(i, j);
+                if queued.clone().copied().any(|q| q.event() == target) {
+                    return (
+                        Some((WaitingAction::Hold, HoldTapReason::PermissiveHold)),
+                        false,
+                    );
                 }
             }
-            (None, false)
-        },
-    )
+        }
+        (None, false)
+    })
 }
 
 pub(crate) fn custom_tap_hold_except(
     keys: &[OsCode],
     a: &Allocations,
-) -> &'static (dyn Fn(QueuedIter) -> (Option<WaitingAction>, bool) + Send + Sync) {
+) -> &'static (dyn Fn(QueuedIter) -> CustomHoldTapDecision + Send + Sync) {
     let keys = a.sref_vec(Vec::from_iter(keys.iter().copied()));
-    a.sref(
-        move |mut queued: QueuedIter| -> (Option<WaitingAction>, bool) {
-            for q in queued.by_ref() {
-                if q.event().is_press() {
-                    let (_i, j) = q.event().coord();
-                    // If any key matches the input, do a tap.
-                    if keys.iter().copied().map(u16::from).any(|j2| j2 == j) {
-                        return (Some(WaitingAction::Tap), false);
-                    }
-                    // Otherwise continue with default behavior
-                    return (None, false);
+    a.sref(move |mut queued: QueuedIter| -> CustomHoldTapDecision {
+        for q in queued.by_ref() {
+            if q.event().is_press() {
+                let (_i, j) = q.event().coord();
+                // If any key matches the input, do a tap.
+                if keys.iter().copied().map(u16::from).any(|j2| j2 == j) {
+                    return (
+                        Some((WaitingAction::Tap, HoldTapReason::TapKeysPress)),
+                        false,
+                    );
                 }
+                // Otherwise continue with default behavior
+                return (None, false);
+            }
+        }
+        // Otherwise skip timeout
+        (None, true)
+    })
+}
+
+/// Returns a closure that can be used in `HoldTapConfig::Custom`, which decides on the first
+/// press of a key assigned to a hand in `defhands`. A key of the same hand as the tap-hold key
+/// results in a Tap action and a key of the opposite hand or a neutral key results in a Hold
+/// action. Keys that are not assigned to a hand, or a tap-hold key that is not assigned to a
+/// hand itself, leave the decision to the timeout.
+pub(crate) fn custom_tap_hold_opposite_hand(
+    hands: &'static Hands,
+    a: &Allocations,
+) -> &'static (dyn Fn(QueuedIter) -> CustomHoldTapDecision + Send + Sync) {
+    let hand_of = |(i, j): (u8, u16)| -> Option<Hand> {
+        match i {
+            NORMAL_KEY_ROW => hands.get(&j).copied(),
+            _ => None,
+        }
+    };
+    a.sref(move |queued: QueuedIter| -> CustomHoldTapDecision {
+        let Some(waiting_hand) = hand_of(queued.waiting_coord()) else {
+            return (None, false);
+        };
+        for q in queued {
+            if !q.event().is_press() {
+                continue;
             }
-            // Otherwise skip timeout
-            (None, true)
-        },
-    )
+            let Some(pressed_hand) = hand_of(q.event().coord()) else {
+                continue;
+            };
+            return match (waiting_hand, pressed_hand) {
+                (Hand::Left, Hand::Left) | (Hand::Right, Hand::Right) => {
+                    (Some((WaitingAction::Tap, HoldTapReason::SameHand)), false)
+                }
+                _ => (
+                    Some((WaitingAction::Hold, HoldTapReason::OppositeHand)),
+                    false,
+                ),
+            };
+        }
+        (None, false)
+    })
 }
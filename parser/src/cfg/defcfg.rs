@@ -1,11 +1,14 @@
 use super::sexpr::SExpr;
 use super::HashSet;
 use super::{error::*, TrimAtomQuotes};
-use crate::cfg::check_first_expr;
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+use crate::cfg::parse_device_id_number;
+use crate::cfg::{check_first_expr, str_to_led};
 use crate::custom_action::*;
 use crate::keys::*;
 #[allow(unused)]
 use crate::{anyhow_expr, anyhow_span, bail, bail_expr, bail_span};
+use kanata_keyberon::key_code::KeyCode;
 
 #[cfg(any(target_os = "linux", target_os = "unknown"))]
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -34,6 +37,13 @@ pub struct CfgLinuxOptions {
     pub linux_use_trackpoint_property: bool,
     pub linux_output_bus_type: LinuxCfgOutputBusType,
     pub linux_device_detect_mode: Option<DeviceDetectMode>,
+    /// Width and height of the screen. When set, an absolute pointer device is created to
+    /// support `setmouse`.
+    pub linux_setmouse_screen_size: Option<(u16, u16)>,
+    /// When set, keyboard and pointer output are sent from separate devices.
+    pub linux_output_devices: Option<LinuxOutputDevices>,
+    /// Rules that include or exclude autodetected devices, in order of priority.
+    pub linux_dev_rules: Vec<crate::cfg::LinuxDevRule>,
 }
 #[cfg(any(target_os = "linux", target_os = "unknown"))]
 impl Default for CfgLinuxOptions {
@@ -51,6 +61,9 @@ impl Default for CfgLinuxOptions {
             linux_use_trackpoint_property: false,
             linux_output_bus_type: LinuxCfgOutputBusType::BusI8042,
             linux_device_detect_mode: None,
+            linux_setmouse_screen_size: None,
+            linux_output_devices: None,
+            linux_dev_rules: vec![],
         }
     }
 }
@@ -61,6 +74,41 @@ pub enum LinuxCfgOutputBusType {
     BusI8042,
 }
 
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinuxOutputDevice {
+    pub name: String,
+    pub vendor: u16,
+    pub product: u16,
+}
+
+/// The output devices used instead of the single `kanata` device by `linux-output-devices`.
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinuxOutputDevices {
+    /// Outputs everything except mouse buttons and mouse movement.
+    pub keyboard: LinuxOutputDevice,
+    /// Outputs mouse buttons, scrolling and mouse movement.
+    pub pointer: LinuxOutputDevice,
+}
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+impl Default for LinuxOutputDevices {
+    fn default() -> Self {
+        Self {
+            keyboard: LinuxOutputDevice {
+                name: "kanata".to_owned(),
+                vendor: 1,
+                product: 1,
+            },
+            pointer: LinuxOutputDevice {
+                name: "kanata pointer".to_owned(),
+                vendor: 1,
+                product: 3,
+            },
+        }
+    }
+}
+
 #[cfg(any(target_os = "macos", target_os = "unknown"))]
 #[derive(Debug, Default, Clone)]
 pub struct CfgMacosOptions {
@@ -129,6 +177,42 @@ impl Default for CfgOptionsGui {
     }
 }
 
+/// Configuration of auto-shift, which outputs the shifted variant of a key that is held for longer
+/// than the timeout.
+#[derive(Debug, Clone)]
+pub struct CfgAutoShiftOptions {
+    pub enabled: bool,
+    pub timeout: u16,
+    /// Keep the shifted output pressed while the key is held so that it repeats.
+    pub repeat: bool,
+    /// Keys to auto-shift. If None, the default set of alphanumeric and punctuation keys is used.
+    pub keys: Option<Vec<KeyCode>>,
+    pub exclude_keys: Vec<KeyCode>,
+    /// Outputs to use instead of shift plus the key. These keys are auto-shifted even if they
+    /// are not in `keys`.
+    pub custom_outputs: Vec<(KeyCode, Vec<KeyCode>)>,
+}
+impl Default for CfgAutoShiftOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout: 175,
+            repeat: false,
+            keys: None,
+            exclude_keys: vec![],
+            custom_outputs: vec![],
+        }
+    }
+}
+
+/// LEDs to turn on while a mode is active.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CfgLedIndicators {
+    pub caps_word: Option<Led>,
+    pub sequence: Option<Led>,
+    pub dynamic_macro_record: Option<Led>,
+}
+
 #[derive(Debug)]
 pub struct CfgOptions {
     pub process_unmapped_keys: bool,
@@ -142,16 +226,26 @@ pub struct CfgOptions {
     pub sequence_backtrack_modcancel: bool,
     pub sequence_always_on: bool,
     pub log_layer_changes: bool,
+    pub log_hold_tap_decisions: bool,
     pub delegate_to_first_layer: bool,
     pub movemouse_inherit_accel_state: bool,
     pub movemouse_smooth_diagonals: bool,
+    /// Distance that input devices move the mouse to tap a mouse movement key, e.g. `mmu`.
+    pub mouse_movement_key_threshold: u16,
     pub override_release_on_activation: bool,
     pub dynamic_macro_max_presses: u16,
     pub dynamic_macro_replay_delay_behaviour: ReplayDelayBehaviour,
+    pub dynamic_macro_file: Option<String>,
+    pub statistics: bool,
+    pub statistics_file: Option<String>,
+    pub statistics_flush_interval: u16,
     pub concurrent_tap_hold: bool,
+    pub tap_hold_require_prior_idle: u16,
     pub rapid_event_delay: u16,
     pub trans_resolution_behavior_v2: bool,
     pub chords_v2_min_idle: u16,
+    pub autoshift_opts: CfgAutoShiftOptions,
+    pub led_indicators: CfgLedIndicators,
     #[cfg(any(target_os = "linux", target_os = "unknown"))]
     pub linux_opts: CfgLinuxOptions,
     #[cfg(any(target_os = "macos", target_os = "unknown"))]
@@ -181,16 +275,25 @@ impl Default for CfgOptions {
             sequence_backtrack_modcancel: true,
             sequence_always_on: false,
             log_layer_changes: true,
+            log_hold_tap_decisions: false,
             delegate_to_first_layer: false,
             movemouse_inherit_accel_state: false,
             movemouse_smooth_diagonals: false,
+            mouse_movement_key_threshold: 16,
             override_release_on_activation: false,
             dynamic_macro_max_presses: 128,
             dynamic_macro_replay_delay_behaviour: ReplayDelayBehaviour::Recorded,
+            dynamic_macro_file: None,
+            statistics: false,
+            statistics_file: None,
+            statistics_flush_interval: 60,
             concurrent_tap_hold: false,
+            tap_hold_require_prior_idle: 0,
             rapid_event_delay: 5,
             trans_resolution_behavior_v2: true,
             chords_v2_min_idle: 5,
+            autoshift_opts: Default::default(),
+            led_indicators: Default::default(),
             #[cfg(any(target_os = "linux", target_os = "unknown"))]
             linux_opts: Default::default(),
             #[cfg(any(target_os = "windows", target_os = "unknown"))]
@@ -265,6 +368,16 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                                 anyhow_expr!(val, "this option must be one of: constant | recorded")
                             })??;
                     }
+                    "dynamic-macro-file" => {
+                        cfg.dynamic_macro_file = Some(sexpr_to_str_or_err(val, label)?.to_string());
+                    }
+                    "statistics" => cfg.statistics = parse_defcfg_val_bool(val, label)?,
+                    "statistics-file" => {
+                        cfg.statistics_file = Some(sexpr_to_str_or_err(val, label)?.to_string());
+                    }
+                    "statistics-flush-interval" => {
+                        cfg.statistics_flush_interval = parse_cfg_val_u16(val, label, true)?;
+                    }
                     "linux-dev" => {
                         #[cfg(any(target_os = "linux", target_os = "unknown"))]
                         {
@@ -293,6 +406,13 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                             cfg.linux_opts.linux_dev_names_exclude = Some(parse_dev(val)?);
                         }
                     }
+                    "linux-dev-rules" => {
+                        #[cfg(any(target_os = "linux", target_os = "unknown"))]
+                        {
+                            cfg.linux_opts.linux_dev_rules =
+                                crate::cfg::parse_linux_dev_rules(val, label)?;
+                        }
+                    }
                     "linux-unicode-u-code" => {
                         #[cfg(any(target_os = "linux", target_os = "unknown"))]
                         {
@@ -341,6 +461,28 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                             });
                         }
                     }
+                    "linux-setmouse-screen-size" => {
+                        #[cfg(any(target_os = "linux", target_os = "unknown"))]
+                        {
+                            let v = sexpr_to_str_or_err(val, label)?;
+                            const ERRMSG: &str = "Invalid value for linux-setmouse-screen-size.\nExpected two numbers 1-65535 separated by a comma, e.g. 1920,1080";
+                            let Some((width, height)) = v.split_once(',') else {
+                                bail_expr!(val, "{}", ERRMSG)
+                            };
+                            cfg.linux_opts.linux_setmouse_screen_size =
+                                match (str::parse::<u16>(width), str::parse::<u16>(height)) {
+                                    (Ok(w), Ok(h)) if w > 0 && h > 0 => Some((w, h)),
+                                    _ => bail_expr!(val, "{}", ERRMSG),
+                                };
+                        }
+                    }
+                    "linux-output-devices" => {
+                        #[cfg(any(target_os = "linux", target_os = "unknown"))]
+                        {
+                            cfg.linux_opts.linux_output_devices =
+                                Some(parse_linux_output_devices(val, label)?);
+                        }
+                    }
                     "linux-use-trackpoint-property" => {
                         #[cfg(any(target_os = "linux", target_os = "unknown"))]
                         {
@@ -743,6 +885,9 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                     "log-layer-changes" => {
                         cfg.log_layer_changes = parse_defcfg_val_bool(val, label)?
                     }
+                    "log-hold-tap-decisions" => {
+                        cfg.log_hold_tap_decisions = parse_defcfg_val_bool(val, label)?
+                    }
                     "delegate-to-first-layer" => {
                         cfg.delegate_to_first_layer = parse_defcfg_val_bool(val, label)?;
                         if cfg.delegate_to_first_layer {
@@ -759,6 +904,9 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                     "movemouse-smooth-diagonals" => {
                         cfg.movemouse_smooth_diagonals = parse_defcfg_val_bool(val, label)?
                     }
+                    "mouse-movement-key-threshold" => {
+                        cfg.mouse_movement_key_threshold = parse_cfg_val_u16(val, label, true)?;
+                    }
                     "movemouse-inherit-accel-state" => {
                         cfg.movemouse_inherit_accel_state = parse_defcfg_val_bool(val, label)?
                     }
@@ -768,6 +916,9 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                     "concurrent-tap-hold" => {
                         cfg.concurrent_tap_hold = parse_defcfg_val_bool(val, label)?
                     }
+                    "tap-hold-require-prior-idle" => {
+                        cfg.tap_hold_require_prior_idle = parse_cfg_val_u16(val, label, false)?
+                    }
                     "rapid-event-delay" => {
                         cfg.rapid_event_delay = parse_cfg_val_u16(val, label, false)?
                     }
@@ -794,6 +945,32 @@ pub fn parse_defcfg(expr: &[SExpr]) -> Result<CfgOptions> {
                         }
                         cfg.chords_v2_min_idle = min_idle;
                     }
+                    "autoshift" => cfg.autoshift_opts.enabled = parse_defcfg_val_bool(val, label)?,
+                    "autoshift-timeout" => {
+                        cfg.autoshift_opts.timeout = parse_cfg_val_u16(val, label, true)?
+                    }
+                    "autoshift-repeat" => {
+                        cfg.autoshift_opts.repeat = parse_defcfg_val_bool(val, label)?
+                    }
+                    "autoshift-keys" => {
+                        cfg.autoshift_opts.keys = Some(parse_key_list(val, label)?);
+                    }
+                    "autoshift-exclude-keys" => {
+                        cfg.autoshift_opts.exclude_keys = parse_key_list(val, label)?;
+                    }
+                    "autoshift-custom" => {
+                        cfg.autoshift_opts.custom_outputs = parse_autoshift_custom(val, label)?;
+                    }
+                    "led-caps-word" => {
+                        cfg.led_indicators.caps_word = Some(parse_defcfg_led(val, label)?);
+                    }
+                    "led-sequence" => {
+                        cfg.led_indicators.sequence = Some(parse_defcfg_led(val, label)?);
+                    }
+                    "led-dynamic-macro-record" => {
+                        cfg.led_indicators.dynamic_macro_record =
+                            Some(parse_defcfg_led(val, label)?);
+                    }
                     _ => bail_expr!(key, "Unknown defcfg option {}", label),
                 };
             }
@@ -917,7 +1094,65 @@ pub fn parse_dev(val: &SExpr) -> Result<Vec<String>> {
     })
 }
 
-fn sexpr_to_str_or_err<'a>(expr: &'a SExpr, label: &str) -> Result<&'a str> {
+fn parse_defcfg_led(val: &SExpr, label: &str) -> Result<Led> {
+    val.atom(None).and_then(str_to_led).ok_or_else(|| {
+        anyhow_expr!(
+            val,
+            "The value for {label} must be one of: caps, num, scroll"
+        )
+    })
+}
+
+/// Parse a list of key names, e.g. `(a b c)`.
+fn parse_key_list(expr: &SExpr, label: &str) -> Result<Vec<KeyCode>> {
+    let Some(list) = expr.list(None) else {
+        bail_expr!(expr, "The value for {label} must be a list of keys");
+    };
+    list.iter()
+        .map(|key_expr| {
+            key_expr
+                .atom(None)
+                .and_then(str_to_oscode)
+                .map(KeyCode::from)
+                .ok_or_else(|| anyhow_expr!(key_expr, "Expected a known key name."))
+        })
+        .collect()
+}
+
+/// Parse pairs of `<key> <output>` where the output is a key name with optional modifier
+/// prefixes, e.g. `(. S-1  , S-/)`.
+fn parse_autoshift_custom(expr: &SExpr, label: &str) -> Result<Vec<(KeyCode, Vec<KeyCode>)>> {
+    const ERR_MSG: &str = "expects pairs of: <key> <output>, e.g. (. S-1)";
+    let Some(list) = expr.list(None) else {
+        bail_expr!(expr, "{label} {ERR_MSG}");
+    };
+    if list.len() % 2 != 0 {
+        bail_expr!(expr, "{label} {ERR_MSG}\nFound a key missing an output");
+    }
+    let mut outputs: Vec<(KeyCode, Vec<KeyCode>)> = vec![];
+    for pair in list.chunks_exact(2) {
+        let key = pair[0]
+            .atom(None)
+            .and_then(str_to_oscode)
+            .map(KeyCode::from)
+            .ok_or_else(|| anyhow_expr!(&pair[0], "Expected a known key name."))?;
+        if outputs.iter().any(|(k, _)| *k == key) {
+            bail_expr!(&pair[0], "Duplicate key name is not allowed.");
+        }
+        let Some(output) = pair[1].atom(None) else {
+            bail_expr!(&pair[1], "{label} {ERR_MSG}\nThe output must not be a list");
+        };
+        let (mut keys, unparsed) =
+            super::parse_mod_prefix(output).map_err(|e| anyhow_expr!(&pair[1], "{}", e.msg))?;
+        let output_key = str_to_oscode(unparsed)
+            .ok_or_else(|| anyhow_expr!(&pair[1], "Expected a known key name after modifiers."))?;
+        keys.push(output_key.into());
+        outputs.push((key, keys));
+    }
+    Ok(outputs)
+}
+
+pub(crate) fn sexpr_to_str_or_err<'a>(expr: &'a SExpr, label: &str) -> Result<&'a str> {
     match expr {
         SExpr::Atom(a) => Ok(a.t.trim_atom_quotes()),
         SExpr::List(_) => bail_expr!(expr, "The value for {label} can't be a list"),
@@ -926,9 +1161,10 @@ fn sexpr_to_str_or_err<'a>(expr: &'a SExpr, label: &str) -> Result<&'a str> {
 
 #[cfg(any(
     all(feature = "interception_driver", target_os = "windows"),
+    target_os = "linux",
     target_os = "unknown"
 ))]
-fn sexpr_to_list_or_err<'a>(expr: &'a SExpr, label: &str) -> Result<&'a [SExpr]> {
+pub(crate) fn sexpr_to_list_or_err<'a>(expr: &'a SExpr, label: &str) -> Result<&'a [SExpr]> {
     match expr {
         SExpr::Atom(_) => bail_expr!(expr, "The value for {label} must be a list"),
         SExpr::List(l) => Ok(&l.t),
@@ -1019,3 +1255,54 @@ pub enum ReplayDelayBehaviour {
     /// This is newer behaviour.
     Recorded,
 }
+
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+fn parse_linux_output_devices(val: &SExpr, label: &str) -> Result<LinuxOutputDevices> {
+    const ERRMSG: &str = "linux-output-devices expects lists of:\n\
+        <keyboard|pointer> [name <name>] [vendor <id>] [product <id>]";
+    let mut devices = LinuxOutputDevices::default();
+    let mut seen_devices: Vec<&str> = vec![];
+    for entry in sexpr_to_list_or_err(val, label)? {
+        let Some((device_expr, opts)) = entry.list(None).and_then(|l| l.split_first()) else {
+            bail_expr!(entry, "{ERRMSG}")
+        };
+        let device_kind = device_expr.atom(None).unwrap_or_default();
+        let device = match device_kind {
+            "keyboard" => &mut devices.keyboard,
+            "pointer" => &mut devices.pointer,
+            _ => bail_expr!(
+                device_expr,
+                "{ERRMSG}\nThe device must be keyboard or pointer"
+            ),
+        };
+        if seen_devices.contains(&device_kind) {
+            bail_expr!(device_expr, "This device is configured more than once");
+        }
+        seen_devices.push(device_kind);
+        let mut kvs = opts.chunks_exact(2);
+        for kv in kvs.by_ref() {
+            match kv[0].atom(None) {
+                Some("name") => {
+                    let name = sexpr_to_str_or_err(&kv[1], "name")?;
+                    if name.is_empty() {
+                        bail_expr!(&kv[1], "The device name can't be empty");
+                    }
+                    device.name = name.to_owned();
+                }
+                Some("vendor") => {
+                    let id = sexpr_to_str_or_err(&kv[1], "vendor")?;
+                    device.vendor = parse_device_id_number(&kv[1], id)?;
+                }
+                Some("product") => {
+                    let id = sexpr_to_str_or_err(&kv[1], "product")?;
+                    device.product = parse_device_id_number(&kv[1], id)?;
+                }
+                _ => bail_expr!(&kv[0], "{ERRMSG}\nUnknown device option"),
+            }
+        }
+        if let Some(missing_value) = kvs.remainder().first() {
+            bail_expr!(missing_value, "This option is missing a value.");
+        }
+    }
+    Ok(devices)
+}
@@ -0,0 +1,170 @@
+//! Parsing of `defdevice`, which gives a name to one or more physical input devices so that
+//! events from them can be handled differently from other devices.
+//!
+//! Example:
+//!
+//! (defdevice macropad
+//!   name "Macro Pad Keyboard"
+//!   vendor-id 0x1234
+//!   product-id 0x5678
+//!   layer macropad-base
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail, bail_expr, bail_span};
+
+/// The maximum number of `defdevice` items. Device identifiers are stored in a `u8` and the
+/// identifier 0 is reserved for devices not matched by any `defdevice`.
+pub const MAX_INPUT_DEVICES: usize = u8::MAX as usize;
+
+/// A device declared with `defdevice`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputDeviceCfg {
+    /// The name used to refer to this device within the configuration.
+    pub name: String,
+    /// The identifier of the device. This is the 1-based position of the `defdevice` within
+    /// the configuration.
+    pub id: u8,
+    /// Device name reported by the operating system. Must match exactly.
+    pub device_name: Option<String>,
+    /// Device path, e.g. `/dev/input/by-id/usb-...`. Must match exactly.
+    pub device_path: Option<String>,
+    pub vendor_id: Option<u16>,
+    pub product_id: Option<u16>,
+    /// Layer that key presses from the device should be resolved from, instead of the default
+    /// layer.
+    pub layer: Option<usize>,
+}
+
+impl InputDeviceCfg {
+    /// Returns true if a device with the given properties matches this `defdevice`. All
+    /// properties specified in the `defdevice` must match.
+    pub fn matches(
+        &self,
+        device_name: &str,
+        device_path: &str,
+        vendor_id: u16,
+        product_id: u16,
+    ) -> bool {
+        self.device_name.as_deref().is_none_or(|n| n == device_name)
+            && self.device_path.as_deref().is_none_or(|p| p == device_path)
+            && self.vendor_id.is_none_or(|v| v == vendor_id)
+            && self.product_id.is_none_or(|p| p == product_id)
+    }
+}
+
+/// Returns the identifier of the first device in `devices` that matches the given properties,
+/// or 0 if none of them match.
+pub fn find_input_device_id(
+    devices: &[InputDeviceCfg],
+    device_name: &str,
+    device_path: &str,
+    vendor_id: u16,
+    product_id: u16,
+) -> u8 {
+    devices
+        .iter()
+        .find(|dev| dev.matches(device_name, device_path, vendor_id, product_id))
+        .map(|dev| dev.id)
+        .unwrap_or(0)
+}
+
+pub(crate) fn parse_defdevices(
+    exprs: &[&Spanned<Vec<SExpr>>],
+    s: &mut ParserState,
+) -> Result<Vec<InputDeviceCfg>> {
+    if exprs.len() > MAX_INPUT_DEVICES {
+        bail_span!(
+            exprs[MAX_INPUT_DEVICES],
+            "Exceeded the maximum of {MAX_INPUT_DEVICES} defdevice items"
+        );
+    }
+    let mut devices = vec![];
+    for (i, expr) in exprs.iter().enumerate() {
+        let id = (i + 1) as u8;
+        let device = parse_defdevice(&expr.t, id, s)?;
+        if s.input_device_idxs.contains_key(&device.name) {
+            bail_expr!(&expr.t[1], "Duplicate defdevice name: {}", device.name);
+        }
+        s.input_device_idxs.insert(device.name.clone(), id);
+        devices.push(device);
+    }
+    Ok(devices)
+}
+
+fn parse_defdevice(expr: &[SExpr], id: u8, s: &ParserState) -> Result<InputDeviceCfg> {
+    const ERR_MSG: &str = "defdevice expects a device name followed by pairs of: <option> <value>";
+    let mut exprs = check_first_expr(expr.iter(), "defdevice")?;
+    let name = match exprs.next() {
+        Some(e) => e
+            .atom(s.vars())
+            .ok_or_else(|| anyhow_expr!(e, "{ERR_MSG}\nThe device name must not be a list"))?,
+        None => bail!("{ERR_MSG}\nFound no device name"),
+    };
+    let mut device = InputDeviceCfg {
+        name: name.to_string(),
+        id,
+        device_name: None,
+        device_path: None,
+        vendor_id: None,
+        product_id: None,
+        layer: None,
+    };
+    let mut seen_options = HashSet::default();
+    while let Some(option) = exprs.next() {
+        let Some(val) = exprs.next() else {
+            bail_expr!(option, "{ERR_MSG}\nFound an option missing a value");
+        };
+        let Some(option_name) = option.atom(s.vars()) else {
+            bail_expr!(option, "{ERR_MSG}\nOptions must not be lists");
+        };
+        if !seen_options.insert(option_name) {
+            bail_expr!(option, "Duplicate defdevice option {option_name}");
+        }
+        let val_str = val
+            .atom(s.vars())
+            .map(|v| v.trim_atom_quotes())
+            .ok_or_else(|| anyhow_expr!(val, "The value for {option_name} must not be a list"))?;
+        match option_name {
+            "name" => device.device_name = Some(val_str.to_string()),
+            "path" => device.device_path = Some(val_str.to_string()),
+            "vendor-id" => device.vendor_id = Some(parse_device_id_number(val, val_str)?),
+            "product-id" => device.product_id = Some(parse_device_id_number(val, val_str)?),
+            "layer" => {
+                device.layer = Some(
+                    s.layer_idxs
+                        .get(val_str)
+                        .copied()
+                        .ok_or_else(|| anyhow_expr!(val, "not a known layer name"))?,
+                );
+            }
+            _ => bail_expr!(
+                option,
+                "Unknown defdevice option {option_name}\n\
+                Valid options are: name | path | vendor-id | product-id | layer"
+            ),
+        }
+    }
+    if device.device_name.is_none()
+        && device.device_path.is_none()
+        && device.vendor_id.is_none()
+        && device.product_id.is_none()
+    {
+        bail_expr!(
+            &expr[1],
+            "defdevice must contain at least one of: name | path | vendor-id | product-id"
+        );
+    }
+    Ok(device)
+}
+
+/// Parse a vendor or product ID, which can be written in decimal or in hexadecimal with a `0x`
+/// prefix.
+pub(crate) fn parse_device_id_number(expr: &SExpr, val: &str) -> Result<u16> {
+    match val.strip_prefix("0x").or_else(|| val.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => val.parse::<u16>().ok(),
+    }
+    .ok_or_else(|| anyhow_expr!(expr, "device ids must be 0-65535 or 0x0000-0xFFFF"))
+}
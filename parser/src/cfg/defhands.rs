@@ -0,0 +1,59 @@
+//! Parsing of `defhands`, which assigns keys to the hand that types them. The assignments are
+//! used by `tap-hold-opposite-hand` to decide between tap and hold.
+//!
+//! Example:
+//!
+//! (defhands
+//!   (left    q w e r t a s d f g z x c v b)
+//!   (right   y u i o p h j k l ; n m , . /)
+//!   (neutral spc)
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail_expr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+    /// The key can be combined with keys of either hand.
+    Neutral,
+}
+
+/// Hand assignments from `defhands`, keyed by the `OsCode` of the key.
+pub type Hands = HashMap<u16, Hand>;
+
+pub(crate) fn parse_defhands(expr: &[SExpr], s: &mut ParserState) -> Result<()> {
+    const ERR_MSG: &str =
+        "defhands expects lists of: <left|right|neutral> <key1> <key2> ... <keyN>";
+    let mut hands = Hands::default();
+    for group in check_first_expr(expr.iter(), "defhands")? {
+        let Some(group_list) = group.list(s.vars()) else {
+            bail_expr!(group, "{ERR_MSG}\nFound a key outside of a hand list");
+        };
+        let Some((hand_expr, keys)) = group_list.split_first() else {
+            bail_expr!(group, "{ERR_MSG}\nFound an empty list");
+        };
+        let hand = match hand_expr.atom(s.vars()) {
+            Some("left") => Hand::Left,
+            Some("right") => Hand::Right,
+            Some("neutral") => Hand::Neutral,
+            _ => bail_expr!(
+                hand_expr,
+                "{ERR_MSG}\nThe hand must be left, right or neutral"
+            ),
+        };
+        for key_expr in keys {
+            let osc = key_expr
+                .atom(s.vars())
+                .and_then(str_to_oscode)
+                .ok_or_else(|| anyhow_expr!(key_expr, "string of a known key is expected"))?;
+            if hands.insert(osc.into(), hand).is_some() {
+                bail_expr!(key_expr, "This key is already assigned to a hand");
+            }
+        }
+    }
+    s.hands = Some(s.a.sref(hands));
+    Ok(())
+}
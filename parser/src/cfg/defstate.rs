@@ -0,0 +1,172 @@
+//! Parsing of `defstate`, which declares named integer variables that actions can change at
+//! runtime and that `switch` can compare, along with the actions operating on the variables.
+//!
+//! Example:
+//!
+//! (defstate
+//!   presses 0
+//!   gaming false
+//! )
+//!
+//! (defalias
+//!   cnt (state-inc presses)
+//!   gm (state-toggle gaming)
+//!   x (switch ((state gaming = true)) a break () b break)
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail, bail_expr};
+
+use kanata_keyberon::action::switch::Comparator;
+
+/// The maximum number of variables that can be declared with `defstate`.
+pub const MAX_STATE_VARS: usize = kanata_keyberon::layout::MAX_VARIABLES;
+
+/// A variable declared in `defstate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateVarCfg {
+    pub name: String,
+    /// Value of the variable when the configuration is loaded.
+    pub initial: i16,
+}
+
+pub(crate) fn parse_defstates(
+    exprs: &[&Vec<SExpr>],
+    s: &mut ParserState,
+) -> Result<Vec<StateVarCfg>> {
+    const ERR_MSG: &str = "defstate expects pairs of: <variable name> <initial value>";
+    let mut vars = vec![];
+    for expr in exprs {
+        let mut subexprs = check_first_expr(expr.iter(), "defstate")?;
+        while let Some(name_expr) = subexprs.next() {
+            let Some(val_expr) = subexprs.next() else {
+                bail_expr!(name_expr, "{ERR_MSG}\nFound a variable missing a value");
+            };
+            let Some(name) = name_expr.atom(s.vars()).map(str::to_string) else {
+                bail_expr!(name_expr, "{ERR_MSG}\nThe variable name must not be a list");
+            };
+            if s.state_var_idxs.contains_key(&name) {
+                bail_expr!(name_expr, "Duplicate defstate variable: {name}");
+            }
+            if vars.len() >= MAX_STATE_VARS {
+                bail_expr!(
+                    name_expr,
+                    "Exceeded the maximum of {MAX_STATE_VARS} defstate variables"
+                );
+            }
+            let initial = parse_state_value(val_expr, s)?;
+            s.state_var_idxs.insert(name.clone(), vars.len() as u16);
+            vars.push(StateVarCfg { name, initial });
+        }
+    }
+    Ok(vars)
+}
+
+/// Parse a variable value: an integer in the range of `i16`, or `true` and `false` which are
+/// equivalent to 1 and 0.
+fn parse_state_value(expr: &SExpr, s: &ParserState) -> Result<i16> {
+    match expr.atom(s.vars()) {
+        Some("true") => Ok(1),
+        Some("false") => Ok(0),
+        Some(a) => a
+            .parse::<i16>()
+            .map_err(|_| anyhow_expr!(expr, "value must be true, false, or -32768-32767")),
+        None => bail_expr!(expr, "value must be true, false, or -32768-32767"),
+    }
+}
+
+fn parse_state_var_index(expr: &SExpr, s: &ParserState) -> Result<u16> {
+    expr.atom(s.vars())
+        .and_then(|name| s.state_var_idxs.get(name))
+        .copied()
+        .ok_or_else(|| anyhow_expr!(expr, "not a variable declared in defstate"))
+}
+
+pub(crate) fn parse_state_set(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    const ERR_MSG: &str = "state-set expects 2 parameters: <variable name> <value>";
+    if ac_params.len() != 2 {
+        bail!("{ERR_MSG}, found {}", ac_params.len());
+    }
+    let index = parse_state_var_index(&ac_params[0], s)?;
+    let value = parse_state_value(&ac_params[1], s)?;
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::StateSet { index, value })),
+    )))
+}
+
+/// Parse `state-inc` and `state-dec`, which take the variable name and an optional amount that
+/// defaults to 1.
+pub(crate) fn parse_state_add(
+    ac_params: &[SExpr],
+    s: &ParserState,
+    action_name: &str,
+) -> Result<&'static KanataAction> {
+    if ac_params.is_empty() || ac_params.len() > 2 {
+        bail!(
+            "{action_name} expects 1 or 2 parameters: <variable name> [amount], found {}",
+            ac_params.len()
+        );
+    }
+    let index = parse_state_var_index(&ac_params[0], s)?;
+    let amount = match ac_params.get(1) {
+        Some(expr) => parse_u16(expr, s, "amount")?
+            .try_into()
+            .map_err(|_| anyhow_expr!(expr, "amount must be 0-32767"))?,
+        None => 1i16,
+    };
+    let amount = match action_name {
+        STATE_INC => amount,
+        STATE_DEC => -amount,
+        _ => unreachable!("not a state add action: {action_name}"),
+    };
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::StateAdd { index, amount })),
+    )))
+}
+
+pub(crate) fn parse_state_toggle(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    const ERR_MSG: &str = "state-toggle expects 1 parameter: <variable name>";
+    if ac_params.len() != 1 {
+        bail!("{ERR_MSG}, found {}", ac_params.len());
+    }
+    let index = parse_state_var_index(&ac_params[0], s)?;
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::StateToggle(index))),
+    )))
+}
+
+/// Parse the parameters of the `state` item of `switch`: `<variable name> <comparator> <value>`.
+pub(crate) fn parse_state_comparison(
+    expr: &SExpr,
+    params: &[SExpr],
+    s: &ParserState,
+) -> Result<(u16, Comparator, i16)> {
+    if params.len() != 3 {
+        bail_expr!(
+            expr,
+            "state must have 3 parameters: <variable name> <comparator> <value>"
+        );
+    }
+    let index = parse_state_var_index(&params[0], s)?;
+    let comparator = match params[1].atom(s.vars()) {
+        Some("=" | "eq") => Comparator::Equal,
+        Some("!=" | "ne") => Comparator::NotEqual,
+        Some("<" | "lt") => Comparator::LessThan,
+        Some(">" | "gt") => Comparator::GreaterThan,
+        Some("<=" | "le") => Comparator::LessThanOrEqual,
+        Some(">=" | "ge") => Comparator::GreaterThanOrEqual,
+        _ => bail_expr!(
+            &params[1],
+            "comparator must be one of: = != < > <= >= (or: eq ne lt gt le ge)"
+        ),
+    };
+    let value = parse_state_value(&params[2], s)?;
+    Ok((index, comparator, value))
+}
@@ -14,6 +14,10 @@ fn mouse_inputs_most_care_about_are_considered_buttons() {
         MouseWheelDown,
         MouseWheelLeft,
         MouseWheelRight,
+        MouseMoveUp,
+        MouseMoveDown,
+        MouseMoveLeft,
+        MouseMoveRight,
         BTN_LEFT,
         BTN_RIGHT,
         BTN_MIDDLE,
@@ -55,7 +55,7 @@ pub(crate) fn add_chordsv2_output_for_key_pos(
         return;
     };
     for chord in chords_for_key.chords.iter() {
-        if !chord.disabled_layers.contains(&(layer_idx as u16)) {
+        if chord.is_enabled_on_layer(layer_idx as u16) {
             add_key_output_from_action_to_key_pos(osc_slot, chord.action, outputs, overrides);
         }
     }
@@ -94,8 +94,12 @@ pub(crate) fn add_key_output_from_action_to_key_pos(
                 add_key_output_from_action_to_key_pos(osc_slot, ac, outputs, overrides);
             }
         }
-        Action::TapDance(TapDance { actions, .. }) => {
-            for ac in actions.iter() {
+        Action::TapDance(TapDance {
+            actions,
+            hold_actions,
+            ..
+        }) => {
+            for ac in actions.iter().chain(hold_actions.iter()) {
                 add_key_output_from_action_to_key_pos(osc_slot, ac, outputs, overrides);
             }
         }
@@ -17,6 +17,17 @@ pub struct OverrideStates {
     mods_pressed: u8,
     oscs_to_remove: Vec<OsCode>,
     oscs_to_add: Vec<OsCode>,
+    active_actions: Vec<ActiveActionOverride>,
+    actions_to_press: Vec<u16>,
+    actions_to_release: Vec<u16>,
+}
+
+/// An action override whose trigger key is still being held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct ActiveActionOverride {
+    trigger: OsCode,
+    mod_mask: u8,
+    vkey_idx: u16,
 }
 
 impl Default for OverrideStates {
@@ -31,6 +42,9 @@ impl OverrideStates {
             mods_pressed: 0,
             oscs_to_add: Vec::new(),
             oscs_to_remove: Vec::new(),
+            active_actions: Vec::new(),
+            actions_to_press: Vec::new(),
+            actions_to_release: Vec::new(),
         }
     }
 
@@ -64,16 +78,39 @@ impl OverrideStates {
     pub fn removed_oscs(&self) -> impl Iterator<Item = OsCode> + '_ {
         self.oscs_to_remove.iter().copied()
     }
+
+    /// Fake key indices of action overrides that became active in the latest update.
+    pub fn action_overrides_to_press(&self) -> impl Iterator<Item = u16> + '_ {
+        self.actions_to_press.iter().copied()
+    }
+
+    /// Fake key indices of action overrides that became inactive in the latest update.
+    pub fn action_overrides_to_release(&self) -> impl Iterator<Item = u16> + '_ {
+        self.actions_to_release.iter().copied()
+    }
+
+    /// Fake key indices of the action overrides that are currently active.
+    pub fn active_action_overrides(&self) -> impl Iterator<Item = u16> + '_ {
+        self.active_actions.iter().map(|active| active.vkey_idx)
+    }
+
+    fn is_key_suppressed_by_action(&self, osc: OsCode) -> bool {
+        self.active_actions.iter().any(|active| {
+            active.trigger == osc
+                || mask_for_key(osc).is_some_and(|mask| mask & active.mod_mask != 0)
+        })
+    }
 }
 
 /// A collection of global key overrides.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Overrides {
     overrides_by_osc: HashMap<OsCode, Vec<Override>>,
+    action_overrides_by_osc: HashMap<OsCode, Vec<ActionOverride>>,
 }
 
 impl Overrides {
-    pub fn new(overrides: &[Override]) -> Self {
+    pub fn new(overrides: &[Override], action_overrides: &[ActionOverride]) -> Self {
         let mut overrides_by_osc: HashMap<OsCode, Vec<Override>> = HashMap::default();
         for o in overrides.iter() {
             overrides_by_osc
@@ -85,10 +122,71 @@ impl Overrides {
             ovds.shrink_to_fit();
         }
         overrides_by_osc.shrink_to_fit();
-        Self { overrides_by_osc }
+        let mut action_overrides_by_osc: HashMap<OsCode, Vec<ActionOverride>> = HashMap::default();
+        for o in action_overrides.iter() {
+            action_overrides_by_osc
+                .entry(o.in_non_mod_osc)
+                .or_default()
+                .push(o.clone());
+        }
+        action_overrides_by_osc.shrink_to_fit();
+        Self {
+            overrides_by_osc,
+            action_overrides_by_osc,
+        }
+    }
+
+    /// Activate action overrides whose trigger key newly appears in `kcs` with the required
+    /// modifiers held, and deactivate those whose trigger key is no longer in `kcs`.
+    ///
+    /// The fake keys to press and release are available afterwards from `states`.
+    pub fn update_action_overrides(
+        &self,
+        kcs: &[KeyCode],
+        layer: u16,
+        states: &mut OverrideStates,
+    ) {
+        states.actions_to_press.clear();
+        states.actions_to_release.clear();
+        if self.action_overrides_by_osc.is_empty() && states.active_actions.is_empty() {
+            return;
+        }
+        states.active_actions.retain(|active| {
+            let held = kcs.iter().any(|kc| OsCode::from(*kc) == active.trigger);
+            if !held {
+                states.actions_to_release.push(active.vkey_idx);
+            }
+            held
+        });
+        let mods_pressed = kcs
+            .iter()
+            .filter_map(|kc| mask_for_key((*kc).into()))
+            .fold(0, |mask, m| mask | m);
+        for osc in kcs.iter().copied().map(OsCode::from) {
+            if states.active_actions.iter().any(|a| a.trigger == osc) {
+                continue;
+            }
+            let Some(ovd) = self.action_overrides_by_osc.get(&osc).and_then(|ovds| {
+                ovds.iter()
+                    .filter(|ovd| ovd.is_active(mods_pressed, layer))
+                    .max_by_key(|ovd| ovd.in_mod_oscs.len())
+            }) else {
+                continue;
+            };
+            log::debug!("activating action override {ovd:?}");
+            states.actions_to_press.push(ovd.vkey_idx);
+            states.active_actions.push(ActiveActionOverride {
+                trigger: osc,
+                mod_mask: ovd.get_mod_mask(),
+                vkey_idx: ovd.vkey_idx,
+            });
+        }
     }
 
     pub fn override_keys(&self, kcs: &mut Vec<KeyCode>, states: &mut OverrideStates) {
+        if !states.active_actions.is_empty() {
+            kcs.retain(|kc| !states.is_key_suppressed_by_action((*kc).into()));
+        }
         if self.is_empty() {
             return;
         }
@@ -233,6 +331,71 @@ impl Override {
     }
 }
 
+/// A global override that activates an action instead of outputting keys.
+///
+/// The action lives in a hidden slot of the fake key row, after all virtual keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionOverride {
+    in_non_mod_osc: OsCode,
+    in_mod_oscs: Vec<OsCode>,
+    negative_mod_mask: u8,
+    layers: Vec<u16>,
+    vkey_idx: u16,
+}
+
+impl ActionOverride {
+    pub fn try_new(
+        in_oscs: &[OsCode],
+        negative_mod_oscs: &[OsCode],
+        layers: Vec<u16>,
+        vkey_idx: u16,
+    ) -> Result<Self> {
+        let mut in_nmoscs = in_oscs
+            .iter()
+            .copied()
+            .filter(|osc| mask_for_key(*osc).is_none());
+        let in_non_mod_osc = in_nmoscs.next().ok_or_else(|| {
+            anyhow!("override must contain exactly one input non-modifier key; found none")
+        })?;
+        if in_nmoscs.next().is_some() {
+            bail!("override must contain exactly one input non-modifier key; found multiple");
+        }
+        let mut in_mod_oscs = in_oscs
+            .iter()
+            .copied()
+            .filter(|osc| mask_for_key(*osc).is_some())
+            .collect::<Vec<_>>();
+        in_mod_oscs.shrink_to_fit();
+        let mut negative_mod_mask = 0;
+        for osc in negative_mod_oscs.iter().copied() {
+            negative_mod_mask |= mask_for_key(osc)
+                .ok_or_else(|| anyhow!("negative modifiers must only contain modifier keys"))?;
+        }
+        Ok(Self {
+            in_non_mod_osc,
+            in_mod_oscs,
+            negative_mod_mask,
+            layers,
+            vkey_idx,
+        })
+    }
+
+    fn get_mod_mask(&self) -> u8 {
+        let mut mask = 0;
+        for osc in self.in_mod_oscs.iter().copied() {
+            mask |= mask_for_key(osc).expect("mod only");
+        }
+        mask
+    }
+
+    fn is_active(&self, mods_pressed: u8, layer: u16) -> bool {
+        let mask = self.get_mod_mask();
+        mask & mods_pressed == mask
+            && self.negative_mod_mask & mods_pressed == 0
+            && (self.layers.is_empty() || self.layers.contains(&layer))
+    }
+}
+
 fn mask_for_key(osc: OsCode) -> Option<u8> {
     match osc {
         OsCode::KEY_LEFTCTRL => Some(1 << 0),
@@ -1,45 +1,134 @@
 use crate::cfg::*;
 use crate::*;
 
+use std::collections::BTreeMap;
+
 pub(crate) const DEFLAYER_ICON: [&str; 3] = ["icon", "🖻", "🖼"];
-pub(crate) type LayerIcons = HashMap<String, Option<String>>;
+pub(crate) const DEFLAYER_TIMEOUT: &str = "timeout";
+pub(crate) const DEFLAYER_ON_ENTER: &str = "on-enter";
+pub(crate) const DEFLAYER_ON_EXIT: &str = "on-exit";
+pub(crate) const DEFLAYER_DESCRIPTION: &str = "description";
+/// Prefix of options that are free-form metadata with a name chosen by the user.
+pub(crate) const DEFLAYER_METADATA_PREFIX: &str = "meta-";
+pub(crate) const DEFLAYER_COLOR: [&str; 2] = ["color", "colour"];
+pub(crate) const DEFLAYER_LED: [&str; 3] = ["led-num", "led-caps", "led-scroll"];
+pub(crate) const DEFLAYER_MOUSE_SPEED: &str = "mouse-speed";
+pub(crate) type LayerOptsByName = HashMap<String, LayerOpts>;
+
+/// Options written in parentheses after the layer name of `deflayer` and `deflayermap`.
+#[derive(Debug, Default, Clone)]
+pub struct LayerOpts {
+    pub icon: Option<String>,
+    pub timeout: Option<u32>,
+    /// Actions to activate when the layer becomes, or stops being, the current layer. These are
+    /// parsed with [`parse_layer_change_action`] once aliases are known.
+    pub on_enter: Option<SExpr>,
+    pub on_exit: Option<SExpr>,
+    /// Free-form information about the layer from `description`, `color` and options starting
+    /// with `meta-`.
+    pub metadata: BTreeMap<String, String>,
+    pub leds: LedOverrides,
+    pub mouse_speed: Option<u16>,
+}
 
-pub fn parse_layer_opts(list: &[SExpr]) -> Result<HashMap<String, String>> {
-    let mut layer_opts: HashMap<String, String> = HashMap::default();
+pub fn parse_layer_opts(list: &[SExpr]) -> Result<LayerOpts> {
+    let mut layer_opts = LayerOpts::default();
+    let mut seen_keys: HashSet<&str> = HashSet::default();
     let mut opts = list.chunks_exact(2);
     for kv in opts.by_ref() {
         let key_expr = &kv[0];
         let val_expr = &kv[1];
         // Read k-v pairs from the configuration
-        // todo: add hashmap for future options, currently only parse icons
-        let opt_key = key_expr.atom(None)
-            .ok_or_else(|| anyhow_expr!(key_expr, "No lists are allowed in {DEFLAYER} options"))
-            .and_then(|opt_key| {
-                if DEFLAYER_ICON.iter().any(|&i| i == opt_key) {
-                    if layer_opts.contains_key(DEFLAYER_ICON[0]) {
-                        // separate dupe check since multi-keys are stored
-                        // with one "canonical" repr, so '🖻' → 'icon'
-                        // and this info will be lost after the loop
-                        bail_expr!(
-                            key_expr,
-                            "Duplicate option found in {DEFLAYER}: {opt_key}, one of {DEFLAYER_ICON:?} already exists"
-                        );
+        let opt_key = key_expr
+            .atom(None)
+            .ok_or_else(|| anyhow_expr!(key_expr, "No lists are allowed in {DEFLAYER} options"))?;
+        // Options with several names are stored with one "canonical" name, e.g. '🖻' → 'icon',
+        // so that duplicates are detected regardless of the name used.
+        let canonical_key = match opt_key {
+            k if DEFLAYER_ICON.contains(&k) => DEFLAYER_ICON[0],
+            k if DEFLAYER_COLOR.contains(&k) => DEFLAYER_COLOR[0],
+            DEFLAYER_TIMEOUT => DEFLAYER_TIMEOUT,
+            DEFLAYER_ON_ENTER => DEFLAYER_ON_ENTER,
+            DEFLAYER_ON_EXIT => DEFLAYER_ON_EXIT,
+            DEFLAYER_DESCRIPTION => DEFLAYER_DESCRIPTION,
+            DEFLAYER_MOUSE_SPEED => DEFLAYER_MOUSE_SPEED,
+            k if DEFLAYER_LED.contains(&k) => k,
+            k if k
+                .strip_prefix(DEFLAYER_METADATA_PREFIX)
+                .is_some_and(|name| !name.is_empty()) =>
+            {
+                k
+            }
+            _ => bail_expr!(
+                key_expr,
+                "Invalid option in {DEFLAYER}: {opt_key}, expected one of {:?} \
+                or a name starting with {DEFLAYER_METADATA_PREFIX}",
+                [
+                    DEFLAYER_ICON[0],
+                    DEFLAYER_TIMEOUT,
+                    DEFLAYER_ON_ENTER,
+                    DEFLAYER_ON_EXIT,
+                    DEFLAYER_DESCRIPTION,
+                    DEFLAYER_COLOR[0],
+                    DEFLAYER_LED[0],
+                    DEFLAYER_LED[1],
+                    DEFLAYER_LED[2],
+                    DEFLAYER_MOUSE_SPEED,
+                ]
+            ),
+        };
+        if !seen_keys.insert(canonical_key) {
+            bail_expr!(key_expr, "Duplicate option found in {DEFLAYER}: {opt_key}");
+        }
+        match canonical_key {
+            DEFLAYER_ON_ENTER => layer_opts.on_enter = Some(val_expr.clone()),
+            DEFLAYER_ON_EXIT => layer_opts.on_exit = Some(val_expr.clone()),
+            _ => {
+                let opt_val = val_expr
+                    .atom(None)
+                    .ok_or_else(|| {
+                        anyhow_expr!(
+                            val_expr,
+                            "No lists are allowed in {DEFLAYER}'s {opt_key} option value"
+                        )
+                    })?
+                    .trim_atom_quotes()
+                    .to_owned();
+                match canonical_key {
+                    DEFLAYER_TIMEOUT => {
+                        let ms = opt_val.parse::<u32>().ok().filter(|ms| *ms > 0);
+                        if ms.is_none() {
+                            bail_expr!(
+                                val_expr,
+                                "{DEFLAYER_TIMEOUT} must be 1-4294967295 milliseconds"
+                            );
+                        }
+                        layer_opts.timeout = ms;
+                    }
+                    DEFLAYER_MOUSE_SPEED => {
+                        let speed = opt_val.parse::<u16>().ok().filter(|speed| *speed > 0);
+                        if speed.is_none() {
+                            bail_expr!(val_expr, "{DEFLAYER_MOUSE_SPEED} must be 1-65535 percent");
+                        }
+                        layer_opts.mouse_speed = speed;
+                    }
+                    k if k == DEFLAYER_ICON[0] => layer_opts.icon = Some(opt_val),
+                    k if DEFLAYER_LED.contains(&k) => {
+                        let led = str_to_led(k.trim_start_matches("led-")).expect("valid LED");
+                        layer_opts.leds[led.index()] = Some(match opt_val.as_str() {
+                            "on" => true,
+                            "off" => false,
+                            _ => bail_expr!(val_expr, "{opt_key} must be on or off"),
+                        });
+                    }
+                    _ => {
+                        layer_opts
+                            .metadata
+                            .insert(canonical_key.to_owned(), opt_val);
                     }
-                    Ok(DEFLAYER_ICON[0])
-                } else {
-                    bail_expr!(key_expr, "Invalid option in {DEFLAYER}: {opt_key}, expected one of {DEFLAYER_ICON:?}")
                 }
-            })?;
-        if layer_opts.contains_key(opt_key) {
-            bail_expr!(key_expr, "Duplicate option found in {DEFLAYER}: {opt_key}");
+            }
         }
-        let opt_val = val_expr.atom(None).ok_or_else(|| {
-            anyhow_expr!(
-                val_expr,
-                "No lists are allowed in {DEFLAYER}'s option values"
-            )
-        })?;
-        layer_opts.insert(opt_key.to_owned(), opt_val.to_owned());
     }
     let rem = opts.remainder();
     if !rem.is_empty() {
@@ -47,3 +136,11 @@ pub fn parse_layer_opts(list: &[SExpr]) -> Result<HashMap<String, String>> {
     }
     Ok(layer_opts)
 }
+
+/// Parse an `on-enter` or `on-exit` action and reserve a fake key to activate it with.
+pub(crate) fn parse_layer_change_action(expr: &SExpr, s: &ParserState) -> Result<Coord> {
+    let action = parse_action(expr, s)?;
+    let y = push_fake_row_action(expr, action, s)?;
+    let (x, y) = get_fake_key_coords(y);
+    Ok(Coord { x, y })
+}
@@ -0,0 +1,47 @@
+//! Parsing of the `set-leds` action, which changes the keyboard LEDs. LEDs can also be set by
+//! the `led-*` options of `deflayer` and the `led-*` indicator options of `defcfg`.
+//!
+//! Example:
+//!
+//! (defcfg led-caps-word caps)
+//! (deflayer (nav led-scroll on) ...)
+//! (defalias nl (set-leds num toggle scroll auto))
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail, bail_expr};
+
+pub(crate) fn str_to_led(s: &str) -> Option<Led> {
+    Led::ALL.into_iter().find(|led| led.to_string() == s)
+}
+
+pub(crate) fn parse_set_leds(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    const ERR_MSG: &str = "set-leds expects pairs of: <caps|num|scroll> <on|off|toggle|auto>";
+    if ac_params.is_empty() || !ac_params.len().is_multiple_of(2) {
+        bail!("{ERR_MSG}, found {} parameters", ac_params.len());
+    }
+    let mut settings: Vec<(Led, LedSetting)> = vec![];
+    for pair in ac_params.chunks_exact(2) {
+        let led = pair[0]
+            .atom(s.vars())
+            .and_then(str_to_led)
+            .ok_or_else(|| anyhow_expr!(&pair[0], "{ERR_MSG}\nUnknown LED"))?;
+        if settings.iter().any(|(l, _)| *l == led) {
+            bail_expr!(&pair[0], "{ERR_MSG}\nThis LED is set more than once");
+        }
+        let setting = match pair[1].atom(s.vars()) {
+            Some("on") => LedSetting::On,
+            Some("off") => LedSetting::Off,
+            Some("toggle") => LedSetting::Toggle,
+            Some("auto") => LedSetting::Auto,
+            _ => bail_expr!(&pair[1], "{ERR_MSG}\nUnknown LED setting"),
+        };
+        settings.push((led, setting));
+    }
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::SetLeds(settings))),
+    )))
+}
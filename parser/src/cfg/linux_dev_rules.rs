@@ -0,0 +1,177 @@
+//! Parsing and matching of the `linux-dev-rules` option of `defcfg`. The rules choose which
+//! autodetected input devices kanata grabs, both at startup and when a device is plugged in.
+//! Rules are checked in order and the first rule that matches a device decides whether it is
+//! included or excluded. Devices that no rule matches fall back to the other device options.
+//!
+//! Example:
+//!
+//! (defcfg
+//!   linux-dev-rules (
+//!     (exclude name "*Consumer Control")
+//!     (include vendor 0x1234 product 0x5678)
+//!     (include bus bluetooth uniq "aa:bb:*")
+//!     (exclude phys "usb-0000:00:14.0-3*")
+//!   )
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail_expr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinuxDevRuleAction {
+    Include,
+    Exclude,
+}
+
+/// A rule of `linux-dev-rules`. Properties that are not specified match any device. The name,
+/// phys and uniq properties are glob patterns.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinuxDevRule {
+    pub action: LinuxDevRuleAction,
+    pub name: Option<String>,
+    pub vendor: Option<u16>,
+    pub product: Option<u16>,
+    pub bus: Option<u16>,
+    pub phys: Option<String>,
+    pub uniq: Option<String>,
+}
+
+/// Properties of an input device that rules are matched against. Missing string properties
+/// are empty.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinuxDevProps<'a> {
+    pub name: &'a str,
+    pub vendor: u16,
+    pub product: u16,
+    pub bus: u16,
+    pub phys: &'a str,
+    pub uniq: &'a str,
+}
+
+/// Bus names accepted by the `bus` property, with the `BUS_*` values of `linux/input.h`.
+const BUS_TYPES: [(&str, u16); 8] = [
+    ("pci", 0x01),
+    ("usb", 0x03),
+    ("bluetooth", 0x05),
+    ("virtual", 0x06),
+    ("i8042", 0x11),
+    ("rs232", 0x13),
+    ("i2c", 0x18),
+    ("host", 0x19),
+];
+
+impl LinuxDevRule {
+    pub fn matches(&self, dev: &LinuxDevProps) -> bool {
+        self.name.as_deref().is_none_or(|g| glob_match(g, dev.name))
+            && self.vendor.is_none_or(|v| v == dev.vendor)
+            && self.product.is_none_or(|p| p == dev.product)
+            && self.bus.is_none_or(|b| b == dev.bus)
+            && self.phys.as_deref().is_none_or(|g| glob_match(g, dev.phys))
+            && self.uniq.as_deref().is_none_or(|g| glob_match(g, dev.uniq))
+    }
+}
+
+/// Returns the first rule in `rules` that matches the device, if any.
+pub fn find_linux_dev_rule<'a>(
+    rules: &'a [LinuxDevRule],
+    dev: &LinuxDevProps,
+) -> Option<&'a LinuxDevRule> {
+    rules.iter().find(|rule| rule.matches(dev))
+}
+
+/// Matches `text` against a glob `pattern`, where `*` matches any sequence of characters and
+/// `?` matches a single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern and of the text it was tried against, to backtrack
+    // to when the characters after the `*` stop matching.
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+pub(crate) fn parse_linux_dev_rules(val: &SExpr, label: &str) -> Result<Vec<LinuxDevRule>> {
+    const ERRMSG: &str = "linux-dev-rules expects lists of:\n\
+        <include|exclude> [name <glob>] [vendor <id>] [product <id>] [bus <bus>] \
+        [phys <glob>] [uniq <glob>]";
+    let mut rules = vec![];
+    for entry in sexpr_to_list_or_err(val, label)? {
+        let Some((action_expr, props)) = entry.list(None).and_then(|l| l.split_first()) else {
+            bail_expr!(entry, "{ERRMSG}")
+        };
+        let action = match action_expr.atom(None) {
+            Some("include") => LinuxDevRuleAction::Include,
+            Some("exclude") => LinuxDevRuleAction::Exclude,
+            _ => bail_expr!(
+                action_expr,
+                "{ERRMSG}\nThe rule must start with include or exclude"
+            ),
+        };
+        let mut rule = LinuxDevRule {
+            action,
+            name: None,
+            vendor: None,
+            product: None,
+            bus: None,
+            phys: None,
+            uniq: None,
+        };
+        let mut seen_props: Vec<&str> = vec![];
+        let mut kvs = props.chunks_exact(2);
+        for kv in kvs.by_ref() {
+            let prop = kv[0].atom(None).unwrap_or_default();
+            if seen_props.contains(&prop) {
+                bail_expr!(&kv[0], "This property is used more than once in the rule");
+            }
+            let v = sexpr_to_str_or_err(&kv[1], prop)?;
+            match prop {
+                "name" => rule.name = Some(v.to_owned()),
+                "phys" => rule.phys = Some(v.to_owned()),
+                "uniq" => rule.uniq = Some(v.to_owned()),
+                "vendor" => rule.vendor = Some(parse_device_id_number(&kv[1], v)?),
+                "product" => rule.product = Some(parse_device_id_number(&kv[1], v)?),
+                "bus" => {
+                    rule.bus = Some(match BUS_TYPES.iter().find(|(name, _)| *name == v) {
+                        Some((_, bus)) => *bus,
+                        None => parse_device_id_number(&kv[1], v).map_err(|_| {
+                            anyhow_expr!(
+                                &kv[1],
+                                "The bus must be a number or one of: {}",
+                                BUS_TYPES.map(|(name, _)| name).join(", ")
+                            )
+                        })?,
+                    })
+                }
+                _ => bail_expr!(&kv[0], "{ERRMSG}\nUnknown rule property"),
+            }
+            seen_props.push(prop);
+        }
+        if let Some(missing_value) = kvs.remainder().first() {
+            bail_expr!(missing_value, "This property is missing a value.");
+        }
+        rules.push(rule);
+    }
+    Ok(rules)
+}
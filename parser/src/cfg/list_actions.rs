@@ -16,8 +16,11 @@ pub const TAP_HOLD_RELEASE_TIMEOUT: &str = "tap-hold-release-timeout";
 pub const TAP_HOLD_RELEASE_TIMEOUT_A: &str = "tap⬓↑timeout";
 pub const TAP_HOLD_RELEASE_KEYS: &str = "tap-hold-release-keys";
 pub const TAP_HOLD_RELEASE_KEYS_A: &str = "tap⬓↑keys";
+pub const TAP_HOLD_OPPOSITE_HAND: &str = "tap-hold-opposite-hand";
+pub const TAP_HOLD_OPPOSITE_HAND_A: &str = "tap⬓⇄hand";
 pub const TAP_HOLD_EXCEPT_KEYS: &str = "tap-hold-except-keys";
 pub const TAP_HOLD_EXCEPT_KEYS_A: &str = "tap⬓⤫keys";
+pub const TAP_HOLD_REQUIRE_PRIOR_IDLE: &str = "tap-hold-require-prior-idle";
 pub const MULTI: &str = "multi";
 pub const MACRO: &str = "macro";
 pub const MACRO_REPEAT: &str = "macro-repeat";
@@ -46,6 +49,7 @@ pub const ONE_SHOT_RELEASE_PCANCEL_A: &str = "one-shot↑⤫";
 pub const ONE_SHOT_PAUSE_PROCESSING: &str = "one-shot-pause-processing";
 pub const TAP_DANCE: &str = "tap-dance";
 pub const TAP_DANCE_EAGER: &str = "tap-dance-eager";
+pub const TAP_DANCE_HOLD: &str = "tap-dance-hold";
 pub const CHORD: &str = "chord";
 pub const RELEASE_KEY: &str = "release-key";
 pub const RELEASE_KEY_A: &str = "key↑";
@@ -87,9 +91,13 @@ pub const MOVEMOUSE_ACCEL_LEFT_A: &str = "🖱accel←";
 pub const MOVEMOUSE_ACCEL_RIGHT_A: &str = "🖱accel→";
 pub const MOVEMOUSE_SPEED_A: &str = "🖱speed";
 pub const SETMOUSE: &str = "setmouse";
+pub const DRAG_SCROLL: &str = "drag-scroll";
 pub const SETMOUSE_A: &str = "set🖱";
 pub const DYNAMIC_MACRO_RECORD: &str = "dynamic-macro-record";
 pub const DYNAMIC_MACRO_PLAY: &str = "dynamic-macro-play";
+pub const DYNAMIC_MACRO_SAVE: &str = "dynamic-macro-save";
+pub const DYNAMIC_MACRO_LOAD: &str = "dynamic-macro-load";
+pub const DYNAMIC_MACRO_CLEAR: &str = "dynamic-macro-clear";
 pub const ARBITRARY_CODE: &str = "arbitrary-code";
 pub const CMD: &str = "cmd";
 pub const CMD_LOG: &str = "cmd-log";
@@ -118,6 +126,14 @@ pub const ON_RELEASE: &str = "on-release";
 pub const ON_RELEASE_A: &str = "on↑";
 pub const ON_IDLE: &str = "on-idle";
 pub const HOLD_FOR_DURATION: &str = "hold-for-duration";
+pub const STATE_SET: &str = "state-set";
+pub const STATE_INC: &str = "state-inc";
+pub const STATE_DEC: &str = "state-dec";
+pub const STATE_TOGGLE: &str = "state-toggle";
+pub const AFTER: &str = "after";
+pub const EVERY: &str = "every";
+pub const TIMER_CANCEL: &str = "timer-cancel";
+pub const SET_LEDS: &str = "set-leds";
 
 pub fn is_list_action(ac: &str) -> bool {
     const LIST_ACTIONS: &[&str] = &[
@@ -135,8 +151,11 @@ pub fn is_list_action(ac: &str) -> bool {
         TAP_HOLD_RELEASE_TIMEOUT_A,
         TAP_HOLD_RELEASE_KEYS,
         TAP_HOLD_RELEASE_KEYS_A,
+        TAP_HOLD_OPPOSITE_HAND,
+        TAP_HOLD_OPPOSITE_HAND_A,
         TAP_HOLD_EXCEPT_KEYS,
         TAP_HOLD_EXCEPT_KEYS_A,
+        TAP_HOLD_REQUIRE_PRIOR_IDLE,
         MULTI,
         MACRO,
         MACRO_REPEAT,
@@ -158,6 +177,7 @@ pub fn is_list_action(ac: &str) -> bool {
         ONE_SHOT_RELEASE_PCANCEL_A,
         TAP_DANCE,
         TAP_DANCE_EAGER,
+        TAP_DANCE_HOLD,
         CHORD,
         RELEASE_KEY,
         RELEASE_KEY_A,
@@ -200,8 +220,12 @@ pub fn is_list_action(ac: &str) -> bool {
         MOVEMOUSE_SPEED_A,
         SETMOUSE,
         SETMOUSE_A,
+        DRAG_SCROLL,
         DYNAMIC_MACRO_RECORD,
         DYNAMIC_MACRO_PLAY,
+        DYNAMIC_MACRO_SAVE,
+        DYNAMIC_MACRO_LOAD,
+        DYNAMIC_MACRO_CLEAR,
         ARBITRARY_CODE,
         CMD,
         CMD_OUTPUT_KEYS,
@@ -235,6 +259,14 @@ pub fn is_list_action(ac: &str) -> bool {
         MACRO_CANCEL_ON_NEXT_PRESS_CANCEL_ON_RELEASE,
         MACRO_REPEAT_CANCEL_ON_NEXT_PRESS_CANCEL_ON_RELEASE,
         ONE_SHOT_PAUSE_PROCESSING,
+        STATE_SET,
+        STATE_INC,
+        STATE_DEC,
+        STATE_TOGGLE,
+        AFTER,
+        EVERY,
+        TIMER_CANCEL,
+        SET_LEDS,
     ];
     LIST_ACTIONS.contains(&ac)
 }
@@ -58,15 +58,38 @@ use layer_opts::*;
 pub mod list_actions;
 use list_actions::*;
 
+mod context;
+pub use context::*;
+
 mod defcfg;
 pub use defcfg::*;
 
+mod defdevice;
+pub use defdevice::*;
+
+mod defhands;
+pub use defhands::*;
+
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+mod linux_dev_rules;
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+pub use linux_dev_rules::*;
+
+mod leds;
+use leds::*;
+
+mod defstate;
+pub use defstate::*;
+
 mod deftemplate;
 pub use deftemplate::*;
 
 mod switch;
 pub use switch::*;
 
+mod timer;
+use timer::*;
+
 use crate::custom_action::*;
 use crate::keys::*;
 use crate::layers::*;
@@ -217,11 +240,11 @@ pub type KanataCustom = &'static &'static [&'static CustomAction];
 pub type KanataAction = Action<'static, KanataCustom>;
 type KLayout = Layout<'static, KEYS_IN_ROW, 2, KanataCustom>;
 
-type TapHoldCustomFunc =
-    fn(
-        &[OsCode],
-        &Allocations,
-    ) -> &'static (dyn Fn(QueuedIter) -> (Option<WaitingAction>, bool) + Send + Sync);
+type TapHoldCustomFunc = fn(
+    &[OsCode],
+    &Allocations,
+)
+    -> &'static (dyn Fn(QueuedIter) -> CustomHoldTapDecision + Send + Sync);
 
 pub type BorrowedKLayout<'a> = Layout<'a, KEYS_IN_ROW, 2, &'a &'a [&'a CustomAction]>;
 pub type KeySeqsToFKeys = Trie<(u8, u16)>;
@@ -240,13 +263,13 @@ impl KanataLayout {
     }
 
     /// bm stands for borrow mut.
-    pub fn bm(&mut self) -> &mut BorrowedKLayout {
+    pub fn bm(&mut self) -> &mut BorrowedKLayout<'_> {
         // shrink the lifetime
         unsafe { std::mem::transmute(&mut self.layout) }
     }
 
     /// b stands for borrow.
-    pub fn b(&self) -> &BorrowedKLayout {
+    pub fn b(&self) -> &BorrowedKLayout<'_> {
         // shrink the lifetime
         unsafe { std::mem::transmute(&self.layout) }
     }
@@ -276,6 +299,12 @@ pub struct Cfg {
     pub switch_max_key_timing: u16,
     /// Zipchord-like configuration.
     pub zippy: Option<(ZchPossibleChords, ZchConfig)>,
+    /// Input devices defined in `defdevice`.
+    pub input_devices: Vec<InputDeviceCfg>,
+    /// Context conditions used by `switch` and `defcontextlayers`.
+    pub contexts: ContextConditions,
+    /// Variables defined in `defstate`, in index order.
+    pub state_vars: Vec<StateVarCfg>,
 }
 
 /// Parse a new configuration from a file.
@@ -315,6 +344,8 @@ pub fn new_from_str(cfg_text: &str, file_content: HashMap<String, String>) -> MR
     layout.bm().chords_v2 = icfg.chords_v2;
     layout.bm().quick_tap_hold_timeout = icfg.options.concurrent_tap_hold;
     layout.bm().oneshot.pause_input_processing_delay = icfg.options.rapid_event_delay;
+    layout.bm().input_device_layers = input_device_layers(&icfg.input_devices, &s.a);
+    layout.bm().variables = icfg.state_vars.iter().map(|v| v.initial).collect();
     let mut fake_keys: HashMap<String, usize> = s
         .virtual_keys
         .iter()
@@ -333,16 +364,40 @@ pub fn new_from_str(cfg_text: &str, file_content: HashMap<String, String>) -> MR
         fake_keys,
         switch_max_key_timing,
         zippy: icfg.zippy,
+        input_devices: icfg.input_devices,
+        contexts: icfg.contexts,
+        state_vars: icfg.state_vars,
     })
 }
 
 pub type MappedKeys = HashSet<OsCode>;
 
+/// Returns the layer bound to each input device, indexed by device identifier.
+fn input_device_layers(devices: &[InputDeviceCfg], a: &Allocations) -> &'static [Option<u16>] {
+    let mut layers = vec![None; devices.len() + 1];
+    for dev in devices.iter() {
+        layers[usize::from(dev.id)] = dev.layer.map(|l| l as u16);
+    }
+    a.sref_vec(layers)
+}
+
 #[derive(Debug)]
 pub struct LayerInfo {
     pub name: String,
     pub cfg_text: String,
     pub icon: Option<String>,
+    /// Milliseconds of inactivity after which the layer falls back to the base layer.
+    pub timeout: Option<u32>,
+    /// Fake keys that are tapped when the layer becomes, or stops being, the current layer.
+    pub on_enter: Option<Coord>,
+    pub on_exit: Option<Coord>,
+    /// Free-form information about the layer from its `description`, `color` and `meta-` options.
+    pub metadata: std::collections::BTreeMap<String, String>,
+    /// LED states while the layer is the current layer.
+    pub leds: LedOverrides,
+    /// Percentage by which the mouse movement of input devices is scaled while the layer is the
+    /// current layer.
+    pub mouse_speed: Option<u16>,
 }
 
 #[allow(clippy::type_complexity)] // return type is not pub
@@ -364,6 +419,8 @@ fn parse_cfg(p: &Path) -> MResult<Cfg> {
     layout.bm().chords_v2 = icfg.chords_v2;
     layout.bm().quick_tap_hold_timeout = icfg.options.concurrent_tap_hold;
     layout.bm().oneshot.pause_input_processing_delay = icfg.options.rapid_event_delay;
+    layout.bm().input_device_layers = input_device_layers(&icfg.input_devices, &s.a);
+    layout.bm().variables = icfg.state_vars.iter().map(|v| v.initial).collect();
     if let Some(s) = icfg.start_action {
         layout.bm().action_queue.push_front(Some(((1, 0), 0, s)));
     }
@@ -385,6 +442,9 @@ fn parse_cfg(p: &Path) -> MResult<Cfg> {
         fake_keys,
         switch_max_key_timing,
         zippy: icfg.zippy,
+        input_devices: icfg.input_devices,
+        contexts: icfg.contexts,
+        state_vars: icfg.state_vars,
     })
 }
 
@@ -422,6 +482,9 @@ pub struct IntermediateCfg {
     pub chords_v2: Option<ChordsV2<'static, KanataCustom>>,
     pub start_action: Option<&'static KanataAction>,
     pub zippy: Option<(ZchPossibleChords, ZchConfig)>,
+    pub input_devices: Vec<InputDeviceCfg>,
+    pub contexts: ContextConditions,
+    pub state_vars: Vec<StateVarCfg>,
 }
 
 // A snapshot of enviroment variables, or an error message with an explanation
@@ -687,7 +750,6 @@ pub fn parse_cfg_raw_string(
     let layer_exprs = spanned_root_exprs
         .iter()
         .filter(deflayer_spanned_filter)
-        .cloned()
         .map(|e| match e.t[0].atom(None).unwrap() {
             DEFLAYER => SpannedLayerExprs::DefsrcMapping(e.clone()),
             DEFLAYER_MAPPED => SpannedLayerExprs::CustomMapping(e.clone()),
@@ -698,7 +760,7 @@ pub fn parse_cfg_raw_string(
         bail!("No deflayer expressions exist. At least one layer must be defined.")
     }
 
-    let (layer_idxs, layer_icons) =
+    let (layer_idxs, layer_opts) =
         parse_layer_indexes(&layer_exprs, mapping_order.len(), &vars, &mut lsp_hints)?;
     let mut sorted_idxs: Vec<(&String, &usize)> =
         layer_idxs.iter().map(|tuple| (tuple.0, tuple.1)).collect();
@@ -719,13 +781,22 @@ pub fn parse_cfg_raw_string(
         .map(|expr| expr.span.file_content()[expr.span.clone()].to_string())
         .collect::<Vec<_>>();
 
-    let layer_info: Vec<LayerInfo> = layer_names
+    let mut layer_info: Vec<LayerInfo> = layer_names
         .into_iter()
         .zip(layer_strings)
-        .map(|(name, cfg_text)| LayerInfo {
-            name: name.clone(),
-            cfg_text,
-            icon: layer_icons.get(&name).unwrap_or(&None).clone(),
+        .map(|(name, cfg_text)| {
+            let opts = layer_opts.get(&name).cloned().unwrap_or_default();
+            LayerInfo {
+                name,
+                cfg_text,
+                icon: opts.icon,
+                timeout: opts.timeout,
+                on_enter: None,
+                on_exit: None,
+                metadata: opts.metadata,
+                leds: opts.leds,
+                mouse_speed: opts.mouse_speed,
+            }
         })
         .collect();
 
@@ -744,7 +815,6 @@ pub fn parse_cfg_raw_string(
     let layer_exprs = root_exprs
         .iter()
         .filter(deflayer_filter)
-        .cloned()
         .map(|e| match e[0].atom(None).unwrap() {
             DEFLAYER => LayerExprs::DefsrcMapping(e.clone()),
             DEFLAYER_MAPPED => LayerExprs::CustomMapping(e.clone()),
@@ -777,28 +847,63 @@ pub fn parse_cfg_raw_string(
         delegate_to_first_layer: cfg.delegate_to_first_layer,
         default_sequence_timeout: cfg.sequence_timeout,
         default_sequence_input_mode: cfg.sequence_input_mode,
+        default_tap_hold_require_prior_idle: cfg.tap_hold_require_prior_idle,
         block_unmapped_keys: cfg.block_unmapped_keys,
         lsp_hints: RefCell::new(lsp_hints),
         vars,
         ..Default::default()
     };
 
-    let chords_exprs = spanned_root_exprs
-        .iter()
-        .filter(gen_first_atom_filter_spanned("defchords"))
-        .collect::<Vec<_>>();
-    parse_chord_groups(&chords_exprs, s)?;
-
     let fake_keys_exprs = root_exprs
         .iter()
         .filter(gen_first_atom_filter("deffakekeys"))
         .collect::<Vec<_>>();
-    parse_fake_keys(&fake_keys_exprs, s)?;
-
     let vkeys_exprs = root_exprs
         .iter()
         .filter(gen_first_atom_filter("defvirtualkeys"))
         .collect::<Vec<_>>();
+    // Actions such as timers can be parsed before all of the virtual keys, e.g. within
+    // defvirtualkeys itself, so count the virtual keys up front to know where the fake row slots
+    // that come after them start.
+    s.fake_row_start = fake_keys_exprs
+        .iter()
+        .chain(vkeys_exprs.iter())
+        .map(|expr| expr.len().saturating_sub(1).div_ceil(2))
+        .sum();
+
+    let device_exprs = spanned_root_exprs
+        .iter()
+        .filter(gen_first_atom_filter_spanned("defdevice"))
+        .collect::<Vec<_>>();
+    let input_devices = parse_defdevices(&device_exprs, s)?;
+
+    if let Some(hands_expr) = root_exprs.iter().find(gen_first_atom_filter("defhands")) {
+        parse_defhands(hands_expr, s)?;
+    }
+    if let Some(spanned) = spanned_root_exprs
+        .iter()
+        .filter(gen_first_atom_filter_spanned("defhands"))
+        .nth(1)
+    {
+        bail_span!(
+            spanned,
+            "Only one defhands is allowed, found more. Delete the extras."
+        )
+    }
+
+    let state_exprs = root_exprs
+        .iter()
+        .filter(gen_first_atom_filter("defstate"))
+        .collect::<Vec<_>>();
+    let state_vars = parse_defstates(&state_exprs, s)?;
+
+    let chords_exprs = spanned_root_exprs
+        .iter()
+        .filter(gen_first_atom_filter_spanned("defchords"))
+        .collect::<Vec<_>>();
+    parse_chord_groups(&chords_exprs, s)?;
+
+    parse_fake_keys(&fake_keys_exprs, s)?;
     parse_virtual_keys(&vkeys_exprs, s)?;
 
     let sequence_exprs = root_exprs
@@ -821,7 +926,40 @@ pub fn parse_cfg_raw_string(
         bail!("alias-to-trigger-on-load was given, but alias could not be found")
     }
 
+    let overrides_v2_exprs = root_exprs
+        .iter()
+        .filter(gen_first_atom_filter("defoverridesv2"))
+        .collect::<Vec<_>>();
+    let action_overrides = match overrides_v2_exprs.len() {
+        0 => vec![],
+        1 => parse_overrides_v2(overrides_v2_exprs[0], s)?,
+        _ => {
+            let spanned = spanned_root_exprs
+                .iter()
+                .filter(gen_first_atom_filter_spanned("defoverridesv2"))
+                .nth(1)
+                .expect("> 2 overrides");
+            bail_span!(
+                spanned,
+                "Only one defoverridesv2 allowed, found more. Delete the extras."
+            )
+        }
+    };
+
+    for info in layer_info.iter_mut() {
+        let Some(opts) = layer_opts.get(&info.name) else {
+            continue;
+        };
+        if let Some(expr) = &opts.on_enter {
+            info.on_enter = Some(parse_layer_change_action(expr, s)?);
+        }
+        if let Some(expr) = &opts.on_exit {
+            info.on_exit = Some(parse_layer_change_action(expr, s)?);
+        }
+    }
+
     let mut klayers = parse_layers(s, &mut mapped_keys, &cfg)?;
+    place_fake_row_actions(&mut klayers, s);
 
     resolve_chord_groups(&mut klayers, s)?;
     let layers = s.a.bref_slice(klayers);
@@ -831,8 +969,8 @@ pub fn parse_cfg_raw_string(
         .filter(gen_first_atom_filter("defoverrides"))
         .collect::<Vec<_>>();
     let overrides = match override_exprs.len() {
-        0 => Overrides::new(&[]),
-        1 => parse_overrides(override_exprs[0], s)?,
+        0 => Overrides::new(&[], &action_overrides),
+        1 => Overrides::new(&parse_overrides(override_exprs[0], s)?, &action_overrides),
         _ => {
             let spanned = spanned_root_exprs
                 .iter()
@@ -914,6 +1052,27 @@ pub fn parse_cfg_raw_string(
         }
     };
 
+    let context_layers_exprs = root_exprs
+        .iter()
+        .filter(gen_first_atom_filter("defcontextlayers"))
+        .collect::<Vec<_>>();
+    let context_layer_rules = match context_layers_exprs.len() {
+        0 => vec![],
+        1 => parse_context_layers(context_layers_exprs[0], s)?,
+        _ => {
+            let spanned = spanned_root_exprs
+                .iter()
+                .filter(gen_first_atom_filter_spanned("defcontextlayers"))
+                .nth(1)
+                .expect("> 2 defcontextlayers");
+            bail_span!(
+                spanned,
+                "Only one defcontextlayers allowed, found more.\nDelete the extras."
+            )
+        }
+    };
+    let contexts = build_context_conditions(context_layer_rules, s);
+
     #[cfg(feature = "lsp")]
     LSP_VARIABLE_REFERENCES.with_borrow_mut(|refs| {
         s.lsp_hints
@@ -935,6 +1094,9 @@ pub fn parse_cfg_raw_string(
         chords_v2,
         start_action,
         zippy,
+        input_devices,
+        contexts,
+        state_vars,
     })
 }
 
@@ -957,6 +1119,7 @@ fn error_on_unknown_top_level_atoms(exprs: &[Spanned<Vec<SExpr>>]) -> Result<()>
                 | DEFLAYER
                 | DEFLAYER_MAPPED
                 | "defoverrides"
+                | "defoverridesv2"
                 | "deflocalkeys-macos"
                 | "deflocalkeys-linux"
                 | "deflocalkeys-win"
@@ -970,6 +1133,10 @@ fn error_on_unknown_top_level_atoms(exprs: &[Spanned<Vec<SExpr>>]) -> Result<()>
                 | "defchordsv2"
                 | "defchordsv2-experimental"
                 | "defzippy-experimental"
+                | "defdevice"
+                | "defcontextlayers"
+                | "defstate"
+                | "defhands"
                 | "defseq" => Ok(()),
                 _ => err_span!(expr, "Found unknown configuration item"),
             })
@@ -1126,7 +1293,11 @@ fn parse_defsrc(
                 | OsCode::MouseWheelUp
                 | OsCode::MouseWheelDown
                 | OsCode::MouseWheelLeft
-                | OsCode::MouseWheelRight,
+                | OsCode::MouseWheelRight
+                | OsCode::MouseMoveUp
+                | OsCode::MouseMoveDown
+                | OsCode::MouseMoveLeft
+                | OsCode::MouseMoveRight,
             ) => MouseInDefsrc::MouseUsed,
             _ => is_mouse_used,
         };
@@ -1187,9 +1358,9 @@ fn parse_layer_indexes(
     expected_len: usize,
     vars: &HashMap<String, SExpr>,
     _lsp_hints: &mut LspHints,
-) -> Result<(LayerIndexes, LayerIcons)> {
+) -> Result<(LayerIndexes, LayerOptsByName)> {
     let mut layer_indexes = HashMap::default();
-    let mut layer_icons = HashMap::default();
+    let mut layer_opts_by_name = HashMap::default();
     for (i, expr_type) in exprs.iter().enumerate() {
         let (mut subexprs, expr, do_element_count_check, deflayer_keyword) = match expr_type {
             SpannedLayerExprs::DefsrcMapping(e) => {
@@ -1208,10 +1379,10 @@ fn parse_layer_indexes(
                 "{deflayer_keyword} requires a layer name after `{deflayer_keyword}` token"
             )
         })?;
-        let (layer_name, _layer_name_span, icon) = {
+        let (layer_name, _layer_name_span, layer_opts) = {
             let name = layer_expr.atom(Some(vars));
             match name {
-                Some(name) => (name.to_owned(), layer_expr.span(), None),
+                Some(name) => (name.to_owned(), layer_expr.span(), LayerOpts::default()),
                 None => {
                     // unwrap: this **must** be a list due to atom() call above.
                     let list = layer_expr.list(Some(vars)).unwrap();
@@ -1224,10 +1395,7 @@ fn parse_layer_indexes(
                             "layer name after {deflayer_keyword} must be a string when enclosed within one pair of parentheses"
                         ))?;
                     let layer_opts = parse_layer_opts(&list[1..])?;
-                    let icon = layer_opts
-                        .get(DEFLAYER_ICON[0])
-                        .map(|icon_s| icon_s.trim_atom_quotes().to_owned());
-                    (name.to_owned(), first.span(), icon)
+                    (name.to_owned(), first.span(), layer_opts)
                 }
             }
         };
@@ -1280,10 +1448,10 @@ fn parse_layer_indexes(
             .insert(layer_name.clone(), _layer_name_span.clone());
 
         layer_indexes.insert(layer_name.clone(), i);
-        layer_icons.insert(layer_name, icon);
+        layer_opts_by_name.insert(layer_name, layer_opts);
     }
 
-    Ok((layer_indexes, layer_icons))
+    Ok((layer_indexes, layer_opts_by_name))
 }
 
 #[derive(Debug, Clone)]
@@ -1310,8 +1478,15 @@ pub struct ParserState {
     layer_exprs: Vec<LayerExprs>,
     aliases: Aliases,
     layer_idxs: LayerIndexes,
+    input_device_idxs: HashMap<String, u8>,
+    state_var_idxs: HashMap<String, u16>,
+    hands: Option<&'static Hands>,
     mapping_order: Vec<usize>,
     virtual_keys: HashMap<String, (usize, &'static KanataAction)>,
+    /// Index of the first fake row slot after the virtual keys.
+    fake_row_start: usize,
+    fake_row_actions: RefCell<Vec<&'static KanataAction>>,
+    fake_row_actions_placed: Cell<bool>,
     chord_groups: HashMap<String, ChordGroup>,
     defsrc_layer: [KanataAction; KEYS_IN_ROW],
     vars: HashMap<String, SExpr>,
@@ -1319,8 +1494,10 @@ pub struct ParserState {
     delegate_to_first_layer: bool,
     default_sequence_timeout: u16,
     default_sequence_input_mode: SequenceInputMode,
+    default_tap_hold_require_prior_idle: u16,
     block_unmapped_keys: bool,
     switch_max_key_timing: Cell<u16>,
+    context_conditions: RefCell<Vec<(String, String)>>,
     multi_action_nest_count: Cell<u16>,
     pctx: ParserContext,
     pub lsp_hints: RefCell<LspHints>,
@@ -1341,17 +1518,25 @@ impl Default for ParserState {
             layer_exprs: Default::default(),
             aliases: Default::default(),
             layer_idxs: Default::default(),
+            input_device_idxs: Default::default(),
+            state_var_idxs: Default::default(),
+            hands: None,
             mapping_order: Default::default(),
             defsrc_layer: [KanataAction::NoOp; KEYS_IN_ROW],
             virtual_keys: Default::default(),
+            fake_row_start: 0,
+            fake_row_actions: Default::default(),
+            fake_row_actions_placed: Cell::new(false),
             chord_groups: Default::default(),
             vars: Default::default(),
             is_cmd_enabled: default_cfg.enable_cmd,
             delegate_to_first_layer: default_cfg.delegate_to_first_layer,
             default_sequence_timeout: default_cfg.sequence_timeout,
             default_sequence_input_mode: default_cfg.sequence_input_mode,
+            default_tap_hold_require_prior_idle: default_cfg.tap_hold_require_prior_idle,
             block_unmapped_keys: default_cfg.block_unmapped_keys,
             switch_max_key_timing: Cell::new(0),
+            context_conditions: Default::default(),
             multi_action_nest_count: Cell::new(0),
             lsp_hints: Default::default(),
             a: unsafe { Allocations::new() },
@@ -1620,6 +1805,7 @@ fn parse_action_atom(ac_span: &Spanned<String>, s: &ParserState) -> Result<&'sta
             )
         }
         "scnl" => return custom(CustomAction::SequenceCancel, &s.a),
+        "layer-lock" => return custom(CustomAction::LayerLock, &s.a),
         "mlft" | "mouseleft" => return custom(CustomAction::Mouse(Btn::Left), &s.a),
         "mrgt" | "mouseright" => return custom(CustomAction::Mouse(Btn::Right), &s.a),
         "mmid" | "mousemid" => return custom(CustomAction::Mouse(Btn::Mid), &s.a),
@@ -1769,9 +1955,13 @@ fn parse_action_list(ac: &[SExpr], s: &ParserState) -> Result<&'static KanataAct
         TAP_HOLD_RELEASE_KEYS | TAP_HOLD_RELEASE_KEYS_A => {
             parse_tap_hold_keys(&ac[1..], s, "release", custom_tap_hold_release)
         }
+        TAP_HOLD_OPPOSITE_HAND | TAP_HOLD_OPPOSITE_HAND_A => {
+            parse_tap_hold_opposite_hand(&ac[1..], s)
+        }
         TAP_HOLD_EXCEPT_KEYS | TAP_HOLD_EXCEPT_KEYS_A => {
             parse_tap_hold_keys(&ac[1..], s, "except", custom_tap_hold_except)
         }
+        TAP_HOLD_REQUIRE_PRIOR_IDLE => parse_tap_hold_require_prior_idle(&ac[1..], s),
         MULTI => parse_multi(&ac[1..], s),
         MACRO => parse_macro(&ac[1..], s, RepeatMacro::No),
         MACRO_REPEAT | MACRO_REPEAT_A => parse_macro(&ac[1..], s, RepeatMacro::Yes),
@@ -1809,6 +1999,7 @@ fn parse_action_list(ac: &[SExpr], s: &ParserState) -> Result<&'static KanataAct
         ONE_SHOT_PAUSE_PROCESSING => parse_one_shot_pause_processing(&ac[1..], s),
         TAP_DANCE => parse_tap_dance(&ac[1..], s, TapDanceConfig::Lazy),
         TAP_DANCE_EAGER => parse_tap_dance(&ac[1..], s, TapDanceConfig::Eager),
+        TAP_DANCE_HOLD => parse_tap_dance_hold(&ac[1..], s),
         CHORD => parse_chord(&ac[1..], s),
         RELEASE_KEY | RELEASE_KEY_A => parse_release_key(&ac[1..], s),
         RELEASE_LAYER | RELEASE_LAYER_A => parse_release_layer(&ac[1..], s),
@@ -1823,6 +2014,13 @@ fn parse_action_list(ac: &[SExpr], s: &ParserState) -> Result<&'static KanataAct
         ON_RELEASE | ON_RELEASE_A => parse_on_release(&ac[1..], s),
         ON_IDLE => parse_on_idle(&ac[1..], s),
         HOLD_FOR_DURATION => parse_hold_for_duration(&ac[1..], s),
+        STATE_SET => parse_state_set(&ac[1..], s),
+        STATE_INC | STATE_DEC => parse_state_add(&ac[1..], s, ac_type),
+        STATE_TOGGLE => parse_state_toggle(&ac[1..], s),
+        AFTER => parse_timer(&ac[1..], s, false),
+        EVERY => parse_timer(&ac[1..], s, true),
+        TIMER_CANCEL => parse_timer_cancel(&ac[1..], s),
+        SET_LEDS => parse_set_leds(&ac[1..], s),
         MWHEEL_UP | MWHEEL_UP_A => parse_mwheel(&ac[1..], MWheelDirection::Up, s),
         MWHEEL_DOWN | MWHEEL_DOWN_A => parse_mwheel(&ac[1..], MWheelDirection::Down, s),
         MWHEEL_LEFT | MWHEEL_LEFT_A => parse_mwheel(&ac[1..], MWheelDirection::Left, s),
@@ -1845,8 +2043,12 @@ fn parse_action_list(ac: &[SExpr], s: &ParserState) -> Result<&'static KanataAct
         }
         MOVEMOUSE_SPEED | MOVEMOUSE_SPEED_A => parse_move_mouse_speed(&ac[1..], s),
         SETMOUSE | SETMOUSE_A => parse_set_mouse(&ac[1..], s),
+        DRAG_SCROLL => parse_drag_scroll(&ac[1..], s),
         DYNAMIC_MACRO_RECORD => parse_dynamic_macro_record(&ac[1..], s),
         DYNAMIC_MACRO_PLAY => parse_dynamic_macro_play(&ac[1..], s),
+        DYNAMIC_MACRO_SAVE => parse_dynamic_macro_slot(&ac[1..], s, DYNAMIC_MACRO_SAVE),
+        DYNAMIC_MACRO_LOAD => parse_dynamic_macro_slot(&ac[1..], s, DYNAMIC_MACRO_LOAD),
+        DYNAMIC_MACRO_CLEAR => parse_dynamic_macro_slot(&ac[1..], s, DYNAMIC_MACRO_CLEAR),
         ARBITRARY_CODE => parse_arbitrary_code(&ac[1..], s),
         CMD => parse_cmd(&ac[1..], s, CmdType::Standard),
         CMD_OUTPUT_KEYS => parse_cmd(&ac[1..], s, CmdType::OutputKeys),
@@ -1945,6 +2147,7 @@ Params in order:
     Ok(s.a.sref(Action::HoldTap(s.a.sref(HoldTapAction {
         config,
         tap_hold_interval: tap_timeout,
+        require_prior_idle: s.default_tap_hold_require_prior_idle,
         timeout: hold_timeout,
         tap: *tap_action,
         hold: *hold_action,
@@ -1976,6 +2179,7 @@ Params in order:
     Ok(s.a.sref(Action::HoldTap(s.a.sref(HoldTapAction {
         config,
         tap_hold_interval: tap_timeout,
+        require_prior_idle: s.default_tap_hold_require_prior_idle,
         timeout: hold_timeout,
         tap: *tap_action,
         hold: *hold_action,
@@ -2009,6 +2213,60 @@ Params in order:
     Ok(s.a.sref(Action::HoldTap(s.a.sref(HoldTapAction {
         config: HoldTapConfig::Custom(custom_func(&tap_trigger_keys, &s.a)),
         tap_hold_interval: tap_timeout,
+        require_prior_idle: s.default_tap_hold_require_prior_idle,
+        timeout: hold_timeout,
+        tap: *tap_action,
+        hold: *hold_action,
+        timeout_action: *hold_action,
+    }))))
+}
+
+fn parse_tap_hold_opposite_hand(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    if ac_params.len() != 4 {
+        bail!(
+            r"tap-hold-opposite-hand expects 4 items after it, got {}.
+Params in order:
+<tap-timeout> <hold-timeout> <tap-action> <hold-action>",
+            ac_params.len(),
+        )
+    }
+    let Some(hands) = s.hands else {
+        bail!("tap-hold-opposite-hand requires the keys to be assigned to hands in defhands");
+    };
+    parse_tap_hold(
+        ac_params,
+        s,
+        HoldTapConfig::Custom(custom_tap_hold_opposite_hand(hands, &s.a)),
+    )
+}
+
+fn parse_tap_hold_require_prior_idle(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    if ac_params.len() != 5 {
+        bail!(
+            r"tap-hold-require-prior-idle expects 5 items after it, got {}.
+Params in order:
+<tap-timeout> <hold-timeout> <tap-action> <hold-action> <prior-idle-timeout>",
+            ac_params.len(),
+        )
+    }
+    let tap_timeout = parse_u16(&ac_params[0], s, "tap timeout")?;
+    let hold_timeout = parse_non_zero_u16(&ac_params[1], s, "hold timeout")?;
+    let tap_action = parse_action(&ac_params[2], s)?;
+    let hold_action = parse_action(&ac_params[3], s)?;
+    let prior_idle = parse_u16(&ac_params[4], s, "prior idle timeout")?;
+    if matches!(tap_action, Action::HoldTap { .. }) {
+        bail!("tap-hold does not work in the tap-action of tap-hold")
+    }
+    Ok(s.a.sref(Action::HoldTap(s.a.sref(HoldTapAction {
+        config: HoldTapConfig::Default,
+        tap_hold_interval: tap_timeout,
+        require_prior_idle: prior_idle,
         timeout: hold_timeout,
         tap: *tap_action,
         hold: *hold_action,
@@ -2111,7 +2369,7 @@ fn parse_multi(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAc
             matches!(
                 ac,
                 Action::TapDance(TapDance {
-                    config: TapDanceConfig::Lazy,
+                    config: TapDanceConfig::Lazy | TapDanceConfig::LazyHold,
                     ..
                 }) | Action::HoldTap { .. }
                     | Action::Chords { .. }
@@ -2649,10 +2907,46 @@ fn parse_tap_dance(
     Ok(s.a.sref(Action::TapDance(s.a.sref(TapDance {
         timeout,
         actions: s.a.sref_vec(actions),
+        hold_actions: &[],
         config,
     }))))
 }
 
+fn parse_tap_dance_hold(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
+    const ERR_MSG: &str = "tap-dance-hold expects a timeout (number) followed by a list of \
+        (<tap-action> <hold-action>) pairs";
+    if ac_params.len() != 2 {
+        bail!(ERR_MSG);
+    }
+
+    let timeout = parse_non_zero_u16(&ac_params[0], s, "timeout")?;
+    let slots = ac_params[1]
+        .list(s.vars())
+        .ok_or_else(|| anyhow_expr!(&ac_params[1], "{ERR_MSG}: expected a list"))?;
+    if slots.is_empty() {
+        bail_expr!(&ac_params[1], "{ERR_MSG}: expected at least one pair");
+    }
+    let mut actions = Vec::with_capacity(slots.len());
+    let mut hold_actions = Vec::with_capacity(slots.len());
+    for slot in slots {
+        let Some([tap, hold]) = slot.list(s.vars()) else {
+            bail_expr!(
+                slot,
+                "{ERR_MSG}: expected a (<tap-action> <hold-action>) pair"
+            );
+        };
+        actions.push(parse_action(tap, s)?);
+        hold_actions.push(parse_action(hold, s)?);
+    }
+
+    Ok(s.a.sref(Action::TapDance(s.a.sref(TapDance {
+        timeout,
+        actions: s.a.sref_vec(actions),
+        hold_actions: s.a.sref_vec(hold_actions),
+        config: TapDanceConfig::LazyHold,
+    }))))
+}
+
 fn parse_chord(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
     const ERR_MSG: &str = "Action chord expects a chords group name followed by an identifier";
     if ac_params.len() != 2 {
@@ -2879,8 +3173,12 @@ fn find_chords_coords(chord_groups: &mut [ChordGroup], coord: (u8, u16), action:
                 find_chords_coords(chord_groups, coord, ac);
             }
         }
-        Action::TapDance(TapDance { actions, .. }) => {
-            for ac in actions.iter() {
+        Action::TapDance(TapDance {
+            actions,
+            hold_actions,
+            ..
+        }) => {
+            for ac in actions.iter().chain(hold_actions.iter()) {
                 find_chords_coords(chord_groups, coord, ac);
             }
         }
@@ -2960,19 +3258,35 @@ fn fill_chords(
                 None
             }
         }
-        Action::TapDance(&td @ TapDance { actions, .. }) => {
-            let new_actions = actions
-                .iter()
-                .map(|ac| fill_chords(chord_groups, ac, s))
-                .collect::<Vec<_>>();
-            if new_actions.iter().any(|it| it.is_some()) {
-                let new_actions = new_actions
+        Action::TapDance(
+            &td @ TapDance {
+                actions,
+                hold_actions,
+                ..
+            },
+        ) => {
+            let fill_all = |actions: &'static [&'static KanataAction]| {
+                let new_actions = actions
                     .iter()
-                    .zip(actions)
-                    .map(|(new_ac, ac)| new_ac.map(|v| s.a.sref(v)).unwrap_or(*ac))
+                    .map(|ac| fill_chords(chord_groups, ac, s))
                     .collect::<Vec<_>>();
+                if new_actions.iter().any(|it| it.is_some()) {
+                    let new_actions = new_actions
+                        .iter()
+                        .zip(actions)
+                        .map(|(new_ac, ac)| new_ac.map(|v| s.a.sref(v)).unwrap_or(*ac))
+                        .collect::<Vec<_>>();
+                    Some(s.a.sref_vec(new_actions))
+                } else {
+                    None
+                }
+            };
+            let new_actions = fill_all(actions);
+            let new_hold_actions = fill_all(hold_actions);
+            if new_actions.is_some() || new_hold_actions.is_some() {
                 Some(Action::TapDance(s.a.sref(TapDance {
-                    actions: s.a.sref_vec(new_actions),
+                    actions: new_actions.unwrap_or(actions),
+                    hold_actions: new_hold_actions.unwrap_or(hold_actions),
                     ..td
                 })))
             } else {
@@ -3185,6 +3499,19 @@ fn parse_move_mouse_speed(ac_params: &[SExpr], s: &ParserState) -> Result<&'stat
     )))
 }
 
+fn parse_drag_scroll(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
+    if ac_params.len() != 1 {
+        bail!(
+            "drag-scroll expects one parameter, found {}\n<distance per notch (1-65535)>",
+            ac_params.len()
+        );
+    }
+    let distance = parse_non_zero_u16(&ac_params[0], s, "distance per notch")?;
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::DragScroll { distance })),
+    )))
+}
+
 fn parse_set_mouse(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
     if ac_params.len() != 2 {
         bail!(
@@ -3224,6 +3551,25 @@ fn parse_dynamic_macro_play(ac_params: &[SExpr], s: &ParserState) -> Result<&'st
     )))
 }
 
+fn parse_dynamic_macro_slot(
+    ac_params: &[SExpr],
+    s: &ParserState,
+    action_name: &str,
+) -> Result<&'static KanataAction> {
+    const ERR_MSG: &str = "expects 1 parameter: <macro ID (number 0-65535)>";
+    if ac_params.len() != 1 {
+        bail!("{action_name} {ERR_MSG}, found {}", ac_params.len());
+    }
+    let key = parse_u16(&ac_params[0], s, "macro ID")?;
+    let action = match action_name {
+        DYNAMIC_MACRO_SAVE => CustomAction::DynamicMacroSave(key),
+        DYNAMIC_MACRO_LOAD => CustomAction::DynamicMacroLoad(key),
+        DYNAMIC_MACRO_CLEAR => CustomAction::DynamicMacroClear(key),
+        _ => unreachable!("not a dynamic macro slot action: {action_name}"),
+    };
+    Ok(s.a.sref(Action::Custom(s.a.sref(s.a.sref_slice(action)))))
+}
+
 fn parse_live_reload_num(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
     const ERR_MSG: &str = "expects 1 parameter: <config argument position (1-65535)>";
     if ac_params.len() != 1 {
@@ -3395,6 +3741,44 @@ fn parse_layers(
     Ok(layers_cfg)
 }
 
+/// Reserve the next fake key slot after the virtual keys for an action that is activated by
+/// kanata rather than by a key, e.g. by `defoverridesv2` or by a timer. Returns the column of the
+/// slot in the fake key row.
+pub(crate) fn push_fake_row_action(
+    expr: &SExpr,
+    action: &'static KanataAction,
+    s: &ParserState,
+) -> Result<u16> {
+    if s.fake_row_actions_placed.get() {
+        bail_expr!(
+            expr,
+            "This action cannot be used here. Try using it within defalias."
+        );
+    }
+    let mut actions = s.fake_row_actions.borrow_mut();
+    let idx = s.fake_row_start + actions.len();
+    if idx >= KEYS_IN_ROW {
+        bail_expr!(
+            expr,
+            "Virtual keys, defoverridesv2 entries, timer actions and layer on-enter/on-exit actions combined must not exceed {KEYS_IN_ROW}"
+        );
+    }
+    actions.push(action);
+    Ok(idx as u16)
+}
+
+/// Place the actions from `push_fake_row_action` in the fake key row of every layer.
+fn place_fake_row_actions(layers: &mut IntermediateLayers, s: &ParserState) {
+    debug_assert_eq!(s.fake_row_start, s.virtual_keys.len());
+    for layer in layers.iter_mut() {
+        for (i, action) in s.fake_row_actions.borrow().iter().enumerate() {
+            let (x, y) = get_fake_key_coords(s.fake_row_start + i);
+            layer[x as usize][y as usize] = **action;
+        }
+    }
+    s.fake_row_actions_placed.set(true);
+}
+
 const SEQ_ERR: &str = "defseq expects pairs of parameters: <virtual_key_name> <key_list>";
 
 fn parse_sequences(exprs: &[&Vec<SExpr>], s: &ParserState) -> Result<KeySeqsToFKeys> {
@@ -3620,7 +4004,7 @@ fn parse_arbitrary_code(ac_params: &[SExpr], s: &ParserState) -> Result<&'static
     )))
 }
 
-fn parse_overrides(exprs: &[SExpr], s: &ParserState) -> Result<Overrides> {
+fn parse_overrides(exprs: &[SExpr], s: &ParserState) -> Result<Vec<Override>> {
     const ERR_MSG: &str =
         "defoverrides expects pairs of parameters: <input key list> <output key list>";
     let mut subexprs = check_first_expr(exprs.iter(), "defoverrides")?;
@@ -3666,7 +4050,57 @@ fn parse_overrides(exprs: &[SExpr], s: &ParserState) -> Result<Overrides> {
             .push(Override::try_new(&in_keys, &out_keys).map_err(|e| anyhow!("{ERR_MSG}: {e}"))?);
     }
     log::debug!("All overrides:\n{overrides:#?}");
-    Ok(Overrides::new(&overrides))
+    Ok(overrides)
+}
+
+fn parse_overrides_v2(exprs: &[SExpr], s: &mut ParserState) -> Result<Vec<ActionOverride>> {
+    const ERR_MSG: &str = "defoverridesv2 expects 4-tuples of parameters: \
+        <input key list> <action> <layer list> <negative modifier list>";
+    let subexprs = check_first_expr(exprs.iter(), "defoverridesv2")?.collect::<Vec<_>>();
+    let mut chunks = subexprs.chunks_exact(4);
+    let mut overrides = Vec::<ActionOverride>::new();
+    for chunk in chunks.by_ref() {
+        let parse_keys = |expr: &SExpr, label: &str| -> Result<Vec<OsCode>> {
+            expr.list(s.vars())
+                .ok_or_else(|| anyhow_expr!(expr, "{ERR_MSG}\n{label} must be a list"))?
+                .iter()
+                .try_fold(vec![], |mut keys, key_expr| {
+                    let key = key_expr
+                        .atom(s.vars())
+                        .and_then(str_to_oscode)
+                        .ok_or_else(|| {
+                            anyhow_expr!(key_expr, "Unknown key name, must use known keys")
+                        })?;
+                    keys.push(key);
+                    Ok(keys)
+                })
+        };
+        let in_keys = parse_keys(chunk[0], "Input keys")?;
+        let action = parse_action(chunk[1], s)?;
+        let layers = chunk[2]
+            .list(s.vars())
+            .ok_or_else(|| anyhow_expr!(chunk[2], "{ERR_MSG}\nLayers must be a list"))?
+            .iter()
+            .try_fold(vec![], |mut layers, layer| -> Result<Vec<u16>> {
+                let l_idx = layer
+                    .atom(s.vars())
+                    .and_then(|l| s.layer_idxs.get(l))
+                    .ok_or_else(|| anyhow_expr!(layer, "Not a known layer name."))?;
+                layers.push(*l_idx as u16);
+                Ok(layers)
+            })?;
+        let negative_mods = parse_keys(chunk[3], "Negative modifiers")?;
+        let vkey_idx = push_fake_row_action(chunk[1], action, s)?;
+        let ovd = ActionOverride::try_new(&in_keys, &negative_mods, layers, vkey_idx)
+            .map_err(|e| anyhow_expr!(chunk[0], "{ERR_MSG}\n{e}"))?;
+        overrides.push(ovd);
+    }
+    let rem = chunks.remainder();
+    if !rem.is_empty() {
+        bail_expr!(rem.last().unwrap(), "Incomplete entry. {ERR_MSG}");
+    }
+    log::debug!("All action overrides:\n{overrides:#?}");
+    Ok(overrides)
 }
 
 fn parse_fork(ac_params: &[SExpr], s: &ParserState) -> Result<&'static KanataAction> {
@@ -9,10 +9,7 @@ pub fn parse_switch(ac_params: &[SExpr], s: &ParserState) -> Result<&'static Kan
     let mut cases = vec![];
 
     let mut params = ac_params.iter();
-    loop {
-        let Some(key_match) = params.next() else {
-            break;
-        };
+    while let Some(key_match) = params.next() {
         let Some(action) = params.next() else {
             bail!("{ERR_STR}\nMissing <action> and <break|fallthrough> for the final triple");
         };
@@ -91,6 +88,9 @@ pub fn parse_switch_case_bool(
             InputHistory,
             Layer,
             BaseLayer,
+            InputDevice,
+            Context,
+            State,
         }
         #[derive(Copy, Clone)]
         enum InputType {
@@ -117,6 +117,9 @@ pub fn parse_switch_case_bool(
                 "input-history" => Some(AllowedListOps::InputHistory),
                 "layer" => Some(AllowedListOps::Layer),
                 "base-layer" => Some(AllowedListOps::BaseLayer),
+                "input-device" => Some(AllowedListOps::InputDevice),
+                "context" => Some(AllowedListOps::Context),
+                "state" => Some(AllowedListOps::State),
                 _ => None,
             })
             .ok_or_else(|| {
@@ -124,7 +127,8 @@ pub fn parse_switch_case_bool(
                     op_expr,
                     "lists inside switch logic must begin with one of:\n\
                     or | and | not | key-history | key-timing\n\
-                    | input | input-history | layer | base-layer",
+                    | input | input-history | layer | base-layer\n\
+                    | input-device | context | state",
                 )
             })?;
 
@@ -267,6 +271,33 @@ pub fn parse_switch_case_bool(
                 ops.extend(&[op1, op2]);
                 Ok(())
             }
+            AllowedListOps::InputDevice => {
+                if l.len() != 2 {
+                    bail_expr!(op_expr, "input-device must have 1 parameter: device-name");
+                }
+                let device = l[1]
+                    .atom(s.vars())
+                    .and_then(|atom| s.input_device_idxs.get(atom))
+                    .copied()
+                    .ok_or_else(|| {
+                        anyhow_expr!(&l[1], "not a device name declared in any defdevice")
+                    })?;
+                let (op1, op2) = OpCode::new_input_device(device);
+                ops.extend(&[op1, op2]);
+                Ok(())
+            }
+            AllowedListOps::Context => {
+                let id = parse_context_condition(op_expr, &l[1..], s)?;
+                let (op1, op2) = OpCode::new_context(id);
+                ops.extend(&[op1, op2]);
+                Ok(())
+            }
+            AllowedListOps::State => {
+                let (index, comparator, value) = parse_state_comparison(op_expr, &l[1..], s)?;
+                let (op1, op2, op3) = OpCode::new_variable(index, comparator, value);
+                ops.extend(&[op1, op2, op3]);
+                Ok(())
+            }
             AllowedListOps::Or | AllowedListOps::And | AllowedListOps::Not => {
                 let op = match op {
                     AllowedListOps::Or => BooleanOperator::Or,
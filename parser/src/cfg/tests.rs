@@ -7,6 +7,7 @@ use std::sync::{Mutex, MutexGuard};
 
 mod ambiguous;
 mod defcfg;
+mod defdevice;
 mod device_detect;
 mod environment;
 mod macros;
@@ -35,7 +36,7 @@ fn init_log() {
     });
 }
 
-fn lock<T>(lk: &Mutex<T>) -> MutexGuard<T> {
+fn lock<T>(lk: &Mutex<T>) -> MutexGuard<'_, T> {
     match lk.lock() {
         Ok(guard) => guard,
         Err(poisoned) => poisoned.into_inner(),
@@ -218,6 +219,7 @@ fn parse_action_vars() {
 (defalias
   tdl (tap-dance $two $td)
   tde (tap-dance-eager $two $td)
+  tdh (tap-dance-hold $two (($one $two) ($a $chr)))
   unc (unicode $one)
   rlk (release-key $one)
   mul (multi $two $one)
@@ -705,6 +707,158 @@ fn parse_nested_macro() {
         .expect("parses");
 }
 
+#[test]
+fn parse_overrides_v2() {
+    let source = r#"
+(defsrc a b)
+(deflayer base a b)
+(deflayer other a b)
+(defvirtualkeys vk XX)
+(defoverridesv2
+  (lsft a) (macro b) () ()
+  (lctl b) (layer-while-held other) (base) (lalt ralt)
+)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    for layer in icfg.klayers.layers.iter() {
+        assert!(matches!(
+            layer[usize::from(FAKE_KEY_ROW)][2],
+            Action::Layer(_)
+        ));
+    }
+
+    for bad in [
+        "(defoverridesv2 (lsft a) b () (lsft b))",
+        "(defoverridesv2 (lsft a b) b () ())",
+        "(defoverridesv2 (lsft a) b (nope) ())",
+        "(defoverridesv2 (lsft a) b ())",
+    ] {
+        parse_cfg(&format!("(defsrc a b) (deflayer base a b) {bad}"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_context() {
+    let source = r#"
+(defsrc a b)
+(deflayer base (switch ((context app firefox)) x break ((context app term)) y break) b)
+(deflayer browser a b)
+(defcontextlayers
+  (app "firefox") browser
+  (title editor) base
+)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    let mut ctx = HashMap::default();
+    ctx.insert("app".to_string(), "firefox".to_string());
+    assert_eq!(icfg.contexts.matching(&ctx).collect::<Vec<_>>(), vec![0]);
+    assert_eq!(icfg.contexts.layer_for(&[0]), Some(1));
+    assert_eq!(icfg.contexts.layer_for(&[1]), None);
+    assert_eq!(icfg.contexts.layer_for(&[2]), Some(0));
+
+    for bad in [
+        "(deflayer base (switch ((context app)) x break) b)",
+        "(deflayer base (switch ((context (app) x)) x break) b)",
+        "(deflayer base a b) (defcontextlayers (app x) nope)",
+        "(deflayer base a b) (defcontextlayers (app x))",
+        "(deflayer base a b) (defcontextlayers app base)",
+        "(deflayer base a b) (defcontextlayers (app x) base) (defcontextlayers (app y) base)",
+    ] {
+        parse_cfg(&format!("(defsrc a b) {bad}"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_defstate() {
+    let source = r#"
+(defsrc a b)
+(defstate presses 0 flag true)
+(defstate neg -5)
+(deflayer base
+  (switch ((state presses > 2)) (state-set flag false) break ((state neg != -5)) (state-dec neg 3) break)
+  (multi (state-inc presses) (state-toggle flag)))
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(
+        icfg.state_vars,
+        vec![
+            StateVarCfg {
+                name: "presses".into(),
+                initial: 0
+            },
+            StateVarCfg {
+                name: "flag".into(),
+                initial: 1
+            },
+            StateVarCfg {
+                name: "neg".into(),
+                initial: -5
+            },
+        ]
+    );
+
+    for bad in [
+        "(defstate x)",
+        "(defstate x 0 x 1)",
+        "(defstate x 40000)",
+        "(defstate x yes)",
+        "(defstate x 0) (defalias y (state-set z 1))",
+        "(defstate x 0) (defalias y (state-set x))",
+        "(defstate x 0) (defalias y (state-inc x 40000))",
+        "(defstate x 0) (defalias y (state-toggle x 1))",
+        "(defstate x 0) (defalias y (switch ((state x ~ 1)) a break))",
+        "(defstate x 0) (defalias y (switch ((state x = )) a break))",
+    ] {
+        parse_cfg(&format!("(defsrc a b) (deflayer base a b) {bad}"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_timers() {
+    let source = r#"
+(defsrc a b)
+(defvirtualkeys vk XX)
+(defalias t1 (after t1 1000 (layer-while-held other)))
+(defoverridesv2 (lsft a) (macro b) () ())
+(deflayer base @t1 (every "t 2" 4294967295 (timer-cancel t1)))
+(deflayer other a b)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    for layer in icfg.klayers.layers.iter() {
+        let fake_row = &layer[usize::from(FAKE_KEY_ROW)];
+        assert!(matches!(fake_row[1], Action::Layer(_)));
+        assert!(matches!(fake_row[2], Action::Sequence { .. }));
+        assert!(matches!(fake_row[3], Action::Custom(_)));
+    }
+
+    for bad in [
+        "(defalias x (after t1 0 a))",
+        "(defalias x (after t1 4294967296 a))",
+        "(defalias x (after t1 100))",
+        "(defalias x (every (t1) 100 a))",
+        "(defalias x (timer-cancel))",
+        "(defalias x (timer-cancel t1 t2))",
+    ] {
+        parse_cfg(&format!("(defsrc a b) (deflayer base a b) {bad}"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
 #[test]
 fn parse_switch() {
     let _lk = lock(&CFG_PARSE_LOCK);
@@ -1388,6 +1542,7 @@ fn parse_all_defcfg() {
   delegate-to-first-layer yes
   movemouse-inherit-accel-state yes
   movemouse-smooth-diagonals yes
+  mouse-movement-key-threshold 20
   override-release-on-activation yes
   dynamic-macro-max-presses 1000
   concurrent-tap-hold yes
@@ -1401,6 +1556,9 @@ fn parse_all_defcfg() {
   linux-x11-repeat-delay-rate 400,50
   linux-use-trackpoint-property yes
   linux-output-device-bus-type USB
+  linux-setmouse-screen-size 1920,1080
+  linux-output-devices ((keyboard name "kanata keyboard") (pointer product 0x10))
+  linux-dev-rules ((exclude name "*Consumer Control") (include bus usb vendor 0x1234))
   tray-icon symbols.ico
   icon-match-layer-name no
   tooltip-layer-changes yes
@@ -1453,6 +1611,129 @@ fn parse_defcfg_linux_output_bus() {
         .contains("Invalid value for linux-output-device-bus-type"));
 }
 
+#[test]
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+fn parse_defcfg_linux_setmouse_screen_size() {
+    let source = "
+(defcfg linux-setmouse-screen-size 2560,1440)
+(defsrc a)
+(deflayer base a)
+";
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(
+        icfg.options.linux_opts.linux_setmouse_screen_size,
+        Some((2560, 1440))
+    );
+    for bad in ["1920", "0,1080", "1920,x", "1920,1080,1"] {
+        let err = parse_cfg(&format!(
+            "(defcfg linux-setmouse-screen-size {bad}) (defsrc a) (deflayer base a)"
+        ))
+        .expect_err(bad);
+        assert!(err
+            .msg
+            .contains("Invalid value for linux-setmouse-screen-size"));
+    }
+}
+
+#[test]
+#[cfg(any(target_os = "linux", target_os = "unknown"))]
+fn parse_defcfg_linux_output_devices() {
+    let source = r#"
+(defcfg linux-output-devices (
+  (keyboard name "My keyboard" vendor 0x1d6b product 260)
+  (pointer name "My mouse")
+))
+(defsrc a)
+(deflayer base a)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(
+        icfg.options.linux_opts.linux_output_devices,
+        Some(LinuxOutputDevices {
+            keyboard: LinuxOutputDevice {
+                name: "My keyboard".into(),
+                vendor: 0x1d6b,
+                product: 260,
+            },
+            pointer: LinuxOutputDevice {
+                name: "My mouse".into(),
+                ..LinuxOutputDevices::default().pointer
+            },
+        })
+    );
+
+    let icfg =
+        parse_cfg("(defcfg linux-output-devices ()) (defsrc a) (deflayer base a)").expect("parses");
+    assert_eq!(
+        icfg.options.linux_opts.linux_output_devices,
+        Some(LinuxOutputDevices::default())
+    );
+
+    for bad in [
+        "yes",
+        "((mouse))",
+        "((keyboard) (keyboard))",
+        "((keyboard name))",
+        "((keyboard name \"\"))",
+        "((keyboard vendor 65536))",
+        "((pointer product 0xfffff))",
+        "((pointer serial 1))",
+        "(keyboard)",
+    ] {
+        let cfg = format!("(defcfg linux-output-devices {bad}) (defsrc a) (deflayer base a)");
+        parse_cfg(&cfg).map(|_| ()).expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_defcfg_autoshift() {
+    let source = r#"
+(defcfg
+  autoshift yes
+  autoshift-timeout 200
+  autoshift-repeat yes
+  autoshift-keys (a b)
+  autoshift-exclude-keys (b)
+  autoshift-custom (. S-1  , RA-/)
+)
+(defsrc a)
+(deflayer base a)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    let opts = &icfg.options.autoshift_opts;
+    assert!(opts.enabled && opts.repeat);
+    assert_eq!(opts.timeout, 200);
+    assert_eq!(opts.keys, Some(vec![KeyCode::A, KeyCode::B]));
+    assert_eq!(opts.exclude_keys, vec![KeyCode::B]);
+    assert_eq!(
+        opts.custom_outputs,
+        vec![
+            (KeyCode::Dot, vec![KeyCode::LShift, KeyCode::Kb1]),
+            (KeyCode::Comma, vec![KeyCode::RAlt, KeyCode::Slash]),
+        ]
+    );
+
+    for bad in [
+        "autoshift-timeout 0",
+        "autoshift-keys a",
+        "autoshift-keys (a notakey)",
+        "autoshift-custom (. S-1 ,)",
+        "autoshift-custom (. (S-1))",
+        "autoshift-custom (. S-notakey)",
+        "autoshift-custom (. S-1 . S-2)",
+    ] {
+        parse_cfg(&format!("(defcfg {bad}) (defsrc a) (deflayer base a)"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
 #[test]
 fn parse_unmod() {
     let source = r#"
@@ -1946,7 +2227,7 @@ fn test_defaliasenvcond() {
         ""
     })
     .expect("parses");
-    assert!(s.aliases.get("a").is_none());
+    assert!(!s.aliases.contains_key("a"));
 }
 
 #[test]
@@ -2047,6 +2328,98 @@ fn disallow_dupe_layer_opts_icon_layermap() {
     parse_cfg(source).map(|_| ()).expect_err("fails");
 }
 
+#[test]
+fn parse_layer_opts_timeout() {
+    let source = "
+(defsrc a)
+(deflayer base layer-lock)
+(deflayer (nav timeout 5000 icon nav.ico) a)
+";
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(icfg.layer_info[0].timeout, None);
+    assert_eq!(icfg.layer_info[1].timeout, Some(5000));
+    assert_eq!(icfg.layer_info[1].icon.as_deref(), Some("nav.ico"));
+
+    for bad in [
+        "timeout 0",
+        "timeout -1",
+        "timeout (5)",
+        "timeout 1 timeout 2",
+    ] {
+        parse_cfg(&format!("(defsrc a) (deflayer (base {bad}) a)"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_layer_opts_actions_and_metadata() {
+    let source = r##"
+(defsrc a)
+(defalias ent (macro a b))
+(deflayer (base on-enter @ent on-exit (layer-switch nav) description "Base layer") a)
+(deflayermap (nav colour "#ff0000" meta-group navigation) a b)
+"##;
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    let base = &icfg.layer_info[0];
+    let (Some(on_enter), Some(on_exit)) = (base.on_enter, base.on_exit) else {
+        panic!("expected layer change actions: {base:?}");
+    };
+    let fake_row = &icfg.klayers.layers[1][usize::from(on_enter.x)];
+    assert!(matches!(
+        fake_row[usize::from(on_enter.y)],
+        Action::Sequence { .. }
+    ));
+    assert!(matches!(
+        fake_row[usize::from(on_exit.y)],
+        Action::DefaultLayer(1)
+    ));
+    assert_eq!(
+        base.metadata.get("description").map(String::as_str),
+        Some("Base layer")
+    );
+    let nav = &icfg.layer_info[1];
+    assert_eq!(nav.on_enter, None);
+    assert_eq!(
+        nav.metadata.get("color").map(String::as_str),
+        Some("#ff0000")
+    );
+    assert_eq!(
+        nav.metadata.get("meta-group").map(String::as_str),
+        Some("navigation")
+    );
+
+    for bad in [
+        "on-enter a on-enter b",
+        "color red colour blue",
+        "description (a b)",
+        "meta-group a meta-group b",
+        "meta- a",
+        "on-exit (notanaction)",
+        "on-enter",
+    ] {
+        parse_cfg(&format!("(defsrc a) (deflayer (base {bad}) a)"))
+            .map(|_| ())
+            .expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_layer_opts_rejects_misspelled_options() {
+    for bad in ["timout 100", "on-entr a", "icno x", "group navigation"] {
+        let err = parse_cfg(&format!("(defsrc a) (deflayer (base {bad}) a)")).expect_err(bad);
+        assert!(
+            err.msg.contains("Invalid option in deflayer"),
+            "{bad}: {}",
+            err.msg
+        );
+    }
+}
+
 #[test]
 fn layer_name_allows_var() {
     let source = "
@@ -2090,3 +2463,84 @@ fn reverse_release_order_must_be_within_multi() {
         "reverse-release-order is only allowed inside of a (multi ...) action list"
     );
 }
+
+#[test]
+fn parse_defhands() {
+    let source = "
+(defsrc a j spc)
+(defhands (left a) (right j) (neutral spc))
+(deflayer base (tap-hold-opposite-hand 200 200 a lsft) (tap⬓⇄hand 200 200 j rsft) spc)
+";
+    parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+
+    for bad in [
+        "(defsrc a) (deflayer base (tap-hold-opposite-hand 200 200 a lsft))",
+        "(defsrc a) (defhands (left a) (right a)) (deflayer base a)",
+        "(defsrc a) (defhands (middle a)) (deflayer base a)",
+        "(defsrc a) (defhands (left notakey)) (deflayer base a)",
+        "(defsrc a) (defhands a) (deflayer base a)",
+        "(defsrc a) (defhands (left a)) (defhands (right a)) (deflayer base a)",
+    ] {
+        parse_cfg(bad).map(|_| ()).expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_leds() {
+    let source = "
+(defcfg led-caps-word caps led-sequence num led-dynamic-macro-record scroll)
+(defsrc a b)
+(deflayer (base led-num on led-scroll off) (set-leds caps toggle num auto) (set-leds scroll on))
+";
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(
+        icfg.options.led_indicators,
+        CfgLedIndicators {
+            caps_word: Some(Led::Caps),
+            sequence: Some(Led::Num),
+            dynamic_macro_record: Some(Led::Scroll),
+        }
+    );
+    assert_eq!(icfg.layer_info[0].leds, [Some(true), None, Some(false)]);
+
+    for bad in [
+        "(defsrc a) (deflayer base (set-leds caps))",
+        "(defsrc a) (deflayer base (set-leds kana on))",
+        "(defsrc a) (deflayer base (set-leds caps blink))",
+        "(defsrc a) (deflayer base (set-leds caps on caps off))",
+        "(defsrc a) (deflayer (base led-caps toggle) a)",
+        "(defsrc a) (deflayer (base led-caps on led-caps off) a)",
+        "(defcfg led-caps-word compose) (defsrc a) (deflayer base a)",
+    ] {
+        parse_cfg(bad).map(|_| ()).expect_err(bad);
+    }
+}
+
+#[test]
+fn parse_mouse_movement() {
+    let source = "
+(defcfg mouse-movement-key-threshold 8)
+(defsrc a mml mmr mmu mmd)
+(deflayer (base mouse-speed 50) (drag-scroll 40) mousemoveleft mousemoveright mousemoveup mousemovedown)
+";
+    let icfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(icfg.options.mouse_movement_key_threshold, 8);
+    assert_eq!(icfg.layer_info[0].mouse_speed, Some(50));
+
+    for bad in [
+        "(defsrc a) (deflayer base (drag-scroll))",
+        "(defsrc a) (deflayer base (drag-scroll 0))",
+        "(defsrc a) (deflayer base (drag-scroll 10 10))",
+        "(defsrc a) (deflayer (base mouse-speed 0) a)",
+        "(defsrc a) (deflayer (base mouse-speed fast) a)",
+        "(defcfg mouse-movement-key-threshold 0) (defsrc a) (deflayer base a)",
+    ] {
+        parse_cfg(bad).map(|_| ()).expect_err(bad);
+    }
+}
@@ -99,3 +99,53 @@ fn unmapped_except_keys_is_removed_from_mapping() {
         }
     }
 }
+
+#[test]
+fn dynamic_macro_file_and_slot_actions() {
+    let source = r#"
+(defcfg dynamic-macro-file "macros.txt")
+(defsrc a b c)
+(deflayer base (dynamic-macro-save 1) (dynamic-macro-load 1) (dynamic-macro-clear 65535))
+"#;
+    let cfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    assert_eq!(
+        cfg.options.dynamic_macro_file.as_deref(),
+        Some("macros.txt")
+    );
+    let source = "
+(defsrc a)
+(deflayer base (dynamic-macro-save 65536))
+";
+    parse_cfg(source).map(|_| ()).expect_err("fails");
+}
+
+#[test]
+fn tap_hold_require_prior_idle() {
+    let source = "
+(defcfg tap-hold-require-prior-idle 150)
+(defsrc a b c)
+(deflayer base
+  (tap-hold 200 200 a lsft)
+  (tap-hold-release-keys 200 200 b lctl (c))
+  (tap-hold-require-prior-idle 200 200 c lalt 80)
+)
+";
+    let cfg = parse_cfg(source)
+        .map_err(|e| eprintln!("{:?}", miette::Error::from(e)))
+        .expect("parses");
+    let (layers, _) = cfg.klayers.get();
+    let prior_idle = |osc: OsCode| match layers[0][0][usize::from(osc)] {
+        Action::HoldTap(hta) => hta.require_prior_idle,
+        _ => panic!("expected tap-hold"),
+    };
+    assert_eq!(prior_idle(OsCode::KEY_A), 150);
+    assert_eq!(prior_idle(OsCode::KEY_B), 150);
+    assert_eq!(prior_idle(OsCode::KEY_C), 80);
+    let source = "
+(defsrc a)
+(deflayer base (tap-hold-require-prior-idle 200 200 a lsft))
+";
+    parse_cfg(source).map(|_| ()).expect_err("fails");
+}
@@ -0,0 +1,107 @@
+use super::*;
+
+#[test]
+fn defdevice_parses_properly() {
+    let source = r#"
+(defsrc a b)
+(deflayer base a b)
+(deflayer pad 1 2)
+(defdevice laptop name "AT Translated Set 2 keyboard")
+(defdevice pad
+  vendor-id 0x1234
+  product-id 22136
+  path /dev/input/by-id/usb-pad-event-kbd
+  layer pad)
+"#;
+    let icfg = parse_cfg(source)
+        .map_err(|e| log::info!("{:?}", miette::Error::from(e)))
+        .expect("no error");
+    assert_eq!(icfg.input_devices.len(), 2);
+    let laptop = &icfg.input_devices[0];
+    assert_eq!(laptop.name, "laptop");
+    assert_eq!(laptop.id, 1);
+    assert_eq!(
+        laptop.device_name.as_deref(),
+        Some("AT Translated Set 2 keyboard")
+    );
+    assert_eq!(laptop.layer, None);
+    let pad = &icfg.input_devices[1];
+    assert_eq!(pad.id, 2);
+    assert_eq!(pad.vendor_id, Some(0x1234));
+    assert_eq!(pad.product_id, Some(0x5678));
+    assert_eq!(
+        pad.device_path.as_deref(),
+        Some("/dev/input/by-id/usb-pad-event-kbd")
+    );
+    assert_eq!(pad.layer, Some(1));
+
+    assert_eq!(
+        find_input_device_id(
+            &icfg.input_devices,
+            "AT Translated Set 2 keyboard",
+            "/dev/input/event3",
+            1,
+            1
+        ),
+        1
+    );
+    assert_eq!(
+        find_input_device_id(
+            &icfg.input_devices,
+            "Macro Pad",
+            "/dev/input/by-id/usb-pad-event-kbd",
+            0x1234,
+            0x5678
+        ),
+        2
+    );
+    assert_eq!(
+        find_input_device_id(
+            &icfg.input_devices,
+            "Macro Pad",
+            "/dev/input/by-id/usb-pad-event-kbd",
+            0x1234,
+            0x0001
+        ),
+        0
+    );
+}
+
+#[test]
+fn defdevice_in_switch_parses() {
+    let source = r#"
+(defsrc a)
+(deflayer base (switch ((input-device pad)) b break () a break))
+(defdevice pad name "Macro Pad")
+"#;
+    parse_cfg(source)
+        .map_err(|e| log::info!("{:?}", miette::Error::from(e)))
+        .expect("no error");
+}
+
+#[test]
+fn defdevice_errors() {
+    for source in [
+        // no match properties
+        "(defsrc a) (deflayer base a) (defdevice pad layer base)",
+        // unknown option
+        "(defsrc a) (deflayer base a) (defdevice pad name pad colour red)",
+        // missing value
+        "(defsrc a) (deflayer base a) (defdevice pad name)",
+        // duplicate option
+        "(defsrc a) (deflayer base a) (defdevice pad name pad name pad2)",
+        // duplicate device name
+        "(defsrc a) (deflayer base a) (defdevice pad name pad) (defdevice pad name pad2)",
+        // invalid id
+        "(defsrc a) (deflayer base a) (defdevice pad vendor-id 0xfffff)",
+        // unknown layer
+        "(defsrc a) (deflayer base a) (defdevice pad name pad layer nope)",
+        // unknown device in switch
+        "(defsrc a) (deflayer base (switch ((input-device pad)) b break))",
+    ] {
+        parse_cfg(source)
+            .map(|_| ())
+            .map_err(|e| log::info!("{:?}", miette::Error::from(e)))
+            .expect_err("error should happen");
+    }
+}
@@ -63,4 +63,151 @@ mod linux {
             .map_err(|e| log::info!("{:?}", miette::Error::from(e)))
             .expect_err("error should happen");
     }
+
+    static LINUX_DEV_RULES_CFG: &str = r#"
+(defcfg linux-dev-rules (
+  (exclude name "*Consumer Control")
+  (include vendor 0x1234 product 5678 bus usb)
+  (include bus 0x05 uniq "aa:bb:??:*")
+  (exclude phys "usb-0000:00:14.0-3*")
+  (include)
+))
+(defsrc) (deflayer base)"#;
+
+    #[test]
+    fn linux_dev_rules_parse() {
+        let icfg = parse_cfg(LINUX_DEV_RULES_CFG)
+            .map_err(|e| log::info!("{:?}", miette::Error::from(e)))
+            .expect("no error");
+        let rules = &icfg.options.linux_opts.linux_dev_rules;
+        assert_eq!(rules.len(), 5);
+        assert_eq!(rules[0].action, LinuxDevRuleAction::Exclude);
+        assert_eq!(rules[0].name.as_deref(), Some("*Consumer Control"));
+        assert_eq!(rules[1].action, LinuxDevRuleAction::Include);
+        assert_eq!(rules[1].vendor, Some(0x1234));
+        assert_eq!(rules[1].product, Some(5678));
+        assert_eq!(rules[1].bus, Some(0x03));
+        assert_eq!(rules[2].bus, Some(0x05));
+        assert_eq!(rules[2].uniq.as_deref(), Some("aa:bb:??:*"));
+        assert_eq!(rules[3].phys.as_deref(), Some("usb-0000:00:14.0-3*"));
+        assert_eq!(
+            rules[4],
+            LinuxDevRule {
+                action: LinuxDevRuleAction::Include,
+                name: None,
+                vendor: None,
+                product: None,
+                bus: None,
+                phys: None,
+                uniq: None,
+            }
+        );
+    }
+
+    #[test]
+    fn linux_dev_rules_first_match_wins() {
+        let icfg = parse_cfg(LINUX_DEV_RULES_CFG).expect("no error");
+        let rules = &icfg.options.linux_opts.linux_dev_rules;
+        let rule_index = |dev: LinuxDevProps| {
+            let rule = find_linux_dev_rule(rules, &dev).expect("a rule matches");
+            rules.iter().position(|r| std::ptr::eq(r, rule))
+        };
+        let usb_keyboard = LinuxDevProps {
+            name: "Keyboard",
+            vendor: 0x1234,
+            product: 5678,
+            bus: 0x03,
+            phys: "usb-0000:00:14.0-3/input0",
+            ..Default::default()
+        };
+        assert_eq!(rule_index(usb_keyboard), Some(1));
+        // An earlier rule takes priority over the later ones that also match.
+        assert_eq!(
+            rule_index(LinuxDevProps {
+                name: "Keyboard Consumer Control",
+                ..usb_keyboard
+            }),
+            Some(0)
+        );
+        assert_eq!(
+            rule_index(LinuxDevProps {
+                product: 1,
+                ..usb_keyboard
+            }),
+            Some(3)
+        );
+        let bt_keyboard = LinuxDevProps {
+            name: "BT Keyboard",
+            bus: 0x05,
+            uniq: "aa:bb:cc:dd:ee:ff",
+            ..Default::default()
+        };
+        assert_eq!(rule_index(bt_keyboard), Some(2));
+        assert_eq!(
+            rule_index(LinuxDevProps {
+                uniq: "aa:bb:c",
+                ..bt_keyboard
+            }),
+            Some(4)
+        );
+
+        // Devices that no rule matches are left to linux-device-detect-mode.
+        let source = r#"(defcfg linux-dev-rules ((exclude name "a*"))) (defsrc) (deflayer base)"#;
+        let icfg = parse_cfg(source).expect("no error");
+        let rules = &icfg.options.linux_opts.linux_dev_rules;
+        let dev = |name| LinuxDevProps {
+            name,
+            ..Default::default()
+        };
+        assert!(find_linux_dev_rule(rules, &dev("abc")).is_some());
+        assert!(find_linux_dev_rule(rules, &dev("bac")).is_none());
+    }
+
+    #[test]
+    fn linux_dev_rules_invalid_config() {
+        for bad in [
+            "linux-dev-rules (exclude name a)",
+            "linux-dev-rules ((allow name a))",
+            "linux-dev-rules (())",
+            "linux-dev-rules ((include name))",
+            "linux-dev-rules ((include name a name b))",
+            "linux-dev-rules ((include serial a))",
+            "linux-dev-rules ((include vendor 0x10000))",
+            "linux-dev-rules ((include bus parallel))",
+            "linux-dev-rules ((include phys (a)))",
+            "linux-dev-rules a",
+        ] {
+            let source = format!("(defcfg {bad}) (defsrc) (deflayer base)");
+            parse_cfg(&source).map(|_| ()).expect_err(bad);
+        }
+    }
+
+    #[test]
+    fn glob_matches() {
+        for (pattern, text) in [
+            ("", ""),
+            ("*", ""),
+            ("*", "any thing"),
+            ("a?c", "abc"),
+            ("*Control", "Consumer Control"),
+            ("usb-*-3*/input?", "usb-0000:00:14.0-3.1/input1"),
+            ("a*b*c", "aXbYbZc"),
+            ("é?", "éè"),
+            ("[ab].+", "[ab].+"),
+        ] {
+            assert!(glob_match(pattern, text), "{pattern} {text}");
+        }
+        for (pattern, text) in [
+            ("", "a"),
+            ("a", ""),
+            ("a?c", "ac"),
+            ("*Control", "Control Consumer"),
+            ("a*b*c", "aXbYbZ"),
+            ("abc", "ABC"),
+            ("[ab]", "a"),
+            ("a.c", "abc"),
+        ] {
+            assert!(!glob_match(pattern, text), "{pattern} {text}");
+        }
+    }
 }
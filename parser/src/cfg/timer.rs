@@ -0,0 +1,67 @@
+//! Parsing of timer actions, which activate an action once after a delay or periodically without
+//! blocking other processing. Timers are named so that they can be cancelled.
+//!
+//! Example:
+//!
+//! (defalias
+//!   rmd (after reminder 60000 (cmd notify-send "stretch"))
+//!   jig (every jiggle 30000 (macro (movemouse-right 1 1) (movemouse-left 1 1)))
+//!   stp (timer-cancel jiggle)
+//! )
+
+use super::sexpr::*;
+use super::*;
+use crate::{anyhow_expr, bail};
+
+pub(crate) fn parse_timer(
+    ac_params: &[SExpr],
+    s: &ParserState,
+    repeat: bool,
+) -> Result<&'static KanataAction> {
+    let action_name = if repeat { EVERY } else { AFTER };
+    if ac_params.len() != 3 {
+        bail!(
+            "{action_name} expects 3 parameters: <timer name> <milliseconds> <action>, found {}",
+            ac_params.len()
+        );
+    }
+    let name = parse_timer_name(&ac_params[0], s)?;
+    let interval = ac_params[1]
+        .atom(s.vars())
+        .and_then(|a| a.parse::<u32>().ok())
+        .filter(|ms| *ms > 0)
+        .ok_or_else(|| anyhow_expr!(&ac_params[1], "milliseconds must be 1-4294967295"))?;
+    let action = parse_action(&ac_params[2], s)?;
+    let y = push_fake_row_action(&ac_params[2], action, s)?;
+    let (x, y) = get_fake_key_coords(y);
+    Ok(s.a.sref(Action::Custom(s.a.sref(s.a.sref_slice(
+        CustomAction::TimerStart {
+            name,
+            coord: Coord { x, y },
+            interval,
+            repeat,
+        },
+    )))))
+}
+
+pub(crate) fn parse_timer_cancel(
+    ac_params: &[SExpr],
+    s: &ParserState,
+) -> Result<&'static KanataAction> {
+    if ac_params.len() != 1 {
+        bail!(
+            "{TIMER_CANCEL} expects 1 parameter: <timer name>, found {}",
+            ac_params.len()
+        );
+    }
+    let name = parse_timer_name(&ac_params[0], s)?;
+    Ok(s.a.sref(Action::Custom(
+        s.a.sref(s.a.sref_slice(CustomAction::TimerCancel(name))),
+    )))
+}
+
+fn parse_timer_name(expr: &SExpr, s: &ParserState) -> Result<String> {
+    expr.atom(s.vars())
+        .map(|name| name.trim_atom_quotes().to_string())
+        .ok_or_else(|| anyhow_expr!(expr, "timer name must not be a list"))
+}
@@ -53,6 +53,11 @@ pub enum CustomAction {
     MoveMouseSpeed {
         speed: u16,
     },
+    /// Scroll with the mouse movement of input devices instead of moving the pointer. The
+    /// distance is how far the pointer would have moved to scroll by one notch.
+    DragScroll {
+        distance: u16,
+    },
     SequenceCancel,
     SequenceLeader(u16, SequenceInputMode),
     LiveReload,
@@ -69,6 +74,9 @@ pub enum CustomAction {
     DynamicMacroRecord(u16),
     DynamicMacroRecordStop(u16),
     DynamicMacroPlay(u16),
+    DynamicMacroSave(u16),
+    DynamicMacroLoad(u16),
+    DynamicMacroClear(u16),
     SendArbitraryCode(u16),
     CapsWord(CapsWordCfg),
     SetMouse {
@@ -83,6 +91,72 @@ pub enum CustomAction {
         keys: Box<[KeyCode]>,
     },
     ReverseReleaseOrder,
+    /// Set the `defstate` variable at `index` to `value`.
+    StateSet {
+        index: u16,
+        value: i16,
+    },
+    /// Add `amount` to the `defstate` variable at `index`, saturating at the bounds of `i16`.
+    StateAdd {
+        index: u16,
+        amount: i16,
+    },
+    /// Set the `defstate` variable at the index to 1 if it is 0, otherwise to 0.
+    StateToggle(u16),
+    /// Start the timer with the given name, replacing any running timer with the same name. The
+    /// fake key at `coord` is tapped after `interval` milliseconds, and then every `interval`
+    /// milliseconds if `repeat` is true.
+    TimerStart {
+        name: String,
+        coord: Coord,
+        interval: u32,
+        repeat: bool,
+    },
+    TimerCancel(String),
+    /// Lock the layers that are held so that they stay active after release, or unlock the
+    /// locked layers if no other layer is held.
+    LayerLock,
+    /// Change the keyboard LEDs, overriding the LED states of layers and indicators.
+    SetLeds(Vec<(Led, LedSetting)>),
+}
+
+/// A lock key LED of a keyboard. The discriminants match the Linux `LED_*` codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Led {
+    Num = 0,
+    Caps = 1,
+    Scroll = 2,
+}
+
+impl Led {
+    pub const ALL: [Led; 3] = [Led::Num, Led::Caps, Led::Scroll];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl fmt::Display for Led {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Led::Num => write!(f, "num"),
+            Led::Caps => write!(f, "caps"),
+            Led::Scroll => write!(f, "scroll"),
+        }
+    }
+}
+
+/// The state that kanata sets each LED to, indexed by [`Led::index`]. `None` leaves the LED
+/// alone, so that it shows the state of its lock key.
+pub type LedOverrides = [Option<bool>; 3];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LedSetting {
+    On,
+    Off,
+    Toggle,
+    /// Stop overriding the LED from the action.
+    Auto,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
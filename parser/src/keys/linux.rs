@@ -757,6 +757,10 @@ impl OsCode {
             746 => Some(OsCode::MouseWheelDown),
             747 => Some(OsCode::MouseWheelLeft),
             748 => Some(OsCode::MouseWheelRight),
+            749 => Some(OsCode::MouseMoveUp),
+            750 => Some(OsCode::MouseMoveDown),
+            751 => Some(OsCode::MouseMoveLeft),
+            752 => Some(OsCode::MouseMoveRight),
             767 => Some(OsCode::KEY_MAX),
             _ => None,
         }
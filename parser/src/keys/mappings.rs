@@ -766,10 +766,10 @@ fn roundtrip_oscode_keycode() {
         MouseWheelDown,
         MouseWheelLeft,
         MouseWheelRight,
-        KEY_749,
-        KEY_750,
-        KEY_751,
-        KEY_752,
+        MouseMoveUp,
+        MouseMoveDown,
+        MouseMoveLeft,
+        MouseMoveRight,
         KEY_753,
         KEY_754,
         KEY_755,
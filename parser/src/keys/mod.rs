@@ -161,7 +161,16 @@ fn add_default_str_osc_mappings(mapping: &mut HashMap<String, OsCode>) {
     }
 }
 
-/// Convert a `&str` to an `OsCode`.
+/// Convert a `&str` to an `OsCode`. The built-in names are in `KEY_NAMES`.
+pub fn str_to_oscode(s: &str) -> Option<OsCode> {
+    if let Some(osc) = CUSTOM_STRS_TO_OSCODES.lock().get(s) {
+        return Some(*osc);
+    }
+    STRS_TO_OSCODES.get(s).copied()
+}
+
+/// The names of each key in the configuration language. The first name of a key is the one
+/// that `oscode_to_str` prefers.
 ///
 /// kmonad's str to key mapping is found here as a reference:
 /// https://github.com/kmonad/kmonad/blob/master/src/KMonad/Keyboard/Keycode.hs
@@ -169,207 +178,236 @@ fn add_default_str_osc_mappings(mapping: &mut HashMap<String, OsCode>) {
 /// Do your best to keep the str side a maximum character length of 4 so that configuration file
 /// can stay clean.
 #[rustfmt::skip]
-pub fn str_to_oscode(s: &str) -> Option<OsCode> {
-    if let Some(osc) = CUSTOM_STRS_TO_OSCODES.lock().get(s) {
-        return Some(*osc);
-    }
-    Some(match s {
-        "Backquote" | "grv" | "ˋ" | "˜" => OsCode::KEY_GRAVE,
-        "Digit1" | "1" => OsCode::KEY_1,
-        "Digit2" | "2" => OsCode::KEY_2,
-        "Digit3" | "3" => OsCode::KEY_3,
-        "Digit4" | "4" => OsCode::KEY_4,
-        "Digit5" | "5" => OsCode::KEY_5,
-        "Digit6" | "6" => OsCode::KEY_6,
-        "Digit7" | "7" => OsCode::KEY_7,
-        "Digit8" | "8" => OsCode::KEY_8,
-        "Digit9" | "9" => OsCode::KEY_9,
-        "Digit0" | "0" => OsCode::KEY_0,
-        "Minus" | "min" | "‐" => OsCode::KEY_MINUS,
-        "Equal" | "eql" | "₌" => OsCode::KEY_EQUAL,
-        "Backspace" | "bspc" | "bks" | "␈" | "⌫"  => OsCode::KEY_BACKSPACE,
-        "Tab" | "tab" | "⭾" | "↹" => OsCode::KEY_TAB,
-        "KeyQ" | "q" => OsCode::KEY_Q,
-        "KeyW" | "w" => OsCode::KEY_W,
-        "KeyE" | "e" => OsCode::KEY_E,
-        "KeyR" | "r" => OsCode::KEY_R,
-        "KeyT" | "t" => OsCode::KEY_T,
-        "KeyY" | "y" => OsCode::KEY_Y,
-        "KeyU" | "u" => OsCode::KEY_U,
-        "KeyI" | "i" => OsCode::KEY_I,
-        "KeyO" | "o" => OsCode::KEY_O,
-        "KeyP" | "p" => OsCode::KEY_P,
-        "BracketLeft" | "lbrc" | "【" | "「" | "〔" | "⎡" => OsCode::KEY_LEFTBRACE,
-        "BracketRight" | "rbrc" | "】" | "」" | "〕" | "⎣" => OsCode::KEY_RIGHTBRACE,
-        "CapsLock" | "caps" | "⇪" => OsCode::KEY_CAPSLOCK,
-        "KeyA" | "a" => OsCode::KEY_A,
-        "KeyS" | "s" => OsCode::KEY_S,
-        "KeyD" | "d" => OsCode::KEY_D,
-        "KeyF" | "f" => OsCode::KEY_F,
-        "KeyG" | "g" => OsCode::KEY_G,
-        "KeyH" | "h" => OsCode::KEY_H,
-        "KeyJ" | "j" => OsCode::KEY_J,
-        "KeyK" | "k" => OsCode::KEY_K,
-        "KeyL" | "l" => OsCode::KEY_L,
-        "Semicolon" | "scln" | "︔" => OsCode::KEY_SEMICOLON,
-        "Quote" | "apo" | "apos" => OsCode::KEY_APOSTROPHE,
-        "Enter" | "ret" | "return" | "ent" | "enter" | "⏎" | "↩" | "⌤" | "␤" => OsCode::KEY_ENTER,
-        "ShiftLeft" | "lshift" | "lshft" | "lsft" | "shft" | "sft" | "‹⇧" => OsCode::KEY_LEFTSHIFT,
-        "KeyZ" | "z" => OsCode::KEY_Z,
-        "KeyX" | "x" => OsCode::KEY_X,
-        "KeyC" | "c" => OsCode::KEY_C,
-        "KeyV" | "v" => OsCode::KEY_V,
-        "KeyB" | "b" => OsCode::KEY_B,
-        "KeyN" | "n" => OsCode::KEY_N,
-        "KeyM" | "m" => OsCode::KEY_M,
-        "Comma" | "comm" | "⸴" => OsCode::KEY_COMMA,
-        "Period" | "．" => OsCode::KEY_DOT,
-        "Slash" | "⁄" => OsCode::KEY_SLASH,
-        "Backslash" | "bksl" | "⧵" | "＼" =>  OsCode::KEY_BACKSLASH,
-        "kp=" | "clr" => OsCode::KEY_CLEAR,
-        // The kp<etc> keys are also known as the numpad keys. E.g. below is numpad enter.
-        "Numpad0" | "kp0" | "🔢₀" => OsCode::KEY_KP0,
-        "Numpad1" | "kp1" | "🔢₁" => OsCode::KEY_KP1,
-        "Numpad2" | "kp2" | "🔢₂" => OsCode::KEY_KP2,
-        "Numpad3" | "kp3" | "🔢₃" => OsCode::KEY_KP3,
-        "Numpad4" | "kp4" | "🔢₄" => OsCode::KEY_KP4,
-        "Numpad5" | "kp5" | "🔢₅" => OsCode::KEY_KP5,
-        "Numpad6" | "kp6" | "🔢₆" => OsCode::KEY_KP6,
-        "Numpad7" | "kp7" | "🔢₇" => OsCode::KEY_KP7,
-        "Numpad8" | "kp8" | "🔢₈" => OsCode::KEY_KP8,
-        "Numpad9" | "kp9" | "🔢₉" => OsCode::KEY_KP9,
-        "NumpadEnter" | "kprt" | "🔢⏎" | "🔢↩" | "🔢⌤" | "🔢␤" => OsCode::KEY_KPENTER,
-        "NumpadDivide" | "kp/" | "🔢⁄" => OsCode::KEY_KPSLASH,
-        "NumpadAdd" | "kp+" | "🔢₊" => OsCode::KEY_KPPLUS,
-        "NumpadMultiply" | "kp*" | "🔢∗" => OsCode::KEY_KPASTERISK,
-        "NumpadEqual" | "🔢₌" => OsCode::KEY_KPEQUAL,
-        "NumpadSubtract" | "kp-" | "🔢₋" => OsCode::KEY_KPMINUS,
-        "NumpadDecimal" | "kp." | "🔢．" => OsCode::KEY_KPDOT,
-        "NumpadComma" | "kp," | "🔢⸴" =>OsCode::KEY_KPCOMMA,
-        "ssrq" | "sys" => OsCode::KEY_SYSRQ,
-        // Typically the Non-US backslash, near the left shift key
-        "IntlBackslash" | "102d" | "lsgt" | "nubs" | "nonusbslash" | "﹨" | "<" => OsCode::KEY_102ND,
-        "ScrollLock" | "scrlck" | "slck" | "⇳🔒" => OsCode::KEY_SCROLLLOCK,
-        "Pause" | "pause" | "break" | "brk" => OsCode::KEY_PAUSE,
-        "WakeUp" | "wkup" => OsCode::KEY_WAKEUP,
-        "Escape" | "esc" | "⎋" => OsCode::KEY_ESC,
-        "ShiftRight" | "RightShift" | "rshift" | "rshft" | "rsft" | "⇧›" => OsCode::KEY_RIGHTSHIFT,
-        "ControlLeft" | "lctrl" | "lctl" | "ctl" | "‹⎈" | "‹⌃" => OsCode::KEY_LEFTCTRL,
-        "AltLeft" | "lalt" | "alt" | "‹⎇" | "‹⌥" => OsCode::KEY_LEFTALT,
-        "Space" | "spc" | "␠" | "␣" => OsCode::KEY_SPACE,
-        "AltRight" | "ralt" | "⎇›" | "⌥›" => OsCode::KEY_RIGHTALT,
-        "ContextMenu" | "comp" | "cmps" | "cmp" | "menu" | "apps" | "▤" | "☰" | "𝌆" => OsCode::KEY_COMPOSE,
-        "🎛" => OsCode::KEY_DASHBOARD,
-        // Also known as Windows, GUI, Comand, Super
-        "MetaLeft" | "lmeta" | "lmet" | "met" | "‹◆" | "‹⌘" | "‹❖" => OsCode::KEY_LEFTMETA,
-        "MetaRight" | "rmeta" | "rmet" | "◆›" | "⌘›" | "❖›"  => OsCode::KEY_RIGHTMETA,
-        "ControlRight" | "rctrl" | "rctl" | "⎈›" | "⌃›" => OsCode::KEY_RIGHTCTRL,
-        "Delete" | "del" | "␡" | "⌦" => OsCode::KEY_DELETE,
-        "Insert" | "ins" | "⎀" => OsCode::KEY_INSERT,
-        "BrowserBack" | "bck" => OsCode::KEY_BACK,
-        "BrowserForward" | "fwd" => OsCode::KEY_FORWARD,
-        "PageUp" | "pgup" | "⇞" => OsCode::KEY_PAGEUP,
-        "PageDown" | "pgdn" | "⇟" => OsCode::KEY_PAGEDOWN,
-        "ArrowUp" | "up" | "▲" => OsCode::KEY_UP,
-        "ArrowDown" | "down" | "▼" => OsCode::KEY_DOWN,
-        "ArrowLeft" | "lft" | "left" | "◀" => OsCode::KEY_LEFT,
-        "ArrowRight" | "rght" | "▶" => OsCode::KEY_RIGHT,
-        "Home" | "home" | "⇤" | "⤒" | "↖" => OsCode::KEY_HOME,
-        "End" | "end" | "⇥" | "⤓" | "↘" => OsCode::KEY_END,
-        "NumLock" | "nlck" | "nlk" | "⇭"=> OsCode::KEY_NUMLOCK,
-        "VolumeMute" | "mute"  | "🔇" | "🔈⓪" | "🔈⓿" | "🔈₀" => OsCode::KEY_MUTE,
-        "VolumeUp" | "volu" | "🔊" | "🔈+" | "🔈➕" | "🔈₊" | "🔈⊕" => OsCode::KEY_VOLUMEUP,
-        "VolumeDown" | "voldwn" | "vold" | "🔉" | "🔈−" | "🔈➖" | "🔈₋" | "🔈⊖" => OsCode::KEY_VOLUMEDOWN,
-        "brup" | "bru" | "🔆" => OsCode::KEY_BRIGHTNESSUP,
-        "brdown" | "brdwn" | "brdn" | "🔅" => OsCode::KEY_BRIGHTNESSDOWN,
-        "blup" | "⌨💡+" | "⌨💡➕" | "⌨💡₊" | "⌨💡⊕" => OsCode::KEY_KBDILLUMUP,
-        "bldn" | "⌨💡−" | "⌨💡➖" | "⌨💡₋" | "⌨💡⊖" => OsCode::KEY_KBDILLUMDOWN,
-        "MediaTrackNext" | "next" | "▶▶" => OsCode::KEY_NEXTSONG,
-        "MediaPlayPause" | "pp" | "▶⏸" => OsCode::KEY_PLAYPAUSE,
-        "MediaTrackPrevious" | "prev" | "◀◀" => OsCode::KEY_PREVIOUSSONG,
-        "F1" | "f1" => OsCode::KEY_F1,
-        "F2" | "f2" => OsCode::KEY_F2,
-        "F3" | "f3" => OsCode::KEY_F3,
-        "F4" | "f4" => OsCode::KEY_F4,
-        "F5" | "f5" => OsCode::KEY_F5,
-        "F6" | "f6" => OsCode::KEY_F6,
-        "F7" | "f7" => OsCode::KEY_F7,
-        "F8" | "f8" => OsCode::KEY_F8,
-        "F9" | "f9" => OsCode::KEY_F9,
-        "F10" | "f10" => OsCode::KEY_F10,
-        "F11" | "f11" => OsCode::KEY_F11,
-        "F12" | "f12" => OsCode::KEY_F12,
-        "F13" | "f13" => OsCode::KEY_F13,
-        "F14" | "f14" => OsCode::KEY_F14,
-        "F15" | "f15" => OsCode::KEY_F15,
-        "F16" | "f16" => OsCode::KEY_F16,
-        "F17" | "f17" => OsCode::KEY_F17,
-        "F18" | "f18" => OsCode::KEY_F18,
-        "F19" | "f19" => OsCode::KEY_F19,
-        "F20" | "f20" => OsCode::KEY_F20,
-        "F21" | "f21" => OsCode::KEY_F21,
-        "F22" | "f22" => OsCode::KEY_F22,
-        "F23" | "f23" => OsCode::KEY_F23,
-        "F24" | "f24" => OsCode::KEY_F24,
-        #[cfg(any(target_os = "macos", target_os = "unknown"))]
-        "fn" | "🌐" | "ƒ" | "ⓕ" | "Ⓕ" | "🄵" | "🅕" | "🅵" => OsCode::KEY_FN,
-        #[cfg(target_os = "windows")]
-        "kana" | "katakana" | "katakanahiragana" => OsCode::KEY_HANGEUL,
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "kana" | "katakanahiragana" => OsCode::KEY_KATAKANAHIRAGANA,
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "hiragana" => OsCode::KEY_HIRAGANA,
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "katakana" => OsCode::KEY_KATAKANA,
-        "cnv" | "conv" | "henk" | "hnk" | "henkan" => OsCode::KEY_HENKAN,
-        "ncnv" | "mhnk" | "muhenkan" => OsCode::KEY_MUHENKAN,
-        "IntlRo" | "ro" => OsCode::KEY_RO,
+const KEY_NAMES: &[(OsCode, &[&str])] = &[
+    (OsCode::KEY_GRAVE, &["grv", "Backquote", "ˋ", "˜"]),
+    (OsCode::KEY_1, &["1", "Digit1"]),
+    (OsCode::KEY_2, &["2", "Digit2"]),
+    (OsCode::KEY_3, &["3", "Digit3"]),
+    (OsCode::KEY_4, &["4", "Digit4"]),
+    (OsCode::KEY_5, &["5", "Digit5"]),
+    (OsCode::KEY_6, &["6", "Digit6"]),
+    (OsCode::KEY_7, &["7", "Digit7"]),
+    (OsCode::KEY_8, &["8", "Digit8"]),
+    (OsCode::KEY_9, &["9", "Digit9"]),
+    (OsCode::KEY_0, &["0", "Digit0"]),
+    (OsCode::KEY_MINUS, &["min", "Minus", "‐"]),
+    (OsCode::KEY_EQUAL, &["eql", "Equal", "₌"]),
+    (OsCode::KEY_BACKSPACE, &["bspc", "Backspace", "bks", "␈", "⌫"]),
+    (OsCode::KEY_TAB, &["tab", "Tab", "⭾", "↹"]),
+    (OsCode::KEY_Q, &["q", "KeyQ"]),
+    (OsCode::KEY_W, &["w", "KeyW"]),
+    (OsCode::KEY_E, &["e", "KeyE"]),
+    (OsCode::KEY_R, &["r", "KeyR"]),
+    (OsCode::KEY_T, &["t", "KeyT"]),
+    (OsCode::KEY_Y, &["y", "KeyY"]),
+    (OsCode::KEY_U, &["u", "KeyU"]),
+    (OsCode::KEY_I, &["i", "KeyI"]),
+    (OsCode::KEY_O, &["o", "KeyO"]),
+    (OsCode::KEY_P, &["p", "KeyP"]),
+    (OsCode::KEY_LEFTBRACE, &["lbrc", "BracketLeft", "【", "「", "〔", "⎡"]),
+    (OsCode::KEY_RIGHTBRACE, &["rbrc", "BracketRight", "】", "」", "〕", "⎣"]),
+    (OsCode::KEY_CAPSLOCK, &["caps", "CapsLock", "⇪"]),
+    (OsCode::KEY_A, &["a", "KeyA"]),
+    (OsCode::KEY_S, &["s", "KeyS"]),
+    (OsCode::KEY_D, &["d", "KeyD"]),
+    (OsCode::KEY_F, &["f", "KeyF"]),
+    (OsCode::KEY_G, &["g", "KeyG"]),
+    (OsCode::KEY_H, &["h", "KeyH"]),
+    (OsCode::KEY_J, &["j", "KeyJ"]),
+    (OsCode::KEY_K, &["k", "KeyK"]),
+    (OsCode::KEY_L, &["l", "KeyL"]),
+    (OsCode::KEY_SEMICOLON, &["scln", "Semicolon", "︔"]),
+    (OsCode::KEY_APOSTROPHE, &["apo", "Quote", "apos"]),
+    (OsCode::KEY_ENTER, &["ret", "Enter", "return", "ent", "enter", "⏎", "↩", "⌤", "␤"]),
+    (OsCode::KEY_LEFTSHIFT, &["lsft", "ShiftLeft", "lshift", "lshft", "shft", "sft", "‹⇧"]),
+    (OsCode::KEY_Z, &["z", "KeyZ"]),
+    (OsCode::KEY_X, &["x", "KeyX"]),
+    (OsCode::KEY_C, &["c", "KeyC"]),
+    (OsCode::KEY_V, &["v", "KeyV"]),
+    (OsCode::KEY_B, &["b", "KeyB"]),
+    (OsCode::KEY_N, &["n", "KeyN"]),
+    (OsCode::KEY_M, &["m", "KeyM"]),
+    (OsCode::KEY_COMMA, &["comm", "Comma", "⸴"]),
+    (OsCode::KEY_DOT, &["Period", "．"]),
+    (OsCode::KEY_SLASH, &["Slash", "⁄"]),
+    (OsCode::KEY_BACKSLASH, &["bksl", "Backslash", "⧵", "＼"]),
+    (OsCode::KEY_CLEAR, &["kp=", "clr"]),
+    // The kp<etc> keys are also known as the numpad keys. E.g. below is numpad enter.
+    (OsCode::KEY_KP0, &["kp0", "Numpad0", "🔢₀"]),
+    (OsCode::KEY_KP1, &["kp1", "Numpad1", "🔢₁"]),
+    (OsCode::KEY_KP2, &["kp2", "Numpad2", "🔢₂"]),
+    (OsCode::KEY_KP3, &["kp3", "Numpad3", "🔢₃"]),
+    (OsCode::KEY_KP4, &["kp4", "Numpad4", "🔢₄"]),
+    (OsCode::KEY_KP5, &["kp5", "Numpad5", "🔢₅"]),
+    (OsCode::KEY_KP6, &["kp6", "Numpad6", "🔢₆"]),
+    (OsCode::KEY_KP7, &["kp7", "Numpad7", "🔢₇"]),
+    (OsCode::KEY_KP8, &["kp8", "Numpad8", "🔢₈"]),
+    (OsCode::KEY_KP9, &["kp9", "Numpad9", "🔢₉"]),
+    (OsCode::KEY_KPENTER, &["kprt", "NumpadEnter", "🔢⏎", "🔢↩", "🔢⌤", "🔢␤"]),
+    (OsCode::KEY_KPSLASH, &["kp/", "NumpadDivide", "🔢⁄"]),
+    (OsCode::KEY_KPPLUS, &["kp+", "NumpadAdd", "🔢₊"]),
+    (OsCode::KEY_KPASTERISK, &["kp*", "NumpadMultiply", "🔢∗"]),
+    (OsCode::KEY_KPEQUAL, &["NumpadEqual", "🔢₌"]),
+    (OsCode::KEY_KPMINUS, &["kp-", "NumpadSubtract", "🔢₋"]),
+    (OsCode::KEY_KPDOT, &["kp.", "NumpadDecimal", "🔢．"]),
+    (OsCode::KEY_KPCOMMA, &["kp,", "NumpadComma", "🔢⸴"]),
+    (OsCode::KEY_SYSRQ, &["ssrq", "sys"]),
+    // Typically the Non-US backslash, near the left shift key
+    (OsCode::KEY_102ND, &["102d", "IntlBackslash", "lsgt", "nubs", "nonusbslash", "﹨", "<"]),
+    (OsCode::KEY_SCROLLLOCK, &["slck", "ScrollLock", "scrlck", "⇳🔒"]),
+    (OsCode::KEY_PAUSE, &["pause", "Pause", "break", "brk"]),
+    (OsCode::KEY_WAKEUP, &["wkup", "WakeUp"]),
+    (OsCode::KEY_ESC, &["esc", "Escape", "⎋"]),
+    (OsCode::KEY_RIGHTSHIFT, &["rsft", "ShiftRight", "RightShift", "rshift", "rshft", "⇧›"]),
+    (OsCode::KEY_LEFTCTRL, &["lctl", "ControlLeft", "lctrl", "ctl", "‹⎈", "‹⌃"]),
+    (OsCode::KEY_LEFTALT, &["lalt", "AltLeft", "alt", "‹⎇", "‹⌥"]),
+    (OsCode::KEY_SPACE, &["spc", "Space", "␠", "␣"]),
+    (OsCode::KEY_RIGHTALT, &["ralt", "AltRight", "⎇›", "⌥›"]),
+    (OsCode::KEY_COMPOSE, &["comp", "ContextMenu", "cmps", "cmp", "menu", "apps", "▤", "☰", "𝌆"]),
+    (OsCode::KEY_DASHBOARD, &["🎛"]),
+    // Also known as Windows, GUI, Comand, Super
+    (OsCode::KEY_LEFTMETA, &["lmet", "MetaLeft", "lmeta", "met", "‹◆", "‹⌘", "‹❖"]),
+    (OsCode::KEY_RIGHTMETA, &["rmet", "MetaRight", "rmeta", "◆›", "⌘›", "❖›"]),
+    (OsCode::KEY_RIGHTCTRL, &["rctl", "ControlRight", "rctrl", "⎈›", "⌃›"]),
+    (OsCode::KEY_DELETE, &["del", "Delete", "␡", "⌦"]),
+    (OsCode::KEY_INSERT, &["ins", "Insert", "⎀"]),
+    (OsCode::KEY_BACK, &["bck", "BrowserBack"]),
+    (OsCode::KEY_FORWARD, &["fwd", "BrowserForward"]),
+    (OsCode::KEY_PAGEUP, &["pgup", "PageUp", "⇞"]),
+    (OsCode::KEY_PAGEDOWN, &["pgdn", "PageDown", "⇟"]),
+    (OsCode::KEY_UP, &["up", "ArrowUp", "▲"]),
+    (OsCode::KEY_DOWN, &["down", "ArrowDown", "▼"]),
+    (OsCode::KEY_LEFT, &["lft", "ArrowLeft", "left", "◀"]),
+    (OsCode::KEY_RIGHT, &["rght", "ArrowRight", "▶"]),
+    (OsCode::KEY_HOME, &["home", "Home", "⇤", "⤒", "↖"]),
+    (OsCode::KEY_END, &["end", "End", "⇥", "⤓", "↘"]),
+    (OsCode::KEY_NUMLOCK, &["nlck", "NumLock", "nlk", "⇭"]),
+    (OsCode::KEY_MUTE, &["mute", "VolumeMute", "🔇", "🔈⓪", "🔈⓿", "🔈₀"]),
+    (OsCode::KEY_VOLUMEUP, &["volu", "VolumeUp", "🔊", "🔈+", "🔈➕", "🔈₊", "🔈⊕"]),
+    (OsCode::KEY_VOLUMEDOWN, &["vold", "VolumeDown", "voldwn", "🔉", "🔈−", "🔈➖", "🔈₋", "🔈⊖"]),
+    (OsCode::KEY_BRIGHTNESSUP, &["brup", "bru", "🔆"]),
+    (OsCode::KEY_BRIGHTNESSDOWN, &["brdn", "brdown", "brdwn", "🔅"]),
+    (OsCode::KEY_KBDILLUMUP, &["blup", "⌨💡+", "⌨💡➕", "⌨💡₊", "⌨💡⊕"]),
+    (OsCode::KEY_KBDILLUMDOWN, &["bldn", "⌨💡−", "⌨💡➖", "⌨💡₋", "⌨💡⊖"]),
+    (OsCode::KEY_NEXTSONG, &["next", "MediaTrackNext", "▶▶"]),
+    (OsCode::KEY_PLAYPAUSE, &["pp", "MediaPlayPause", "▶⏸"]),
+    (OsCode::KEY_PREVIOUSSONG, &["prev", "MediaTrackPrevious", "◀◀"]),
+    (OsCode::KEY_F1, &["f1", "F1"]),
+    (OsCode::KEY_F2, &["f2", "F2"]),
+    (OsCode::KEY_F3, &["f3", "F3"]),
+    (OsCode::KEY_F4, &["f4", "F4"]),
+    (OsCode::KEY_F5, &["f5", "F5"]),
+    (OsCode::KEY_F6, &["f6", "F6"]),
+    (OsCode::KEY_F7, &["f7", "F7"]),
+    (OsCode::KEY_F8, &["f8", "F8"]),
+    (OsCode::KEY_F9, &["f9", "F9"]),
+    (OsCode::KEY_F10, &["f10", "F10"]),
+    (OsCode::KEY_F11, &["f11", "F11"]),
+    (OsCode::KEY_F12, &["f12", "F12"]),
+    (OsCode::KEY_F13, &["f13", "F13"]),
+    (OsCode::KEY_F14, &["f14", "F14"]),
+    (OsCode::KEY_F15, &["f15", "F15"]),
+    (OsCode::KEY_F16, &["f16", "F16"]),
+    (OsCode::KEY_F17, &["f17", "F17"]),
+    (OsCode::KEY_F18, &["f18", "F18"]),
+    (OsCode::KEY_F19, &["f19", "F19"]),
+    (OsCode::KEY_F20, &["f20", "F20"]),
+    (OsCode::KEY_F21, &["f21", "F21"]),
+    (OsCode::KEY_F22, &["f22", "F22"]),
+    (OsCode::KEY_F23, &["f23", "F23"]),
+    (OsCode::KEY_F24, &["f24", "F24"]),
+    #[cfg(any(target_os = "macos", target_os = "unknown"))]
+    (OsCode::KEY_FN, &["fn", "🌐", "ƒ", "ⓕ", "Ⓕ", "🄵", "🅕", "🅵"]),
+    #[cfg(target_os = "windows")]
+    (OsCode::KEY_HANGEUL, &["kana", "katakana", "katakanahiragana"]),
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_KATAKANAHIRAGANA, &["kana", "katakanahiragana"]),
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_HIRAGANA, &["hiragana"]),
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_KATAKANA, &["katakana"]),
+    (OsCode::KEY_HENKAN, &["cnv", "conv", "henk", "hnk", "henkan"]),
+    (OsCode::KEY_MUHENKAN, &["ncnv", "mhnk", "muhenkan"]),
+    (OsCode::KEY_RO, &["ro", "IntlRo"]),
 
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "PrintScreen" | "prtsc" | "prnt" => OsCode::KEY_SYSRQ,
-        #[cfg(target_os = "windows")]
-        "PrintScreen" | "prtsc" | "prnt" => OsCode::KEY_PRINT,
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_SYSRQ, &["prtsc", "PrintScreen", "prnt"]),
+    #[cfg(target_os = "windows")]
+    (OsCode::KEY_PRINT, &["prtsc", "PrintScreen", "prnt"]),
 
-        // NOTE: these are linux and interception-only due to missing implementation for LLHOOK.
-        "mlft" | "mouseleft" | "🖰1" | "‹🖰" => OsCode::BTN_LEFT,
-        "mrgt" | "mouseright" | "🖰2" | "🖰›" => OsCode::BTN_RIGHT,
-        "mmid" | "mousemid" | "🖰3" => OsCode::BTN_MIDDLE,
-        "mbck" | "mousebackward" | "🖰4" => OsCode::BTN_SIDE,
-        "mfwd" | "mouseforward" | "🖰5" => OsCode::BTN_EXTRA,
-        "mwu" | "mousewheelup" => OsCode::MouseWheelUp,
-        "mwd" | "mousewheeldown" => OsCode::MouseWheelDown,
-        "mwl" | "mousewheelleft" => OsCode::MouseWheelLeft,
-        "mwr" | "mousewheelright" => OsCode::MouseWheelRight,
+    // NOTE: these are linux and interception-only due to missing implementation for LLHOOK.
+    (OsCode::BTN_LEFT, &["mlft", "mouseleft", "🖰1", "‹🖰"]),
+    (OsCode::BTN_RIGHT, &["mrgt", "mouseright", "🖰2", "🖰›"]),
+    (OsCode::BTN_MIDDLE, &["mmid", "mousemid", "🖰3"]),
+    (OsCode::BTN_SIDE, &["mbck", "mousebackward", "🖰4"]),
+    (OsCode::BTN_EXTRA, &["mfwd", "mouseforward", "🖰5"]),
+    (OsCode::MouseWheelUp, &["mwu", "mousewheelup"]),
+    (OsCode::MouseWheelDown, &["mwd", "mousewheeldown"]),
+    (OsCode::MouseWheelLeft, &["mwl", "mousewheelleft"]),
+    (OsCode::MouseWheelRight, &["mwr", "mousewheelright"]),
+    (OsCode::MouseMoveUp, &["mmu", "mousemoveup"]),
+    (OsCode::MouseMoveDown, &["mmd", "mousemovedown"]),
+    (OsCode::MouseMoveLeft, &["mml", "mousemoveleft"]),
+    (OsCode::MouseMoveRight, &["mmr", "mousemoveright"]),
 
-        "hmpg" | "homepage" => OsCode::KEY_HOMEPAGE,
-        "mdia" | "media" => OsCode::KEY_MEDIA,
-        "LaunchMail" | "mail" => OsCode::KEY_MAIL,
-        "email" => OsCode::KEY_EMAIL,
-        "calc" => OsCode::KEY_CALC,
+    (OsCode::KEY_HOMEPAGE, &["hmpg", "homepage"]),
+    (OsCode::KEY_MEDIA, &["mdia", "media"]),
+    (OsCode::KEY_MAIL, &["mail", "LaunchMail"]),
+    (OsCode::KEY_EMAIL, &["email"]),
+    (OsCode::KEY_CALC, &["calc"]),
 
-        // NOTE: these are linux-only right now due to missing the mappings in windows.rs
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "plyr" | "player" => OsCode::KEY_PLAYER,
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "powr" | "power" => OsCode::KEY_POWER,
-        #[cfg(any(target_os = "linux", target_os = "unknown"))]
-        "zzz" | "sleep" => OsCode::KEY_SLEEP,
+    // NOTE: these are linux-only right now due to missing the mappings in windows.rs
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_PLAYER, &["plyr", "player"]),
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_POWER, &["powr", "power"]),
+    #[cfg(any(target_os = "linux", target_os = "unknown"))]
+    (OsCode::KEY_SLEEP, &["zzz", "sleep"]),
 
-        // Keys that behave as no-ops but can be used in sequences.
-        // Also see: POTENTIAL PROBLEM - G-keys
-        "nop0" => OsCode::KEY_676,
-        "nop1" => OsCode::KEY_677,
-        "nop2" => OsCode::KEY_678,
-        "nop3" => OsCode::KEY_679,
-        "nop4" => OsCode::KEY_680,
-        "nop5" => OsCode::KEY_681,
-        "nop6" => OsCode::KEY_682,
-        "nop7" => OsCode::KEY_683,
-        "nop8" => OsCode::KEY_684,
-        "nop9" => OsCode::KEY_685,
+    // Keys that behave as no-ops but can be used in sequences.
+    // Also see: POTENTIAL PROBLEM - G-keys
+    (OsCode::KEY_676, &["nop0"]),
+    (OsCode::KEY_677, &["nop1"]),
+    (OsCode::KEY_678, &["nop2"]),
+    (OsCode::KEY_679, &["nop3"]),
+    (OsCode::KEY_680, &["nop4"]),
+    (OsCode::KEY_681, &["nop5"]),
+    (OsCode::KEY_682, &["nop6"]),
+    (OsCode::KEY_683, &["nop7"]),
+    (OsCode::KEY_684, &["nop8"]),
+    (OsCode::KEY_685, &["nop9"]),
+];
 
-        _ => return None,
-    })
+static STRS_TO_OSCODES: Lazy<HashMap<&'static str, OsCode>> = Lazy::new(|| {
+    let mut mapping = HashMap::default();
+    for (osc, names) in KEY_NAMES {
+        for name in names.iter() {
+            mapping.entry(*name).or_insert(*osc);
+        }
+    }
+    mapping
+});
+
+static OSCODES_TO_STRS: Lazy<HashMap<OsCode, Vec<&'static str>>> = Lazy::new(|| {
+    let mut mapping: HashMap<OsCode, Vec<&'static str>> = HashMap::default();
+    for (osc, names) in KEY_NAMES {
+        mapping
+            .entry(*osc)
+            .or_default()
+            .extend(names.iter().copied());
+    }
+    mapping
+});
+
+/// Convert an `OsCode` to a name that `str_to_oscode` accepts, preferring the first name in
+/// `KEY_NAMES`. Returns `None` if the key has no name in the configuration language.
+pub fn oscode_to_str(osc: OsCode) -> Option<&'static str> {
+    // A name can be remapped to another key by `deflocalkeys`.
+    OSCODES_TO_STRS
+        .get(&osc)?
+        .iter()
+        .copied()
+        .find(|name| str_to_oscode(name) == Some(osc))
 }
 
 /// This is a shameless copy of evdev_rs::enums::EV_KEY.
@@ -1134,10 +1172,13 @@ pub enum OsCode {
     MouseWheelLeft = 747,
     MouseWheelRight = 748,
 
-    KEY_749 = 749,
-    KEY_750 = 750,
-    KEY_751 = 751,
-    KEY_752 = 752,
+    // Mouse movement is likewise turned into key taps, once the movement in
+    // one direction reaches the configured threshold.
+    MouseMoveUp = 749,
+    MouseMoveDown = 750,
+    MouseMoveLeft = 751,
+    MouseMoveRight = 752,
+
     KEY_753 = 753,
     KEY_754 = 754,
     KEY_755 = 755,
@@ -1173,6 +1214,18 @@ fn parser_key_max_lt_keyberon_key_max() {
     assert!(u16::from(OsCode::KEY_MAX) < KEY_MAX);
 }
 
+#[test]
+fn oscode_to_str_roundtrips() {
+    for (osc, names) in KEY_NAMES {
+        assert_eq!(Some(*osc), oscode_to_str(*osc).and_then(str_to_oscode));
+        for name in names.iter() {
+            assert!(str_to_oscode(name).is_some());
+        }
+    }
+    assert_eq!(Some("lsft"), oscode_to_str(OsCode::KEY_LEFTSHIFT));
+    assert_eq!(Some("a"), oscode_to_str(OsCode::KEY_A));
+}
+
 impl TryFrom<usize> for OsCode {
     type Error = ();
     fn try_from(item: usize) -> Result<Self, Self::Error> {
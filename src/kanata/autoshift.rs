@@ -0,0 +1,155 @@
+//! Auto-shift: a key that is held for longer than the timeout outputs its shifted variant instead
+//! of the key itself. The output of an auto-shifted key is held back until the key is released,
+//! another key is pressed, or the timeout expires.
+
+use kanata_keyberon::key_code::KeyCode;
+use kanata_parser::cfg::CfgAutoShiftOptions;
+use kanata_parser::keys::OsCode;
+use rustc_hash::{FxHashMap as HashMap, FxHashSet as HashSet};
+
+use KeyCode::*;
+
+/// Keys that are auto-shifted when `autoshift-keys` is not configured.
+const DEFAULT_KEYS: &[KeyCode] = &[
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z, Kb1, Kb2, Kb3,
+    Kb4, Kb5, Kb6, Kb7, Kb8, Kb9, Kb0, Minus, Equal, LBracket, RBracket, Bslash, SColon, Quote,
+    Grave, Comma, Dot, Slash,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// The output is held back. Contains the number of ticks the key has been held.
+    Pending(u16),
+    /// Another key was pressed before the timeout so the key is output as-is.
+    Unshifted,
+    /// The timeout expired and the shifted output is active.
+    Shifted,
+    /// The shifted output was tapped; nothing is output until the key is released.
+    Done,
+}
+
+#[derive(Debug)]
+struct HeldKey {
+    key: KeyCode,
+    phase: Phase,
+}
+
+#[derive(Debug)]
+pub struct AutoShiftState {
+    keys: HashSet<KeyCode>,
+    custom_outputs: HashMap<KeyCode, Vec<KeyCode>>,
+    timeout: u16,
+    repeat: bool,
+    held: Vec<HeldKey>,
+    /// Active keys of the previous tick, before auto-shift processing. Used to find new presses.
+    prev_keys: Vec<KeyCode>,
+    /// Keys released before the timeout, which are tapped without shift.
+    taps: Vec<KeyCode>,
+}
+
+impl AutoShiftState {
+    /// Returns None if auto-shift is not enabled.
+    pub(crate) fn new(cfg: &CfgAutoShiftOptions) -> Option<Self> {
+        if !cfg.enabled {
+            return None;
+        }
+        let mut keys: HashSet<KeyCode> = match &cfg.keys {
+            Some(keys) => keys.iter().copied().collect(),
+            None => DEFAULT_KEYS.iter().copied().collect(),
+        };
+        keys.extend(cfg.custom_outputs.iter().map(|(k, _)| *k));
+        for k in cfg.exclude_keys.iter() {
+            keys.remove(k);
+        }
+        Some(Self {
+            keys,
+            custom_outputs: cfg.custom_outputs.iter().cloned().collect(),
+            timeout: cfg.timeout,
+            repeat: cfg.repeat,
+            held: vec![],
+            prev_keys: vec![],
+            taps: vec![],
+        })
+    }
+
+    /// Returns true if no key output is being held back or replaced.
+    pub(crate) fn is_idle(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Replaces the active keys with the auto-shift outputs. Should be called once per tick.
+    pub(crate) fn process_keys(&mut self, active_keys: &mut Vec<KeyCode>) {
+        let taps = &mut self.taps;
+        self.held.retain(|h| {
+            if active_keys.contains(&h.key) {
+                return true;
+            }
+            if matches!(h.phase, Phase::Pending(_)) {
+                taps.push(h.key);
+            }
+            false
+        });
+
+        // Auto-shift does not apply to keys pressed together with a modifier, e.g. C-a.
+        let modifier_held = active_keys.iter().any(|k| OsCode::from(k).is_modifier());
+        for k in active_keys.iter().copied() {
+            if self.prev_keys.contains(&k) {
+                continue;
+            }
+            for h in self.held.iter_mut() {
+                if matches!(h.phase, Phase::Pending(_)) {
+                    h.phase = Phase::Unshifted;
+                }
+            }
+            if !modifier_held && self.keys.contains(&k) && !self.held.iter().any(|h| h.key == k) {
+                self.held.push(HeldKey {
+                    key: k,
+                    phase: Phase::Pending(0),
+                });
+            }
+        }
+        self.prev_keys.clear();
+        self.prev_keys.extend(active_keys.iter().copied());
+
+        for h in self.held.iter_mut() {
+            h.phase = match h.phase {
+                Phase::Pending(ticks) if ticks + 1 >= self.timeout => Phase::Shifted,
+                Phase::Pending(ticks) => Phase::Pending(ticks + 1),
+                Phase::Shifted if !self.repeat => Phase::Done,
+                phase => phase,
+            };
+        }
+
+        for h in self.held.iter() {
+            match h.phase {
+                Phase::Unshifted => {}
+                Phase::Pending(_) | Phase::Done => active_keys.retain(|k| *k != h.key),
+                Phase::Shifted => {
+                    active_keys.retain(|k| *k != h.key);
+                    match self.custom_outputs.get(&h.key) {
+                        Some(outputs) => active_keys.extend(outputs.iter().copied()),
+                        None => active_keys.extend([LShift, h.key]),
+                    }
+                }
+            }
+        }
+        active_keys.append(&mut self.taps);
+    }
+
+    /// Returns the key to send a repeat event for when the key is repeated by the OS, or None
+    /// if the repeat should be suppressed because the output of the key is held back.
+    pub(crate) fn repeat_output(&self, key: KeyCode) -> Option<KeyCode> {
+        let Some(h) = self.held.iter().find(|h| h.key == key) else {
+            return Some(key);
+        };
+        match h.phase {
+            Phase::Unshifted => Some(key),
+            Phase::Shifted => self
+                .custom_outputs
+                .get(&key)
+                .and_then(|outputs| outputs.last().copied())
+                .or(Some(key)),
+            Phase::Pending(_) | Phase::Done => None,
+        }
+    }
+}
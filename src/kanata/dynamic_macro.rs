@@ -1,8 +1,14 @@
 use std::collections::VecDeque;
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
 
+use anyhow::{anyhow, bail, Result};
 use kanata_keyberon::layout::Event;
 use kanata_parser::cfg::ReplayDelayBehaviour;
-use kanata_parser::keys::OsCode;
+use kanata_parser::keys::{oscode_to_str, str_to_oscode, OsCode};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
 use rustc_hash::FxHashMap as HashMap;
 use rustc_hash::FxHashSet as HashSet;
 
@@ -41,6 +47,16 @@ impl DynamicMacroRecordState {
         }
     }
 
+    /// The id of the macro being recorded.
+    pub fn macro_id(&self) -> u16 {
+        self.starting_macro_id
+    }
+
+    /// The number of press and release events recorded so far.
+    pub fn recorded_events(&self) -> usize {
+        self.macro_items.len()
+    }
+
     fn add_release_for_all_unreleased_presses(&mut self) {
         let mut pressed_oscs = HashSet::default();
         for item in self.macro_items.iter() {
@@ -313,3 +329,350 @@ pub fn play_macro(
         }
     }
 }
+
+const MACRO_FILE_HEADER: &str = "\
+;; Dynamic macros saved by kanata. This file can be edited by hand.
+;; Each line is `<macro ID>: <events>`. The events are:
+;;   d:<key>  press a key
+;;   u:<key>  release a key
+;;   t:<ms>   wait after the previous event
+";
+
+/// Serialize macros into the dynamic macro file format. Macros are written in order of their id.
+pub fn macros_to_string(macros: &HashMap<u16, Vec<DynamicMacroItem>>) -> Result<String> {
+    let mut ids: Vec<u16> = macros.keys().copied().collect();
+    ids.sort_unstable();
+    let mut out = String::from(MACRO_FILE_HEADER);
+    for id in ids {
+        let _ = write!(out, "{id}:");
+        for item in macros[&id].iter() {
+            let (prefix, osc, delay) = match item {
+                DynamicMacroItem::Press((osc, delay)) => ("d", osc, delay),
+                DynamicMacroItem::Release((osc, delay)) => ("u", osc, delay),
+                DynamicMacroItem::EndMacro(_) => continue,
+            };
+            let name = oscode_to_str(*osc)
+                .ok_or_else(|| anyhow!("key {osc} of macro {id} has no name to write"))?;
+            let _ = write!(out, " {prefix}:{name}");
+            if *delay > 0 {
+                let _ = write!(out, " t:{delay}");
+            }
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Parse macros from the dynamic macro file format.
+pub fn macros_from_str(text: &str) -> Result<HashMap<u16, Vec<DynamicMacroItem>>> {
+    let mut macros = HashMap::default();
+    for (line_num, line) in text.lines().enumerate().map(|(i, l)| (i + 1, l.trim())) {
+        if line.is_empty() || line.starts_with(";;") {
+            continue;
+        }
+        let (id, events) = line
+            .split_once(':')
+            .ok_or_else(|| anyhow!("line {line_num}: expected `<macro ID>: <events>`"))?;
+        let id: u16 = id
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("line {line_num}: invalid macro ID {:?}", id.trim()))?;
+        let mut items = vec![];
+        for event in events.split_whitespace() {
+            let (kind, val) = event.split_once(':').ok_or_else(|| {
+                anyhow!("line {line_num}: expected d:<key>, u:<key> or t:<ms>, found {event}")
+            })?;
+            match kind {
+                "d" | "u" => {
+                    let osc = str_to_oscode(val)
+                        .ok_or_else(|| anyhow!("line {line_num}: unknown key {val}"))?;
+                    items.push(match kind {
+                        "d" => DynamicMacroItem::Press((osc, 0)),
+                        _ => DynamicMacroItem::Release((osc, 0)),
+                    });
+                }
+                "t" => {
+                    let ms: u16 = val
+                        .parse()
+                        .map_err(|_| anyhow!("line {line_num}: invalid delay {val}"))?;
+                    match items.last_mut() {
+                        Some(
+                            DynamicMacroItem::Press((_, delay))
+                            | DynamicMacroItem::Release((_, delay)),
+                        ) => *delay = delay.saturating_add(ms),
+                        _ => bail!("line {line_num}: t:{val} must follow a d: or u: event"),
+                    }
+                }
+                _ => bail!("line {line_num}: expected d:<key>, u:<key> or t:<ms>, found {event}"),
+            }
+        }
+        if macros.insert(id, items).is_some() {
+            bail!("line {line_num}: macro ID {id} is defined more than once");
+        }
+    }
+    Ok(macros)
+}
+
+/// Read all macros from the file. A file that does not exist yet contains no macros.
+pub fn read_macro_file(path: &Path) -> Result<HashMap<u16, Vec<DynamicMacroItem>>> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => macros_from_str(&text),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(HashMap::default()),
+        Err(e) => bail!("could not read {}: {e}", path.display()),
+    }
+}
+
+/// Read all macros from the file if one is configured. Errors are logged rather than returned so
+/// that a broken file does not prevent kanata from starting.
+pub fn load_macro_file(path: Option<&Path>) -> HashMap<u16, Vec<DynamicMacroItem>> {
+    let Some(path) = path else {
+        return HashMap::default();
+    };
+    flush_macro_file();
+    match read_macro_file(path) {
+        Ok(macros) => {
+            log::info!(
+                "loaded {} dynamic macros from {}",
+                macros.len(),
+                path.display()
+            );
+            macros
+        }
+        Err(e) => {
+            log::error!("failed to load dynamic macros: {e}");
+            HashMap::default()
+        }
+    }
+}
+
+/// Replace the macro with the given id in the file, or remove it if `items` is `None`. Other
+/// macros in the file are kept as they are.
+pub fn update_macro_file(
+    path: &Path,
+    macro_id: u16,
+    items: Option<&[DynamicMacroItem]>,
+) -> Result<()> {
+    let mut macros = read_macro_file(path)?;
+    match items {
+        Some(items) => {
+            macros.insert(macro_id, items.to_vec());
+        }
+        None => {
+            macros.remove(&macro_id);
+        }
+    }
+    std::fs::write(path, macros_to_string(&macros)?)
+        .map_err(|e| anyhow!("could not write {}: {e}", path.display()))
+}
+
+enum MacroFileUpdate {
+    Write {
+        path: PathBuf,
+        macro_id: u16,
+        items: Option<Vec<DynamicMacroItem>>,
+    },
+    Flush(Sender<()>),
+}
+
+/// Channel to the thread that writes the dynamic macro file, so that the processing loop does
+/// not wait on file IO. The updates are written in the order they were sent.
+static MACRO_FILE_WRITER: Lazy<Mutex<Sender<MacroFileUpdate>>> = Lazy::new(|| {
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for update in rx {
+            match update {
+                MacroFileUpdate::Write {
+                    path,
+                    macro_id,
+                    items,
+                } => match update_macro_file(&path, macro_id, items.as_deref()) {
+                    Ok(()) if items.is_some() => {
+                        log::info!("saved dynamic macro {macro_id} to {}", path.display())
+                    }
+                    Ok(()) => {
+                        log::info!("removed dynamic macro {macro_id} from {}", path.display())
+                    }
+                    Err(e) => log::error!("failed to update dynamic macro {macro_id}: {e}"),
+                },
+                MacroFileUpdate::Flush(done) => {
+                    let _ = done.send(());
+                }
+            }
+        }
+    });
+    Mutex::new(tx)
+});
+
+/// Replace or remove the macro in the file like `update_macro_file`, on the writer thread.
+fn update_macro_file_in_background(
+    path: &Path,
+    macro_id: u16,
+    items: Option<Vec<DynamicMacroItem>>,
+) {
+    let update = MacroFileUpdate::Write {
+        path: path.to_owned(),
+        macro_id,
+        items,
+    };
+    if let Err(e) = MACRO_FILE_WRITER.lock().send(update) {
+        log::error!("failed to save dynamic macro {macro_id}: {e}");
+    }
+}
+
+/// Wait until the macro file updates sent so far have been written, e.g. before reading the file
+/// or exiting.
+pub(crate) fn flush_macro_file() {
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    if MACRO_FILE_WRITER
+        .lock()
+        .send(MacroFileUpdate::Flush(done_tx))
+        .is_ok()
+    {
+        let _ = done_rx.recv_timeout(std::time::Duration::from_millis(500));
+    }
+}
+
+/// Keep a finished recording and write it to the dynamic macro file, if one is configured.
+pub fn store_macro(
+    macros: &mut HashMap<u16, Vec<DynamicMacroItem>>,
+    macro_file: Option<&Path>,
+    macro_id: u16,
+    items: Vec<DynamicMacroItem>,
+) {
+    if let Some(path) = macro_file {
+        update_macro_file_in_background(path, macro_id, Some(items.clone()));
+    }
+    macros.insert(macro_id, items);
+}
+
+pub fn save_macro(
+    macros: &mut HashMap<u16, Vec<DynamicMacroItem>>,
+    macro_file: Option<&Path>,
+    macro_id: u16,
+) {
+    let Some(path) = macro_file else {
+        log::warn!("dynamic-macro-save requires dynamic-macro-file in defcfg");
+        return;
+    };
+    let Some(items) = macros.get(&macro_id) else {
+        log::warn!("dynamic macro {macro_id} has not been recorded, nothing to save");
+        return;
+    };
+    update_macro_file_in_background(path, macro_id, Some(items.clone()));
+}
+
+pub fn load_macro(
+    macros: &mut HashMap<u16, Vec<DynamicMacroItem>>,
+    macro_file: Option<&Path>,
+    macro_id: u16,
+) {
+    let Some(path) = macro_file else {
+        log::warn!("dynamic-macro-load requires dynamic-macro-file in defcfg");
+        return;
+    };
+    flush_macro_file();
+    match read_macro_file(path) {
+        Ok(mut saved) => match saved.remove(&macro_id) {
+            Some(items) => {
+                log::info!("loaded dynamic macro {macro_id} from {}", path.display());
+                macros.insert(macro_id, items);
+            }
+            None => log::warn!("dynamic macro {macro_id} is not in {}", path.display()),
+        },
+        Err(e) => log::error!("failed to load dynamic macro {macro_id}: {e}"),
+    }
+}
+
+/// Forget the macro and remove it from the dynamic macro file, if one is configured.
+pub fn clear_macro(
+    macros: &mut HashMap<u16, Vec<DynamicMacroItem>>,
+    macro_file: Option<&Path>,
+    macro_id: u16,
+) {
+    log::info!("clearing dynamic macro {macro_id}");
+    macros.remove(&macro_id);
+    if let Some(path) = macro_file {
+        update_macro_file_in_background(path, macro_id, None);
+    }
+}
+
+/// Modifiers that can be written as a prefix of a key within `macro`, e.g. `S-a`.
+#[cfg(feature = "tcp_server")]
+const MACRO_MOD_PREFIXES: &[(OsCode, &str)] = &[
+    (OsCode::KEY_LEFTCTRL, "C-"),
+    (OsCode::KEY_RIGHTCTRL, "RC-"),
+    (OsCode::KEY_LEFTALT, "A-"),
+    (OsCode::KEY_RIGHTALT, "RA-"),
+    (OsCode::KEY_LEFTMETA, "M-"),
+    (OsCode::KEY_RIGHTMETA, "RM-"),
+    (OsCode::KEY_LEFTSHIFT, "S-"),
+    (OsCode::KEY_RIGHTSHIFT, "RS-"),
+];
+
+/// Convert a recorded macro into the equivalent `macro` action for a configuration file, e.g.
+/// `(macro h 30 i 120 S-a)`.
+///
+/// Every press of a non-modifier key becomes a tap of that key, prefixed by the modifiers held at
+/// the time. A modifier that is pressed and released without any key in between is written as a
+/// tap of the modifier itself. The recorded delays between taps are written as numbers; how long
+/// a key was held is not kept since `macro` only taps keys.
+#[cfg(feature = "tcp_server")]
+pub fn macro_to_config(items: &[DynamicMacroItem]) -> Result<String> {
+    // Held modifiers in the order pressed, and whether a key was tapped while each was held.
+    let mut held_mods: Vec<(OsCode, bool)> = vec![];
+    let mut pending_delay: u16 = 0;
+    let mut macro_items: Vec<String> = vec![];
+    let mut push_tap = |osc: OsCode, held_mods: &[(OsCode, bool)], pending_delay: &mut u16| {
+        let name = oscode_to_str(osc)
+            .ok_or_else(|| anyhow!("key {osc} has no name that can be used in a macro"))?;
+        // A delay before the first tap only reflects when recording started.
+        if *pending_delay > 0 && !macro_items.is_empty() {
+            macro_items.push(pending_delay.to_string());
+        }
+        *pending_delay = 0;
+        // Plain numbers are delays within macro, so digits need their long names.
+        let name = match name.bytes().all(|b| b.is_ascii_digit()) {
+            true => format!("Digit{name}"),
+            false => name.to_string(),
+        };
+        let prefixes: String = MACRO_MOD_PREFIXES
+            .iter()
+            .filter(|(m, _)| held_mods.iter().any(|(held, _)| held == m))
+            .map(|(_, prefix)| *prefix)
+            .collect();
+        macro_items.push(format!("{prefixes}{name}"));
+        Ok::<_, anyhow::Error>(())
+    };
+    let is_mod = |osc: &OsCode| MACRO_MOD_PREFIXES.iter().any(|(m, _)| m == osc);
+    for item in items.iter() {
+        match item {
+            DynamicMacroItem::Press((osc, delay)) => {
+                if is_mod(osc) {
+                    if !held_mods.iter().any(|(m, _)| m == osc) {
+                        held_mods.push((*osc, false));
+                    }
+                } else {
+                    push_tap(*osc, &held_mods, &mut pending_delay)?;
+                    for (_, used) in held_mods.iter_mut() {
+                        *used = true;
+                    }
+                }
+                pending_delay = pending_delay.saturating_add(*delay);
+            }
+            DynamicMacroItem::Release((osc, delay)) => {
+                if let Some(i) = held_mods.iter().position(|(m, _)| m == osc) {
+                    let (_, used) = held_mods.remove(i);
+                    if !used {
+                        push_tap(*osc, &held_mods, &mut pending_delay)?;
+                    }
+                }
+                pending_delay = pending_delay.saturating_add(*delay);
+            }
+            DynamicMacroItem::EndMacro(_) => {}
+        }
+    }
+    if macro_items.is_empty() {
+        bail!("the macro does not contain any key presses");
+    }
+    Ok(format!("(macro {})", macro_items.join(" ")))
+}
@@ -0,0 +1,170 @@
+//! Sends input and output events to TCP clients that subscribed to them.
+
+use super::*;
+
+use kanata_tcp_protocol::{EventMessage, SubscriptionTopic};
+
+/// Channel to the loop that relays events to TCP clients. Events have their own channel so that
+/// a burst of them can't crowd out layer and config notifications. It is global rather than a
+/// part of `EventStream` so that key output can be reported by the output functions.
+#[cfg(feature = "tcp_server")]
+static EVENT_TX: Mutex<Option<Sender<ServerMessage>>> = Mutex::new(None);
+
+/// Set the channel that events are sent on.
+#[cfg(feature = "tcp_server")]
+pub(crate) fn set_event_channel(tx: Option<Sender<ServerMessage>>) {
+    *EVENT_TX.lock() = tx;
+}
+
+/// Send a message for the topic, if any client is subscribed to it. The message is only created
+/// if it will be sent.
+#[allow(unused_variables)]
+pub(crate) fn send_event(topic: SubscriptionTopic, msg: impl FnOnce(u64) -> EventMessage) {
+    #[cfg(feature = "tcp_server")]
+    {
+        use crate::tcp_server::{is_subscribed, timestamp_us};
+        if !is_subscribed(topic) {
+            return;
+        }
+        let Some(tx) = &*EVENT_TX.lock() else {
+            return;
+        };
+        if let Err(e) = tx.try_send(ServerMessage::Event(msg(timestamp_us()))) {
+            log::warn!("could not send {topic:?} event notification: {e}");
+        }
+    }
+}
+
+/// Report a key that was written to the output device.
+pub(crate) fn key_output(osc: OsCode, action: KeyEventAction) {
+    send_event(SubscriptionTopic::KeyOutput, |timestamp_us| {
+        EventMessage::KeyOutput {
+            key: osc.to_string(),
+            action,
+            timestamp_us,
+        }
+    });
+}
+
+impl Kanata {
+    /// Starts a new thread that relays subscribed events to TCP clients.
+    #[cfg(feature = "tcp_server")]
+    pub fn start_event_stream(clients: crate::tcp_server::Connections) {
+        let (tx, rx) = std::sync::mpsc::sync_channel(1000);
+        set_event_channel(Some(tx));
+        Self::start_notification_loop(rx, clients);
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct EventStream {
+    /// Indices of virtual keys that were active as of the previous tick.
+    #[cfg(feature = "tcp_server")]
+    prev_virtual_keys: Vec<u16>,
+}
+
+impl EventStream {
+    pub(crate) fn input(&self, event: &KeyEvent) {
+        let action = match event.value {
+            KeyValue::Press => KeyEventAction::Press,
+            KeyValue::Release => KeyEventAction::Release,
+            KeyValue::Repeat => KeyEventAction::Repeat,
+            KeyValue::Tap | KeyValue::WakeUp => return,
+        };
+        send_event(SubscriptionTopic::InputEvents, |timestamp_us| {
+            EventMessage::InputEvent {
+                key: event.code.to_string(),
+                action,
+                timestamp_us,
+            }
+        });
+    }
+
+    pub(crate) fn mouse(&self, action: impl FnOnce() -> MouseActionMessage) {
+        send_event(SubscriptionTopic::MouseActions, |timestamp_us| {
+            EventMessage::MouseAction {
+                action: action(),
+                timestamp_us,
+            }
+        });
+    }
+
+    pub(crate) fn mouse_press(&self, btn: Btn) {
+        self.mouse(|| MouseActionMessage::Press {
+            button: format!("{btn:?}"),
+        });
+    }
+
+    pub(crate) fn mouse_release(&self, btn: Btn) {
+        self.mouse(|| MouseActionMessage::Release {
+            button: format!("{btn:?}"),
+        });
+    }
+
+    pub(crate) fn mouse_scroll(&self, direction: MWheelDirection, distance: u16) {
+        self.mouse(|| MouseActionMessage::Scroll {
+            direction: format!("{direction:?}"),
+            distance,
+        });
+    }
+
+    pub(crate) fn mouse_move(&self, mv: CalculatedMouseMove) {
+        self.mouse(|| MouseActionMessage::Move {
+            direction: format!("{:?}", mv.direction),
+            distance: mv.distance,
+        });
+    }
+
+    pub(crate) fn unicode(&self, c: char) {
+        send_event(SubscriptionTopic::UnicodeOutput, |timestamp_us| {
+            EventMessage::UnicodeOutput {
+                text: c.to_string(),
+                timestamp_us,
+            }
+        });
+    }
+
+    /// Compare the active virtual keys against the previous tick and send press and release
+    /// events for any that changed.
+    #[cfg(feature = "tcp_server")]
+    pub(crate) fn virtual_keys<T>(&mut self, states: &[State<T>], names: &HashMap<String, usize>) {
+        if !crate::tcp_server::is_subscribed(SubscriptionTopic::VirtualKeys) {
+            self.prev_virtual_keys.clear();
+            return;
+        }
+        let mut cur_virtual_keys: Vec<u16> = states
+            .iter()
+            .filter_map(State::coord)
+            .filter(|(x, _)| *x == FAKE_KEY_ROW)
+            .map(|(_, y)| y)
+            .collect();
+        cur_virtual_keys.sort_unstable();
+        cur_virtual_keys.dedup();
+        let released = self
+            .prev_virtual_keys
+            .iter()
+            .filter(|idx| !cur_virtual_keys.contains(idx))
+            .map(|idx| (*idx, false));
+        let pressed = cur_virtual_keys
+            .iter()
+            .filter(|idx| !self.prev_virtual_keys.contains(idx))
+            .map(|idx| (*idx, true));
+        for (idx, pressed) in released.chain(pressed) {
+            let Some(name) = names
+                .iter()
+                .find(|(_, i)| **i == usize::from(idx))
+                .map(|(name, _)| name.clone())
+            else {
+                continue;
+            };
+            send_event(SubscriptionTopic::VirtualKeys, |timestamp_us| {
+                EventMessage::VirtualKeyChange {
+                    name,
+                    pressed,
+                    timestamp_us,
+                }
+            });
+        }
+        self.prev_virtual_keys = cur_virtual_keys;
+    }
+}
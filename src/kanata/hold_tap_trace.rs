@@ -0,0 +1,156 @@
+//! Reports how tap-hold actions were decided, to help with diagnosing misfires.
+
+use super::*;
+
+use kanata_keyberon::layout::{HoldTapDecision, HoldTapReason, KCoord, WaitingAction};
+#[cfg(feature = "tcp_server")]
+use kanata_tcp_protocol as proto;
+
+fn outcome_str(outcome: WaitingAction) -> &'static str {
+    match outcome {
+        WaitingAction::Tap => "tap",
+        WaitingAction::Hold => "hold",
+        WaitingAction::Timeout => "timeout",
+        WaitingAction::NoOp => "noop",
+    }
+}
+
+fn reason_str(reason: HoldTapReason) -> &'static str {
+    match reason {
+        HoldTapReason::Release => "release",
+        HoldTapReason::ReleaseAfterTimeout => "release-after-timeout",
+        HoldTapReason::Timeout => "timeout",
+        HoldTapReason::ConcurrentTimeout => "concurrent-timeout",
+        HoldTapReason::OtherKeyPress => "other-key-press",
+        HoldTapReason::PermissiveHold => "permissive-hold",
+        HoldTapReason::QuickTap => "quick-tap",
+        HoldTapReason::RequirePriorIdle => "require-prior-idle",
+        HoldTapReason::Custom => "custom",
+        HoldTapReason::TapKeysPress => "tap-keys-press",
+        HoldTapReason::QueueOverflow => "queue-overflow",
+        HoldTapReason::OppositeHand => "opposite-hand",
+        HoldTapReason::SameHand => "same-hand",
+    }
+}
+
+impl Kanata {
+    /// Report the tap-hold decisions made during the tick to the log, the simulator output, the
+    /// statistics and subscribed TCP clients.
+    pub(crate) fn tick_hold_tap_decisions(&mut self) {
+        let layout = self.layout.bm();
+        if layout.hold_tap_decisions.is_empty() {
+            return;
+        }
+        let decisions: Vec<HoldTapDecision> = layout.hold_tap_decisions.drain(..).collect();
+        for decision in decisions.iter() {
+            let key = self.coord_name(decision.coord);
+            if log::log_enabled!(log::Level::Debug) || self.log_hold_tap_decisions {
+                let queued = decision
+                    .queued
+                    .iter()
+                    .map(|q| {
+                        let (dir, coord) = event_parts(q.event());
+                        format!("{dir}{} {}ms ago", self.coord_name(coord), q.since())
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                let msg = format!(
+                    "hold-tap {key}: {} ({}) after {}ms of {}ms timeout; queued: [{queued}]",
+                    outcome_str(decision.outcome),
+                    reason_str(decision.reason),
+                    decision.ticks,
+                    decision.timeout,
+                );
+                if self.log_hold_tap_decisions {
+                    log::info!("{msg}");
+                } else {
+                    log::debug!("{msg}");
+                }
+            }
+            #[cfg(feature = "simulated_output")]
+            if self.log_hold_tap_decisions {
+                let mut sim = format!(
+                    "{key}:{}:{}:{}ms",
+                    outcome_str(decision.outcome),
+                    reason_str(decision.reason),
+                    decision.ticks,
+                );
+                if !decision.queued.is_empty() {
+                    let queued = decision
+                        .queued
+                        .iter()
+                        .map(|q| {
+                            let (dir, coord) = event_parts(q.event());
+                            format!("{dir}{}", self.coord_name(coord))
+                        })
+                        .collect::<Vec<_>>()
+                        .join(",");
+                    sim.push(':');
+                    sim.push_str(&queued);
+                }
+                self.kbd_out.hold_tap_decision(&sim);
+            }
+            if let Some(collector) = &mut self.statistics {
+                if decision.coord.0 == NORMAL_KEY_ROW {
+                    collector.hold_tap(key, decision.outcome);
+                }
+            }
+            #[cfg(feature = "tcp_server")]
+            send_event(proto::SubscriptionTopic::HoldTapDecisions, |timestamp_us| {
+                proto::EventMessage::HoldTapDecision {
+                    decision: self.hold_tap_decision_message(decision),
+                    timestamp_us,
+                }
+            });
+        }
+    }
+
+    #[cfg(feature = "tcp_server")]
+    fn hold_tap_decision_message(&self, decision: &HoldTapDecision) -> proto::HoldTapDecision {
+        proto::HoldTapDecision {
+            position: self.key_position(decision.coord),
+            outcome: outcome_str(decision.outcome).into(),
+            reason: reason_str(decision.reason).into(),
+            elapsed_ms: decision.ticks,
+            timeout_ms: decision.timeout,
+            queued: decision
+                .queued
+                .iter()
+                .map(|q| {
+                    let (_, coord) = event_parts(q.event());
+                    proto::QueuedKeyEvent {
+                        position: self.key_position(coord),
+                        action: if q.event().is_press() {
+                            KeyEventAction::Press
+                        } else {
+                            KeyEventAction::Release
+                        },
+                        age_ms: q.since(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Name of a key position as used in the configuration.
+    fn coord_name(&self, (row, column): KCoord) -> String {
+        match row {
+            NORMAL_KEY_ROW => OsCode::from_u16(column).map(key_name),
+            #[cfg(feature = "tcp_server")]
+            FAKE_KEY_ROW => self
+                .virtual_keys
+                .iter()
+                .find(|(_, idx)| **idx == usize::from(column))
+                .map(|(name, _)| name.clone()),
+            _ => None,
+        }
+        .unwrap_or_else(|| format!("{row},{column}"))
+    }
+}
+
+fn event_parts(event: Event) -> (&'static str, KCoord) {
+    match event {
+        Event::Press(row, column) => ("↓", (row, column)),
+        Event::Release(row, column) => ("↑", (row, column)),
+    }
+}
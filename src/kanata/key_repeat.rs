@@ -47,11 +47,7 @@ impl Kanata {
                         || self.unshifted_keys.contains(&kc)
                         || self.unmodded_keys.contains(&kc)
                     {
-                        log::debug!("repeat    {:?}", KeyCode::from(osc));
-                        if let Err(e) = write_key(&mut self.kbd_out, osc, KeyValue::Repeat) {
-                            bail!("could not write key {e:?}")
-                        }
-                        return Ok(());
+                        return self.write_repeat(osc);
                     }
                 }
             }
@@ -76,11 +72,7 @@ impl Kanata {
                     || self.unshifted_keys.contains(&kc)
                     || self.unmodded_keys.contains(&kc)
                 {
-                    log::debug!("repeat    {:?}", KeyCode::from(osc));
-                    if let Err(e) = write_key(&mut self.kbd_out, osc, KeyValue::Repeat) {
-                        bail!("could not write key {e:?}")
-                    }
-                    return Ok(());
+                    return self.write_repeat(osc);
                 }
             }
         }
@@ -94,9 +86,23 @@ impl Kanata {
             || self.unshifted_keys.contains(&kc)
             || self.unmodded_keys.contains(&kc)
         {
-            if let Err(e) = write_key(&mut self.kbd_out, event.code, KeyValue::Repeat) {
-                bail!("could not write key {e:?}");
-            }
+            return self.write_repeat(event.code);
+        }
+        Ok(())
+    }
+
+    /// Write the repeat event for the key, or for its output when auto-shift has replaced it.
+    fn write_repeat(&mut self, osc: OsCode) -> Result<()> {
+        let osc = match &self.autoshift {
+            Some(autoshift) => match autoshift.repeat_output(osc.into()) {
+                Some(kc) => kc.into(),
+                None => return Ok(()),
+            },
+            None => osc,
+        };
+        log::debug!("repeat    {:?}", KeyCode::from(osc));
+        if let Err(e) = write_key(&mut self.kbd_out, osc, KeyValue::Repeat) {
+            bail!("could not write key {e:?}")
         }
         Ok(())
     }
@@ -0,0 +1,79 @@
+//! Keyboard LEDs that show kanata's state. The state of each LED is chosen in order of priority
+//! from: the `set-leds` action, the indicators of active modes configured in `defcfg`, and the
+//! `led-*` options of the current layer. LEDs that none of these set are left alone.
+
+use kanata_parser::cfg::CfgLedIndicators;
+use kanata_parser::custom_action::{Led, LedOverrides, LedSetting};
+
+use super::*;
+
+#[derive(Debug, Default)]
+pub struct LedState {
+    indicators: CfgLedIndicators,
+    /// LED states set by the `set-leds` action.
+    action_overrides: LedOverrides,
+    /// LED states last written to the keyboards.
+    applied: LedOverrides,
+}
+
+impl LedState {
+    pub(crate) fn new(indicators: CfgLedIndicators) -> Self {
+        Self {
+            indicators,
+            ..Default::default()
+        }
+    }
+
+    /// Keeps the LEDs set by actions, which are not part of the configuration.
+    pub(crate) fn update_cfg(&mut self, indicators: CfgLedIndicators) {
+        self.indicators = indicators;
+    }
+
+    pub(crate) fn apply_settings(&mut self, settings: &[(Led, LedSetting)]) {
+        for (led, setting) in settings.iter().copied() {
+            let i = led.index();
+            self.action_overrides[i] = match setting {
+                LedSetting::On => Some(true),
+                LedSetting::Off => Some(false),
+                // The state of an LED that kanata does not set is unknown, so treat it as off.
+                LedSetting::Toggle => Some(!self.applied[i].unwrap_or(false)),
+                LedSetting::Auto => None,
+            };
+        }
+    }
+}
+
+impl Kanata {
+    pub(crate) fn tick_leds(&mut self) -> Result<()> {
+        let layer = self.layout.bm().current_layer();
+        let mut leds = self.layer_info[layer].leds;
+        let indicators = self.leds.indicators;
+        let active_modes = [
+            (indicators.caps_word, self.caps_word.is_some()),
+            (
+                indicators.sequence,
+                self.sequence_state.get_active().is_some(),
+            ),
+            (
+                indicators.dynamic_macro_record,
+                self.dynamic_macro_record_state.is_some(),
+            ),
+        ];
+        for (led, active) in active_modes {
+            if let (Some(led), true) = (led, active) {
+                leds[led.index()] = Some(true);
+            }
+        }
+        for (led, action_override) in leds.iter_mut().zip(self.leds.action_overrides) {
+            if action_override.is_some() {
+                *led = action_override;
+            }
+        }
+        if leds != self.leds.applied {
+            log::debug!("setting LEDs {leds:?}");
+            self.leds.applied = leds;
+            self.kbd_out.set_leds(leds)?;
+        }
+        Ok(())
+    }
+}
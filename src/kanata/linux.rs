@@ -4,7 +4,8 @@
 )]
 
 use anyhow::{anyhow, bail, Result};
-use evdev::{InputEvent, InputEventKind, RelativeAxisType};
+use evdev::{EventType, InputEvent, InputEventKind, RelativeAxisType};
+use kanata_keyberon::action::Action;
 use log::info;
 use parking_lot::Mutex;
 use std::convert::TryFrom;
@@ -19,14 +20,16 @@ impl Kanata {
     pub fn event_loop(kanata: Arc<Mutex<Self>>, tx: Sender<KeyEvent>) -> Result<()> {
         info!("entering the event loop");
 
-        let k = kanata.lock();
+        let mut k = kanata.lock();
         let allow_hardware_repeat = k.allow_hardware_repeat;
         let mut kbd_in = match KbdIn::new(
             &k.kbd_in_paths,
             k.continue_if_no_devices,
             k.include_names.clone(),
             k.exclude_names.clone(),
+            k.linux_dev_rules.clone(),
             k.device_detect_mode,
+            k.input_devices.clone(),
         ) {
             Ok(kbd_in) => kbd_in,
             Err(e) => {
@@ -37,15 +40,17 @@ impl Kanata {
         // In some environments, this needs to be done after the input device grab otherwise it
         // does not work on kanata startup.
         Kanata::set_repeat_rate(k.x11_repeat_rate)?;
+        k.input_devices_tx = Some(kbd_in.input_devices_sender());
         drop(k);
 
+        let mut mouse_movement = MouseMovement::default();
         loop {
             let events = kbd_in.read().map_err(|e| anyhow!("failed read: {}", e))?;
             log::trace!("event count: {}\nevents:\n{events:?}", events.len());
 
-            for in_event in events.iter().copied() {
+            for (in_event, device) in events.iter().copied() {
                 let key_event = match KeyEvent::try_from(in_event) {
-                    Ok(ev) => ev,
+                    Ok(ev) => ev.with_device(device),
                     _ => {
                         // Pass-through non-key and non-scroll events
                         let mut kanata = kanata.lock();
@@ -65,7 +70,23 @@ impl Kanata {
                 }
 
                 if key_event.value == KeyValue::Tap {
-                    // Scroll event for sure. Only scroll events produce Tap.
+                    // Scroll or mouse movement event for sure. Only those produce Tap.
+                    if matches!(
+                        key_event.code,
+                        OsCode::MouseMoveUp
+                            | OsCode::MouseMoveDown
+                            | OsCode::MouseMoveLeft
+                            | OsCode::MouseMoveRight
+                    ) {
+                        handle_mouse_movement(
+                            &kanata,
+                            &mut mouse_movement,
+                            key_event,
+                            in_event,
+                            &tx,
+                        )?;
+                        continue;
+                    }
                     if !handle_scroll(&kanata, in_event, key_event.code, &events)? {
                         continue;
                     }
@@ -134,7 +155,7 @@ fn handle_scroll(
     kanata: &Mutex<Kanata>,
     in_event: InputEvent,
     code: OsCode,
-    all_events: &[InputEvent],
+    all_events: &[(InputEvent, u8)],
 ) -> Result<bool> {
     let direction: MWheelDirection = code.try_into().unwrap();
     let scroll_distance = in_event.value().unsigned_abs() as u16;
@@ -156,7 +177,7 @@ fn handle_scroll(
                     // scroll event. In this scenario, the hi-res event should be used to call
                     // scroll, and not the normal event. Otherwise, too much scrolling will happen.
                     let mut kanata = kanata.lock();
-                    if !all_events.iter().any(|ev| {
+                    if !all_events.iter().any(|(ev, _)| {
                         matches!(
                             ev.kind(),
                             InputEventKind::RelAxis(
@@ -192,3 +213,139 @@ fn handle_scroll(
         _ => unreachable!("expect to be handling a wheel event"),
     }
 }
+
+/// Mouse movement that was too small to act on yet, indexed by axis: 0 for X and 1 for Y.
+#[derive(Default)]
+pub(crate) struct MouseMovement {
+    /// Movement towards the next tap of a mouse movement key.
+    key_distance: [i32; 2],
+    /// Movement lost to rounding when scaling with the `mouse-speed` layer option, in percent.
+    speed_remainder: [i32; 2],
+    /// Movement towards the next hi-res scroll unit while a `drag-scroll` action is held.
+    scroll_remainder: [i32; 2],
+}
+
+impl MouseMovement {
+    /// Returns the hi-res scroll distance for the movement while `drag-scroll` is held, where
+    /// `notch_distance` is the movement for one notch.
+    pub(crate) fn drag_scroll(&mut self, axis: usize, distance: i32, notch_distance: u16) -> i32 {
+        let hi_res_distance =
+            self.scroll_remainder[axis] + distance * i32::from(HI_RES_SCROLL_UNITS_IN_LO_RES);
+        let notch_distance = i32::from(notch_distance);
+        self.scroll_remainder[axis] = hi_res_distance % notch_distance;
+        hi_res_distance / notch_distance
+    }
+
+    /// Returns how many times to tap a mouse movement key for the movement.
+    pub(crate) fn key_taps(&mut self, axis: usize, distance: i32, threshold: u16) -> i32 {
+        let threshold = i32::from(threshold);
+        let key_distance = &mut self.key_distance[axis];
+        if key_distance.signum() != distance.signum() {
+            // Movement in the opposite direction starts over.
+            *key_distance = 0;
+        }
+        *key_distance += distance;
+        let taps = key_distance.abs() / threshold;
+        *key_distance %= threshold;
+        taps
+    }
+
+    /// Returns the movement scaled by the `mouse-speed` layer option, in percent.
+    pub(crate) fn scale_speed(&mut self, axis: usize, distance: i32, speed: Option<u16>) -> i32 {
+        match speed {
+            Some(speed) => {
+                let scaled = distance * i32::from(speed) + self.speed_remainder[axis];
+                self.speed_remainder[axis] = scaled % 100;
+                scaled / 100
+            }
+            None => {
+                self.speed_remainder[axis] = 0;
+                distance
+            }
+        }
+    }
+}
+
+impl Kanata {
+    /// Returns whether the mouse movement key of the event outputs itself, e.g. with `_`. Its
+    /// movement then moves the pointer as usual instead of tapping the key.
+    pub(crate) fn mouse_movement_key_outputs_itself(&self, key_event: &KeyEvent) -> bool {
+        let layout = self.layout.b();
+        let idx = usize::from(key_event.code);
+        let action = layout
+            .input_layer_order(key_event.device)
+            .iter()
+            .map(|layer| &layout.layers[usize::from(*layer)][0][idx])
+            .find(|action| !matches!(action, Action::Trans))
+            .filter(|action| !matches!(action, Action::Src))
+            .unwrap_or(&layout.src_keys[idx]);
+        matches!(action, Action::KeyCode(kc) if *kc == KeyCode::from(key_event.code))
+    }
+}
+
+/// Handles a mouse movement event from an input device. In order of priority, the movement
+/// scrolls while `drag-scroll` is held, taps a mouse movement key if that key is in defsrc and
+/// does not output itself, or otherwise moves the pointer, scaled by the `mouse-speed` of the
+/// current layer.
+fn handle_mouse_movement(
+    kanata: &Mutex<Kanata>,
+    movement: &mut MouseMovement,
+    key_event: KeyEvent,
+    in_event: InputEvent,
+    tx: &Sender<KeyEvent>,
+) -> Result<()> {
+    let axis = match in_event.kind() {
+        InputEventKind::RelAxis(RelativeAxisType::REL_X) => 0,
+        InputEventKind::RelAxis(RelativeAxisType::REL_Y) => 1,
+        _ => unreachable!("expect to be handling a mouse movement event"),
+    };
+    let distance = in_event.value();
+    let mut k = kanata.lock();
+
+    if let Some(notch_distance) = k.drag_scroll {
+        let scroll_distance = movement.drag_scroll(axis, distance, notch_distance);
+        if scroll_distance != 0 {
+            let direction = match (axis, scroll_distance > 0) {
+                (0, true) => MWheelDirection::Right,
+                (0, false) => MWheelDirection::Left,
+                (_, true) => MWheelDirection::Down,
+                (_, false) => MWheelDirection::Up,
+            };
+            let scroll_distance = u16::try_from(scroll_distance.unsigned_abs()).unwrap_or(u16::MAX);
+            k.kbd_out
+                .scroll(direction, scroll_distance)
+                .map_err(|e| anyhow!("failed write: {}", e))?;
+        }
+        return Ok(());
+    }
+    movement.scroll_remainder[axis] = 0;
+
+    if MOUSE_MOVEMENT_KEYS_MAPPED.load(Ordering::SeqCst)
+        && MAPPED_KEYS.lock().contains(&key_event.code)
+        && !k.mouse_movement_key_outputs_itself(&key_event)
+    {
+        drop(k);
+        let threshold = MOUSE_MOVEMENT_KEY_THRESHOLD.load(Ordering::SeqCst);
+        for _ in 0..movement.key_taps(axis, distance, threshold) {
+            if let Err(e) = tx.try_send(key_event) {
+                bail!("failed to send on channel: {}", e)
+            }
+        }
+        return Ok(());
+    }
+    movement.key_distance[axis] = 0;
+
+    let layer = k.layout.bm().current_layer();
+    let distance = movement.scale_speed(axis, distance, k.layer_info[layer].mouse_speed);
+    if distance != 0 {
+        #[cfg(not(feature = "simulated_output"))]
+        k.kbd_out
+            .write_raw(InputEvent::new(
+                EventType::RELATIVE,
+                in_event.code(),
+                distance,
+            ))
+            .map_err(|e| anyhow!("failed write: {}", e))?;
+    }
+    Ok(())
+}
@@ -15,7 +15,9 @@ use kanata_keyberon::action::ReleasableState;
 use kanata_keyberon::key_code::*;
 use kanata_keyberon::layout::{CustomEvent, Event, Layout, State};
 
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU16, Ordering};
 use std::sync::Arc;
 use std::time;
 
@@ -30,16 +32,32 @@ use kanata_parser::cfg::list_actions::*;
 use kanata_parser::cfg::*;
 use kanata_parser::custom_action::*;
 pub use kanata_parser::keys::*;
-use kanata_tcp_protocol::ServerMessage;
+use kanata_tcp_protocol::{KeyEventAction, MouseActionMessage, ServerMessage};
 
 mod dynamic_macro;
+pub(crate) use dynamic_macro::flush_macro_file;
 use dynamic_macro::*;
 
+mod event_stream;
+pub(crate) use event_stream::*;
+
+mod hold_tap_trace;
+
 mod key_repeat;
 
+#[cfg(feature = "tcp_server")]
+mod runtime_state;
+
 mod sequences;
 use sequences::*;
 
+mod statistics;
+use statistics::*;
+pub(crate) use statistics::{flush_statistics_on_exit, save_statistics_on_exit};
+
+mod timers;
+use timers::*;
+
 pub mod cfg_forced;
 use cfg_forced::*;
 
@@ -55,6 +73,13 @@ pub use windows::*;
 
 #[cfg(target_os = "linux")]
 mod linux;
+#[cfg(all(
+    target_os = "linux",
+    test,
+    feature = "simulated_output",
+    not(feature = "simulated_input")
+))]
+pub(crate) use linux::MouseMovement;
 
 #[cfg(target_os = "macos")]
 mod macos;
@@ -63,6 +88,12 @@ use macos::*;
 
 mod output_logic;
 use output_logic::*;
+#[cfg(all(
+    target_os = "linux",
+    not(feature = "simulated_output"),
+    not(feature = "passthru_ahk")
+))]
+pub(crate) use output_logic::{output_device_class, OutputDeviceClass};
 
 #[cfg(target_os = "unknown")]
 mod unknown;
@@ -72,9 +103,19 @@ use unknown::*;
 mod caps_word;
 pub use caps_word::*;
 
+mod autoshift;
+pub use autoshift::*;
+
+mod leds;
+pub use leds::*;
+
 type HashSet<T> = rustc_hash::FxHashSet<T>;
 type HashMap<K, V> = rustc_hash::FxHashMap<K, V>;
 
+/// Sends the result of a live reload to the TCP client that requested it.
+#[cfg(feature = "tcp_server")]
+pub type LiveReloadReply = std::sync::mpsc::Sender<std::result::Result<(), miette::Error>>;
+
 pub struct Kanata {
     /// Handle to some OS keyboard output mechanism.
     pub kbd_out: KbdOut,
@@ -112,6 +153,9 @@ pub struct Kanata {
     pub move_mouse_state_horizontal: Option<MoveMouseState>,
     /// A list of mouse speed modifiers in percentages by which mouse travel distance is scaled.
     pub move_mouse_speed_modifiers: Vec<u16>,
+    /// Is Some(distance per notch) while a `drag-scroll` action is held, turning the mouse
+    /// movement of input devices into scrolling.
+    pub drag_scroll: Option<u16>,
     /// The user configuration for backtracking to find valid sequences. See
     /// <../../docs/sequence-adding-chords-ideas.md> for more info.
     pub sequence_backtrack_modcancel: bool,
@@ -146,6 +190,17 @@ pub struct Kanata {
     time_remainder: u128,
     /// Is true if a live reload was requested by the user and false otherwise.
     live_reload_requested: bool,
+    /// Configuration text from a TCP client to load on the requested live reload instead of the
+    /// configuration file.
+    #[cfg(feature = "tcp_server")]
+    live_reload_text: Option<String>,
+    /// Receives the result of the requested live reload, if a TCP client requested it.
+    #[cfg(feature = "tcp_server")]
+    live_reload_reply: Option<LiveReloadReply>,
+    /// Index of the configuration file that a TCP client requested to load. It only becomes
+    /// `cur_cfg_idx` once the configuration is loaded.
+    #[cfg(feature = "tcp_server")]
+    live_reload_cfg_idx: Option<usize>,
     #[cfg(target_os = "linux")]
     /// Linux input paths in the user configuration.
     pub kbd_in_paths: Vec<String>,
@@ -160,6 +215,9 @@ pub struct Kanata {
     /// Tracks the Linux/Macos user configuration for device names (instead of paths) that should be
     /// excluded for interception and processing by kanata.
     pub exclude_names: Option<Vec<String>>,
+    #[cfg(target_os = "linux")]
+    /// Rules from `linux-dev-rules` that include or exclude autodetected devices.
+    pub linux_dev_rules: Vec<LinuxDevRule>,
     #[cfg(target_os = "windows")]
     /// Tracks whether Kanata should try to synchronize keystates with the Windows OS.
     /// Has no effect on Interception. Fixes some use cases related to admin window permissions and
@@ -183,8 +241,14 @@ pub struct Kanata {
     intercept_kb_hwids_exclude: Option<Vec<[u8; HWID_ARR_SZ]>>,
     /// User configuration to do logging of layer changes or not.
     log_layer_changes: bool,
+    /// User configuration to log how each tap-hold was decided.
+    log_hold_tap_decisions: bool,
     /// Tracks the caps-word state. Is Some(...) if caps-word is active and None otherwise.
     pub caps_word: Option<CapsWordState>,
+    /// Tracks the auto-shift state. Is Some(...) if auto-shift is enabled in defcfg.
+    pub autoshift: Option<AutoShiftState>,
+    /// Tracks the keyboard LEDs that kanata sets.
+    pub leds: LedState,
     /// Config items from `defcfg`.
     #[cfg(target_os = "linux")]
     pub x11_repeat_rate: Option<KeyRepeatSettings>,
@@ -197,6 +261,15 @@ pub struct Kanata {
     /// The key is the coordinate and the value is the number of ticks until release should be
     /// done.
     pub vkeys_pending_release: HashMap<Coord, u16>,
+    /// Timers started by the `after` and `every` actions.
+    timers: Timers,
+    /// Milliseconds without input while the current layer has a `timeout` option.
+    layer_idle_ticks: u32,
+    /// Default layers that were switched away from, most recent last. A default layer with a
+    /// `timeout` option falls back to the most recent one.
+    default_layer_history: Vec<usize>,
+    /// Default layer as of the previous tick, for noticing switches.
+    history_default_layer: usize,
     /// Number of ticks since kanata was idle.
     pub ticks_since_idle: u16,
     /// If a mousemove action is active and another mousemove action is activated,
@@ -215,6 +288,10 @@ pub struct Kanata {
     dynamic_macro_max_presses: u16,
     /// Determines behaviour of replayed dynamic macros.
     dynamic_macro_replay_behaviour: ReplayBehaviour,
+    /// File that dynamic macros are saved to and loaded from, if configured.
+    dynamic_macro_file: Option<PathBuf>,
+    /// Collects usage statistics. Is Some(...) when statistics are enabled and None otherwise.
+    pub(crate) statistics: Option<StatisticsCollector>,
     /// Keys that should be unmodded. If non-empty, any modifier should be cleared.
     unmodded_keys: Vec<KeyCode>,
     /// Modifiers to be cleared in case the above is non-empty.
@@ -228,6 +305,21 @@ pub struct Kanata {
     pub virtual_keys: HashMap<String, usize>,
     /// The maximum value of switch's key-timing item in the configuration.
     pub switch_max_key_timing: u16,
+    /// Input devices defined in `defdevice`. Used by the input event loop to identify which
+    /// device events come from.
+    pub input_devices: Arc<Vec<InputDeviceCfg>>,
+    /// Sends `input_devices` to the input event loop when a live reload changes them.
+    #[cfg(target_os = "linux")]
+    input_devices_tx: Option<std::sync::mpsc::Sender<Arc<Vec<InputDeviceCfg>>>>,
+    /// Variables defined in `defstate`. The values are stored in the layout.
+    pub state_vars: Vec<StateVarCfg>,
+    /// Context conditions used by `switch` and `defcontextlayers`.
+    contexts: ContextConditions,
+    /// Context values most recently provided by a client, e.g. the focused application.
+    context_values: HashMap<String, String>,
+    /// Base layer to go back to once no `defcontextlayers` rule applies anymore, and the layer
+    /// that the rule switched to. The base layer is only restored if it was not changed since.
+    context_layer_restore: Option<(usize, usize)>,
     #[cfg(feature = "tcp_server")]
     tcp_server_address: Option<SocketAddrWrapper>,
     #[cfg(all(target_os = "windows", feature = "gui"))]
@@ -237,6 +329,8 @@ pub struct Kanata {
     /// When > 0, it means macros should be cancelled on the next press.
     /// Upon cancelling this should be set to 0.
     pub macro_on_press_cancel_duration: u32,
+    /// Sends events to TCP clients that subscribed to them.
+    event_stream: EventStream,
 }
 
 #[derive(PartialEq, Clone, Copy)]
@@ -289,9 +383,31 @@ use once_cell::sync::Lazy;
 static MAPPED_KEYS: Lazy<Mutex<cfg::MappedKeys>> =
     Lazy::new(|| Mutex::new(cfg::MappedKeys::default()));
 
+/// Mouse movement distance of a mouse movement key, e.g. `mmu`.
+static MOUSE_MOVEMENT_KEY_THRESHOLD: AtomicU16 = AtomicU16::new(16);
+
+/// Whether any mouse movement key is in `MAPPED_KEYS`, so that pointer movement events can skip
+/// the mouse movement key handling without taking any lock.
+static MOUSE_MOVEMENT_KEYS_MAPPED: AtomicBool = AtomicBool::new(false);
+
+fn set_mapped_keys(mapped_keys: cfg::MappedKeys) {
+    MOUSE_MOVEMENT_KEYS_MAPPED.store(
+        [
+            OsCode::MouseMoveUp,
+            OsCode::MouseMoveDown,
+            OsCode::MouseMoveLeft,
+            OsCode::MouseMoveRight,
+        ]
+        .iter()
+        .any(|osc| mapped_keys.contains(osc)),
+        Ordering::SeqCst,
+    );
+    *MAPPED_KEYS.lock() = mapped_keys;
+}
+
 impl Kanata {
     pub fn new(args: &ValidatedArgs) -> Result<Self> {
-        let cfg = match cfg::new_from_file(&args.paths[0]) {
+        let mut cfg = match cfg::new_from_file(&args.paths[0]) {
             Ok(c) => c,
             Err(e) => {
                 log::error!("{e:?}");
@@ -309,6 +425,10 @@ impl Kanata {
                 LinuxCfgOutputBusType::BusUsb => evdev::BusType::BUS_USB,
                 LinuxCfgOutputBusType::BusI8042 => evdev::BusType::BUS_I8042,
             },
+            #[cfg(target_os = "linux")]
+            cfg.options.linux_opts.linux_setmouse_screen_size,
+            #[cfg(target_os = "linux")]
+            cfg.options.linux_opts.linux_output_devices.as_ref(),
         ) {
             Ok(kbd_out) => kbd_out,
             Err(err) => {
@@ -339,12 +459,19 @@ impl Kanata {
         #[cfg(target_os = "windows")]
         set_win_altgr_behaviour(cfg.options.windows_opts.windows_altgr);
 
-        *MAPPED_KEYS.lock() = cfg.mapped_keys;
+        set_mapped_keys(cfg.mapped_keys);
+        MOUSE_MOVEMENT_KEY_THRESHOLD
+            .store(cfg.options.mouse_movement_key_threshold, Ordering::SeqCst);
         #[cfg(feature = "zippychord")]
         {
             zch().zch_configure(cfg.zippy.unwrap_or_default());
         }
 
+        let dynamic_macro_file =
+            cfg_relative_path(&args.paths[0], cfg.options.dynamic_macro_file.as_deref());
+        let statistics = StatisticsCollector::from_cfg(&cfg.options, &args.paths[0]);
+        tap_startup_on_enter(&cfg.layer_info, &mut cfg.layout);
+
         Ok(Self {
             kbd_out,
             cfg_paths: args.paths.clone(),
@@ -360,6 +487,7 @@ impl Kanata {
             move_mouse_state_vertical: None,
             move_mouse_state_horizontal: None,
             move_mouse_speed_modifiers: Vec::new(),
+            drag_scroll: None,
             sequence_backtrack_modcancel: cfg.options.sequence_backtrack_modcancel,
             sequence_always_on: cfg.options.sequence_always_on,
             sequence_input_mode: cfg.options.sequence_input_mode,
@@ -369,6 +497,12 @@ impl Kanata {
             last_tick: instant::Instant::now(),
             time_remainder: 0,
             live_reload_requested: false,
+            #[cfg(feature = "tcp_server")]
+            live_reload_text: None,
+            #[cfg(feature = "tcp_server")]
+            live_reload_reply: None,
+            #[cfg(feature = "tcp_server")]
+            live_reload_cfg_idx: None,
             overrides: cfg.overrides,
             override_states: OverrideStates::new(),
             #[cfg(target_os = "macos")]
@@ -383,6 +517,8 @@ impl Kanata {
             include_names: cfg.options.linux_opts.linux_dev_names_include,
             #[cfg(target_os = "linux")]
             exclude_names: cfg.options.linux_opts.linux_dev_names_exclude,
+            #[cfg(target_os = "linux")]
+            linux_dev_rules: cfg.options.linux_opts.linux_dev_rules,
             #[cfg(target_os = "windows")]
             windows_sync_keystates: cfg.options.windows_opts.sync_keystates,
             #[cfg(all(feature = "interception_driver", target_os = "windows"))]
@@ -404,10 +540,13 @@ impl Kanata {
                 .windows_interception_keyboard_hwids_exclude,
             dynamic_macro_replay_state: None,
             dynamic_macro_record_state: None,
-            dynamic_macros: Default::default(),
+            dynamic_macros: load_macro_file(dynamic_macro_file.as_deref()),
             log_layer_changes: get_forced_log_layer_changes()
                 .unwrap_or(cfg.options.log_layer_changes),
+            log_hold_tap_decisions: cfg.options.log_hold_tap_decisions,
             caps_word: None,
+            autoshift: AutoShiftState::new(&cfg.options.autoshift_opts),
+            leds: LedState::new(cfg.options.led_indicators),
             movemouse_smooth_diagonals: cfg.options.movemouse_smooth_diagonals,
             override_release_on_activation: cfg.options.override_release_on_activation,
             movemouse_inherit_accel_state: cfg.options.movemouse_inherit_accel_state,
@@ -415,6 +554,8 @@ impl Kanata {
             dynamic_macro_replay_behaviour: ReplayBehaviour {
                 delay: cfg.options.dynamic_macro_replay_delay_behaviour,
             },
+            dynamic_macro_file,
+            statistics,
             #[cfg(target_os = "linux")]
             x11_repeat_rate: cfg.options.linux_opts.linux_x11_repeat_delay_rate,
             #[cfg(target_os = "linux")]
@@ -425,6 +566,10 @@ impl Kanata {
                 .expect("parser should default to some"),
             waiting_for_idle: HashSet::default(),
             vkeys_pending_release: HashMap::default(),
+            timers: Timers::default(),
+            layer_idle_ticks: 0,
+            default_layer_history: vec![],
+            history_default_layer: 0,
             ticks_since_idle: 0,
             movemouse_buffer: None,
             unmodded_keys: vec![],
@@ -434,22 +579,32 @@ impl Kanata {
             #[cfg(feature = "tcp_server")]
             virtual_keys: cfg.fake_keys,
             switch_max_key_timing: cfg.switch_max_key_timing,
+            input_devices: Arc::new(cfg.input_devices),
+            #[cfg(target_os = "linux")]
+            input_devices_tx: None,
+            state_vars: cfg.state_vars,
+            contexts: cfg.contexts,
+            context_values: HashMap::default(),
+            context_layer_restore: None,
             #[cfg(feature = "tcp_server")]
             tcp_server_address: args.tcp_server_address.clone(),
             #[cfg(all(target_os = "windows", feature = "gui"))]
             gui_opts: cfg.options.gui_opts,
             allow_hardware_repeat: cfg.options.allow_hardware_repeat,
             macro_on_press_cancel_duration: 0,
+            event_stream: EventStream::default(),
         })
     }
 
     /// Create a new configuration from a file, wrapped in an Arc<Mutex<_>>
     pub fn new_arc(args: &ValidatedArgs) -> Result<Arc<Mutex<Self>>> {
-        Ok(Arc::new(Mutex::new(Self::new(args)?)))
+        let kanata = Arc::new(Mutex::new(Self::new(args)?));
+        save_statistics_on_exit(&kanata);
+        Ok(kanata)
     }
 
     pub fn new_from_str(cfg: &str, file_content: HashMap<String, String>) -> Result<Self> {
-        let cfg = match cfg::new_from_str(cfg, file_content) {
+        let mut cfg = match cfg::new_from_str(cfg, file_content) {
             Ok(c) => c,
             Err(e) => {
                 bail!("{e:?}");
@@ -466,6 +621,10 @@ impl Kanata {
                 LinuxCfgOutputBusType::BusUsb => evdev::BusType::BUS_USB,
                 LinuxCfgOutputBusType::BusI8042 => evdev::BusType::BUS_I8042,
             },
+            #[cfg(target_os = "linux")]
+            cfg.options.linux_opts.linux_setmouse_screen_size,
+            #[cfg(target_os = "linux")]
+            cfg.options.linux_opts.linux_output_devices.as_ref(),
         ) {
             Ok(kbd_out) => kbd_out,
             Err(err) => {
@@ -474,12 +633,21 @@ impl Kanata {
             }
         };
 
-        *MAPPED_KEYS.lock() = cfg.mapped_keys;
+        set_mapped_keys(cfg.mapped_keys);
+        MOUSE_MOVEMENT_KEY_THRESHOLD
+            .store(cfg.options.mouse_movement_key_threshold, Ordering::SeqCst);
         #[cfg(feature = "zippychord")]
         {
             zch().zch_configure(cfg.zippy.unwrap_or_default());
         }
 
+        let dynamic_macro_file = cfg_relative_path(
+            Path::new("config string"),
+            cfg.options.dynamic_macro_file.as_deref(),
+        );
+        let statistics = StatisticsCollector::from_cfg(&cfg.options, Path::new("config string"));
+        tap_startup_on_enter(&cfg.layer_info, &mut cfg.layout);
+
         Ok(Self {
             kbd_out,
             cfg_paths: vec!["config string".into()],
@@ -495,6 +663,7 @@ impl Kanata {
             move_mouse_state_vertical: None,
             move_mouse_state_horizontal: None,
             move_mouse_speed_modifiers: Vec::new(),
+            drag_scroll: None,
             sequence_backtrack_modcancel: cfg.options.sequence_backtrack_modcancel,
             sequence_always_on: cfg.options.sequence_always_on,
             sequence_input_mode: cfg.options.sequence_input_mode,
@@ -504,6 +673,12 @@ impl Kanata {
             last_tick: instant::Instant::now(),
             time_remainder: 0,
             live_reload_requested: false,
+            #[cfg(feature = "tcp_server")]
+            live_reload_text: None,
+            #[cfg(feature = "tcp_server")]
+            live_reload_reply: None,
+            #[cfg(feature = "tcp_server")]
+            live_reload_cfg_idx: None,
             overrides: cfg.overrides,
             override_states: OverrideStates::new(),
             #[cfg(target_os = "macos")]
@@ -518,6 +693,8 @@ impl Kanata {
             include_names: cfg.options.linux_opts.linux_dev_names_include,
             #[cfg(target_os = "linux")]
             exclude_names: cfg.options.linux_opts.linux_dev_names_exclude,
+            #[cfg(target_os = "linux")]
+            linux_dev_rules: cfg.options.linux_opts.linux_dev_rules,
             #[cfg(target_os = "windows")]
             windows_sync_keystates: cfg.options.windows_opts.sync_keystates,
             #[cfg(all(feature = "interception_driver", target_os = "windows"))]
@@ -539,10 +716,13 @@ impl Kanata {
                 .windows_interception_keyboard_hwids_exclude,
             dynamic_macro_replay_state: None,
             dynamic_macro_record_state: None,
-            dynamic_macros: Default::default(),
+            dynamic_macros: load_macro_file(dynamic_macro_file.as_deref()),
             log_layer_changes: get_forced_log_layer_changes()
                 .unwrap_or(cfg.options.log_layer_changes),
+            log_hold_tap_decisions: cfg.options.log_hold_tap_decisions,
             caps_word: None,
+            autoshift: AutoShiftState::new(&cfg.options.autoshift_opts),
+            leds: LedState::new(cfg.options.led_indicators),
             movemouse_smooth_diagonals: cfg.options.movemouse_smooth_diagonals,
             override_release_on_activation: cfg.options.override_release_on_activation,
             movemouse_inherit_accel_state: cfg.options.movemouse_inherit_accel_state,
@@ -550,6 +730,8 @@ impl Kanata {
             dynamic_macro_replay_behaviour: ReplayBehaviour {
                 delay: cfg.options.dynamic_macro_replay_delay_behaviour,
             },
+            dynamic_macro_file,
+            statistics,
             #[cfg(target_os = "linux")]
             x11_repeat_rate: cfg.options.linux_opts.linux_x11_repeat_delay_rate,
             #[cfg(target_os = "linux")]
@@ -560,6 +742,10 @@ impl Kanata {
                 .expect("parser should default to some"),
             waiting_for_idle: HashSet::default(),
             vkeys_pending_release: HashMap::default(),
+            timers: Timers::default(),
+            layer_idle_ticks: 0,
+            default_layer_history: vec![],
+            history_default_layer: 0,
             ticks_since_idle: 0,
             movemouse_buffer: None,
             unmodded_keys: vec![],
@@ -569,12 +755,20 @@ impl Kanata {
             #[cfg(feature = "tcp_server")]
             virtual_keys: cfg.fake_keys,
             switch_max_key_timing: cfg.switch_max_key_timing,
+            input_devices: Arc::new(cfg.input_devices),
+            #[cfg(target_os = "linux")]
+            input_devices_tx: None,
+            state_vars: cfg.state_vars,
+            contexts: cfg.contexts,
+            context_values: HashMap::default(),
+            context_layer_restore: None,
             #[cfg(feature = "tcp_server")]
             tcp_server_address: None,
             #[cfg(all(target_os = "windows", feature = "gui"))]
             gui_opts: cfg.options.gui_opts,
             allow_hardware_repeat: cfg.options.allow_hardware_repeat,
             macro_on_press_cancel_duration: 0,
+            event_stream: EventStream::default(),
         })
     }
 
@@ -589,13 +783,39 @@ impl Kanata {
     }
 
     fn do_live_reload(&mut self, _tx: &Option<Sender<ServerMessage>>) -> Result<()> {
-        let cfg = match cfg::new_from_file(&self.cfg_paths[self.cur_cfg_idx]) {
+        #[cfg(feature = "tcp_server")]
+        let cfg_idx = self.live_reload_cfg_idx.take().unwrap_or(self.cur_cfg_idx);
+        #[cfg(not(feature = "tcp_server"))]
+        let cfg_idx = self.cur_cfg_idx;
+        #[cfg(feature = "tcp_server")]
+        let parsed = match self.live_reload_text.take() {
+            Some(text) => cfg::new_from_str(&text, HashMap::default()),
+            None => cfg::new_from_file(&self.cfg_paths[cfg_idx]),
+        };
+        #[cfg(not(feature = "tcp_server"))]
+        let parsed = cfg::new_from_file(&self.cfg_paths[cfg_idx]);
+        let cfg = match parsed {
             Ok(c) => c,
             Err(e) => {
                 log::error!("{e:?}");
+                #[cfg(feature = "tcp_server")]
+                if let Some(reply) = self.live_reload_reply.take() {
+                    let _ = reply.send(Err(e));
+                }
                 bail!("failed to parse config file");
             }
         };
+        // The new override states don't know about action overrides that are still active, so
+        // release their fake keys while the layout that pressed them is still in use.
+        let active_overrides: Vec<u16> = self.override_states.active_action_overrides().collect();
+        if !active_overrides.is_empty() {
+            let layout = self.layout.bm();
+            for idx in active_overrides {
+                layout.event(Event::Release(FAKE_KEY_ROW, idx));
+            }
+            self.handle_keystate_changes(_tx)?;
+        }
+        self.cur_cfg_idx = cfg_idx;
         update_kbd_out(&cfg.options, &self.kbd_out)?;
         #[cfg(target_os = "windows")]
         set_win_altgr_behaviour(cfg.options.windows_opts.windows_altgr);
@@ -608,16 +828,41 @@ impl Kanata {
         self.layer_info = cfg.layer_info;
         self.sequences = cfg.sequences;
         self.overrides = cfg.overrides;
+        self.override_states = OverrideStates::new();
         self.log_layer_changes =
             get_forced_log_layer_changes().unwrap_or(cfg.options.log_layer_changes);
+        self.log_hold_tap_decisions = cfg.options.log_hold_tap_decisions;
         self.movemouse_smooth_diagonals = cfg.options.movemouse_smooth_diagonals;
         self.override_release_on_activation = cfg.options.override_release_on_activation;
+        self.autoshift = AutoShiftState::new(&cfg.options.autoshift_opts);
+        self.leds.update_cfg(cfg.options.led_indicators);
         self.movemouse_inherit_accel_state = cfg.options.movemouse_inherit_accel_state;
         self.dynamic_macro_max_presses = cfg.options.dynamic_macro_max_presses;
         self.dynamic_macro_replay_behaviour = ReplayBehaviour {
             delay: cfg.options.dynamic_macro_replay_delay_behaviour,
         };
+        self.dynamic_macro_file = cfg_relative_path(
+            &self.cfg_paths[self.cur_cfg_idx],
+            cfg.options.dynamic_macro_file.as_deref(),
+        );
+        self.dynamic_macros
+            .extend(load_macro_file(self.dynamic_macro_file.as_deref()));
+        StatisticsCollector::update_from_cfg(
+            &mut self.statistics,
+            &cfg.options,
+            &self.cfg_paths[self.cur_cfg_idx],
+        );
         self.switch_max_key_timing = cfg.switch_max_key_timing;
+        self.input_devices = Arc::new(cfg.input_devices);
+        #[cfg(target_os = "linux")]
+        if let Some(tx) = &self.input_devices_tx {
+            let _ = tx.send(self.input_devices.clone());
+        }
+        self.state_vars = cfg.state_vars;
+        self.timers.clear();
+        self.contexts = cfg.contexts;
+        self.context_layer_restore = None;
+        self.apply_context();
         #[cfg(feature = "tcp_server")]
         {
             self.virtual_keys = cfg.fake_keys;
@@ -644,7 +889,9 @@ impl Kanata {
             zch().zch_configure(cfg.zippy.unwrap_or_default());
         }
 
-        *MAPPED_KEYS.lock() = cfg.mapped_keys;
+        set_mapped_keys(cfg.mapped_keys);
+        MOUSE_MOVEMENT_KEY_THRESHOLD
+            .store(cfg.options.mouse_movement_key_threshold, Ordering::SeqCst);
         #[cfg(target_os = "linux")]
         Kanata::set_repeat_rate(cfg.options.linux_opts.linux_x11_repeat_delay_rate)?;
         log::info!("Live reload successful");
@@ -668,6 +915,8 @@ impl Kanata {
 
         let cur_layer = self.layout.bm().current_layer();
         self.prev_layer = cur_layer;
+        self.default_layer_history.clear();
+        self.history_default_layer = self.layout.b().default_layer;
         self.print_layer(cur_layer);
         self.macro_on_press_cancel_duration = 0;
 
@@ -679,7 +928,8 @@ impl Kanata {
         #[cfg(feature = "tcp_server")]
         if let Some(tx) = _tx {
             let new = self.layer_info[cur_layer].name.clone();
-            match tx.try_send(ServerMessage::LayerChange { new }) {
+            let metadata = self.layer_info[cur_layer].metadata.clone();
+            match tx.try_send(ServerMessage::LayerChange { new, metadata }) {
                 Ok(_) => {}
                 Err(error) => {
                     log::error!("could not send LayerChange event notification: {}", error);
@@ -688,22 +938,34 @@ impl Kanata {
         }
         #[cfg(all(target_os = "windows", feature = "gui"))]
         send_gui_cfg_notice();
+        #[cfg(feature = "tcp_server")]
+        if let Some(reply) = self.live_reload_reply.take() {
+            let _ = reply.send(Ok(()));
+        }
         Ok(())
     }
 
     /// Update keyberon layout state for press/release, handle repeat separately
     pub fn handle_input_event(&mut self, event: &KeyEvent) -> Result<()> {
         log::debug!("process recv ev {event:?}");
+        self.event_stream.input(event);
         let evc: u16 = event.code.into();
         self.ticks_since_idle = 0;
+        self.layer_idle_ticks = 0;
         let kbrn_ev = match event.value {
             KeyValue::Press => {
+                self.record_press_statistics(event);
                 if let Some((macro_id, recorded_macro)) = record_press(
                     &mut self.dynamic_macro_record_state,
                     event.code,
                     self.dynamic_macro_max_presses,
                 ) {
-                    self.dynamic_macros.insert(macro_id, recorded_macro);
+                    store_macro(
+                        &mut self.dynamic_macros,
+                        self.dynamic_macro_file.as_deref(),
+                        macro_id,
+                        recorded_macro,
+                    );
                 }
                 if self.macro_on_press_cancel_duration > 0 {
                     log::debug!("cancelling all macros: other press");
@@ -725,21 +987,22 @@ impl Kanata {
                 return ret;
             }
             KeyValue::Tap => {
-                self.layout.bm().event(Event::Press(0, evc));
-                self.layout.bm().event(Event::Release(0, evc));
+                let layout = self.layout.bm();
+                layout.event_from_device(Event::Press(0, evc), event.device);
+                layout.event_from_device(Event::Release(0, evc), event.device);
                 return Ok(());
             }
             KeyValue::WakeUp => {
                 return Ok(());
             }
         };
-        self.layout.bm().event(kbrn_ev);
+        self.layout.bm().event_from_device(kbrn_ev, event.device);
         Ok(())
     }
 
     /// Advance keyberon layout state and send events based on changes to its state.
     /// Returns the number of ticks that elapsed.
-    fn handle_time_ticks(&mut self, tx: &Option<Sender<ServerMessage>>) -> Result<u16> {
+    pub(crate) fn handle_time_ticks(&mut self, tx: &Option<Sender<ServerMessage>>) -> Result<u16> {
         const NS_IN_MS: u128 = 1_000_000;
         let now = instant::Instant::now();
         let ns_elapsed = now.duration_since(self.last_tick).as_nanos();
@@ -762,8 +1025,6 @@ impl Kanata {
             _ => instant::Instant::now(),
         };
 
-        self.check_handle_layer_change(tx);
-
         if self.live_reload_requested
             && ((self.prev_keys.is_empty() && self.cur_keys.is_empty())
                 || self.ticks_since_idle > 1000)
@@ -780,6 +1041,10 @@ impl Kanata {
             self.live_reload_requested = false;
             if let Err(e) = self.do_live_reload(tx) {
                 log::error!("live reload failed {e}");
+                #[cfg(feature = "tcp_server")]
+                if let Some(reply) = self.live_reload_reply.take() {
+                    let _ = reply.send(Err(miette::miette!("live reload failed: {e}")));
+                }
             }
         }
 
@@ -836,6 +1101,60 @@ impl Kanata {
         });
     }
 
+    fn tick_timers(&mut self) {
+        if self.timers.is_empty() {
+            return;
+        }
+        let layout = self.layout.bm();
+        self.timers.tick(|Coord { x, y }| {
+            handle_fakekey_action(FakeKeyAction::Tap, layout, x, y);
+        });
+    }
+
+    /// Falls back from a layer with a `timeout` option once there has been no input for the
+    /// configured duration. A locked layer is unlocked, otherwise the default layer is switched
+    /// back to the default layer that was active before it.
+    fn tick_layer_timeout(&mut self) {
+        let layout = self.layout.bm();
+        let default_layer = layout.default_layer;
+        if default_layer != self.history_default_layer {
+            // Switching back to an earlier layer forgets the layers that were switched to since.
+            match self
+                .default_layer_history
+                .iter()
+                .position(|layer| *layer == default_layer)
+            {
+                Some(i) => self.default_layer_history.truncate(i),
+                None => self.default_layer_history.push(self.history_default_layer),
+            }
+            self.history_default_layer = default_layer;
+        }
+        let layer = layout.current_layer();
+        let Some(timeout) = self.layer_info[layer].timeout else {
+            self.layer_idle_ticks = 0;
+            return;
+        };
+        if !layout.states.is_empty() {
+            self.layer_idle_ticks = 0;
+            return;
+        }
+        self.layer_idle_ticks = self.layer_idle_ticks.saturating_add(1);
+        if self.layer_idle_ticks < timeout {
+            return;
+        }
+        self.layer_idle_ticks = 0;
+        log::debug!("layer {} timed out", self.layer_info[layer].name);
+        if layout.locked_layers.contains(&(layer as u16)) {
+            layout
+                .locked_layers
+                .retain(|locked| *locked != layer as u16);
+        } else {
+            let prev_layer = self.default_layer_history.pop().unwrap_or(0);
+            layout.set_default_layer(prev_layer);
+            self.history_default_layer = prev_layer;
+        }
+    }
+
     fn tick_states(&mut self, _tx: &Option<Sender<ServerMessage>>) -> Result<()> {
         self.live_reload_requested |= self.handle_keystate_changes(_tx)?;
         self.handle_scrolling()?;
@@ -848,6 +1167,12 @@ impl Kanata {
         self.prev_keys.clear();
         self.prev_keys.append(&mut self.cur_keys);
         self.tick_held_vkeys();
+        self.tick_timers();
+        self.tick_layer_timeout();
+        self.check_handle_layer_change(_tx);
+        self.tick_leds()?;
+        self.tick_hold_tap_decisions();
+        self.tick_statistics();
         #[cfg(feature = "simulated_output")]
         {
             self.kbd_out.tick();
@@ -861,6 +1186,8 @@ impl Kanata {
                 scroll_state.ticks_until_scroll = scroll_state.interval - 1;
                 self.kbd_out
                     .scroll(scroll_state.direction, scroll_state.distance)?;
+                self.event_stream
+                    .mouse_scroll(scroll_state.direction, scroll_state.distance);
             } else {
                 scroll_state.ticks_until_scroll -= 1;
             }
@@ -870,6 +1197,8 @@ impl Kanata {
                 hscroll_state.ticks_until_scroll = hscroll_state.interval - 1;
                 self.kbd_out
                     .scroll(hscroll_state.direction, hscroll_state.distance)?;
+                self.event_stream
+                    .mouse_scroll(hscroll_state.direction, hscroll_state.distance);
             } else {
                 hscroll_state.ticks_until_scroll -= 1;
             }
@@ -907,10 +1236,13 @@ impl Kanata {
                         Some((previous_axis, previous_move)) => {
                             if axis == *previous_axis {
                                 self.kbd_out.move_mouse(*previous_move)?;
+                                self.event_stream.mouse_move(*previous_move);
                                 self.movemouse_buffer = Some((axis, current_move));
                             } else {
                                 self.kbd_out
                                     .move_mouse_many(&[*previous_move, current_move])?;
+                                self.event_stream.mouse_move(*previous_move);
+                                self.event_stream.mouse_move(current_move);
                                 self.movemouse_buffer = None;
                             }
                         }
@@ -920,6 +1252,7 @@ impl Kanata {
                     }
                 } else {
                     self.kbd_out.move_mouse(current_move)?;
+                    self.event_stream.mouse_move(current_move);
                 }
             } else {
                 mmsv.ticks_until_move -= 1;
@@ -954,10 +1287,13 @@ impl Kanata {
                         Some((previous_axis, previous_move)) => {
                             if axis == *previous_axis {
                                 self.kbd_out.move_mouse(*previous_move)?;
+                                self.event_stream.mouse_move(*previous_move);
                                 self.movemouse_buffer = Some((axis, current_move));
                             } else {
                                 self.kbd_out
                                     .move_mouse_many(&[*previous_move, current_move])?;
+                                self.event_stream.mouse_move(*previous_move);
+                                self.event_stream.mouse_move(current_move);
                                 self.movemouse_buffer = None;
                             }
                         }
@@ -967,6 +1303,7 @@ impl Kanata {
                     }
                 } else {
                     self.kbd_out.move_mouse(current_move)?;
+                    self.event_stream.mouse_move(current_move);
                 }
             } else {
                 mmsh.ticks_until_move -= 1;
@@ -1012,6 +1349,9 @@ impl Kanata {
     fn handle_keystate_changes(&mut self, _tx: &Option<Sender<ServerMessage>>) -> Result<bool> {
         let layout = self.layout.bm();
         let custom_event = layout.tick();
+        #[cfg(feature = "tcp_server")]
+        self.event_stream
+            .virtual_keys(&layout.states, &self.virtual_keys);
         let mut live_reload_requested = false;
         let cur_keys = &mut self.cur_keys;
         cur_keys.extend(layout.keycodes());
@@ -1076,6 +1416,17 @@ impl Kanata {
             cur_keys.extend(self.unshifted_keys.iter());
         }
 
+        self.overrides.update_action_overrides(
+            cur_keys,
+            layout.current_layer() as u16,
+            &mut self.override_states,
+        );
+        for idx in self.override_states.action_overrides_to_release() {
+            layout.event(Event::Release(FAKE_KEY_ROW, idx));
+        }
+        for idx in self.override_states.action_overrides_to_press() {
+            layout.event(Event::Press(FAKE_KEY_ROW, idx));
+        }
         self.overrides
             .override_keys(cur_keys, &mut self.override_states);
         mark_overridden_nonmodkeys_for_eager_erasure(&self.override_states, &mut layout.states);
@@ -1090,6 +1441,10 @@ impl Kanata {
             }
         }
 
+        if let Some(autoshift) = &mut self.autoshift {
+            autoshift.process_keys(cur_keys);
+        }
+
         if let Some(caps_word) = &mut self.caps_word {
             if caps_word.maybe_add_lsft(cur_keys) == CapsWordNextState::End {
                 self.caps_word = None;
@@ -1230,7 +1585,10 @@ impl Kanata {
                     match custact {
                         // For unicode, only send on the press. No repeat action is supported for this for
                         // now.
-                        CustomAction::Unicode(c) => self.kbd_out.send_unicode(*c)?,
+                        CustomAction::Unicode(c) => {
+                            self.kbd_out.send_unicode(*c)?;
+                            self.event_stream.unicode(*c);
+                        }
                         CustomAction::LiveReload => {
                             live_reload_requested = true;
                             log::info!(
@@ -1302,15 +1660,19 @@ impl Kanata {
                             if let Some(pbtn) = prev_mouse_btn {
                                 log::debug!("unclick   {:?}", pbtn);
                                 self.kbd_out.release_btn(pbtn)?;
+                                self.event_stream.mouse_release(pbtn);
                             }
                             self.kbd_out.click_btn(*btn)?;
+                            self.event_stream.mouse_press(*btn);
                             prev_mouse_btn = Some(*btn);
                         }
                         CustomAction::MouseTap(btn) => {
                             log::debug!("click     {:?}", btn);
                             self.kbd_out.click_btn(*btn)?;
+                            self.event_stream.mouse_press(*btn);
                             log::debug!("unclick   {:?}", btn);
                             self.kbd_out.release_btn(*btn)?;
+                            self.event_stream.mouse_release(*btn);
                         }
                         CustomAction::MWheel {
                             direction,
@@ -1337,6 +1699,8 @@ impl Kanata {
                         CustomAction::MWheelNotch { direction } => {
                             self.kbd_out
                                 .scroll(*direction, HI_RES_SCROLL_UNITS_IN_LO_RES)?;
+                            self.event_stream
+                                .mouse_scroll(*direction, HI_RES_SCROLL_UNITS_IN_LO_RES);
                         }
                         CustomAction::MoveMouse {
                             direction,
@@ -1435,6 +1799,9 @@ impl Kanata {
                                 self.move_mouse_speed_modifiers
                             );
                         }
+                        CustomAction::DragScroll { distance } => {
+                            self.drag_scroll = Some(*distance);
+                        }
                         CustomAction::Cmd(_cmd) => {
                             #[cfg(feature = "cmd")]
                             cmds.push((
@@ -1548,7 +1915,7 @@ impl Kanata {
                             press_key(&mut self.kbd_out, osc)?;
                             release_key(&mut self.kbd_out, osc)?;
                             if do_caps_word {
-                                self.kbd_out.release_key(OsCode::KEY_LEFTSHIFT)?;
+                                release_key(&mut self.kbd_out, OsCode::KEY_LEFTSHIFT)?;
                             }
                         }
                         CustomAction::DynamicMacroRecord(macro_id) => {
@@ -1556,7 +1923,12 @@ impl Kanata {
                                 begin_record_macro(*macro_id, &mut self.dynamic_macro_record_state)
                             {
                                 log::debug!("saving macro {prev_recorded_macro:?}");
-                                self.dynamic_macros.insert(macro_id, prev_recorded_macro);
+                                store_macro(
+                                    &mut self.dynamic_macros,
+                                    self.dynamic_macro_file.as_deref(),
+                                    macro_id,
+                                    prev_recorded_macro,
+                                );
                             }
                         }
                         CustomAction::DynamicMacroRecordStop(num_actions_to_remove) => {
@@ -1565,7 +1937,12 @@ impl Kanata {
                                 *num_actions_to_remove,
                             ) {
                                 log::debug!("saving macro {prev_recorded_macro:?}");
-                                self.dynamic_macros.insert(macro_id, prev_recorded_macro);
+                                store_macro(
+                                    &mut self.dynamic_macros,
+                                    self.dynamic_macro_file.as_deref(),
+                                    macro_id,
+                                    prev_recorded_macro,
+                                );
                             }
                         }
                         CustomAction::DynamicMacroPlay(macro_id) => {
@@ -1575,6 +1952,27 @@ impl Kanata {
                                 &self.dynamic_macros,
                             );
                         }
+                        CustomAction::DynamicMacroSave(macro_id) => {
+                            save_macro(
+                                &mut self.dynamic_macros,
+                                self.dynamic_macro_file.as_deref(),
+                                *macro_id,
+                            );
+                        }
+                        CustomAction::DynamicMacroLoad(macro_id) => {
+                            load_macro(
+                                &mut self.dynamic_macros,
+                                self.dynamic_macro_file.as_deref(),
+                                *macro_id,
+                            );
+                        }
+                        CustomAction::DynamicMacroClear(macro_id) => {
+                            clear_macro(
+                                &mut self.dynamic_macros,
+                                self.dynamic_macro_file.as_deref(),
+                                *macro_id,
+                            );
+                        }
                         CustomAction::CancelMacroOnNextPress(duration) => {
                             self.macro_on_press_cancel_duration = *duration;
                         }
@@ -1599,8 +1997,34 @@ impl Kanata {
                                 };
                             }
                         },
+                        CustomAction::StateSet { index, value } => {
+                            layout.variables[usize::from(*index)] = *value;
+                        }
+                        CustomAction::StateAdd { index, amount } => {
+                            let var = &mut layout.variables[usize::from(*index)];
+                            *var = var.saturating_add(*amount);
+                        }
+                        CustomAction::StateToggle(index) => {
+                            let var = &mut layout.variables[usize::from(*index)];
+                            *var = i16::from(*var == 0);
+                        }
+                        CustomAction::TimerStart {
+                            name,
+                            coord,
+                            interval,
+                            repeat,
+                        } => {
+                            self.timers.start(name, *coord, *interval, *repeat);
+                        }
+                        CustomAction::TimerCancel(name) => {
+                            self.timers.cancel(name);
+                        }
+                        CustomAction::LayerLock => layout.toggle_layer_lock(),
+                        CustomAction::SetLeds(settings) => self.leds.apply_settings(settings),
                         CustomAction::SetMouse { x, y } => {
                             self.kbd_out.set_mouse(*x, *y)?;
+                            self.event_stream
+                                .mouse(|| MouseActionMessage::SetPosition { x: *x, y: *y });
                         }
                         CustomAction::FakeKeyOnIdle(fkd) => {
                             self.ticks_since_idle = 0;
@@ -1621,7 +2045,7 @@ impl Kanata {
                         | CustomAction::Unmodded { .. }
                         | CustomAction::Unshifted { .. }
                         // Note: ReverseReleaseOrder is already handled earlier on.
-                        | CustomAction::ReverseReleaseOrder { .. }
+                        | CustomAction::ReverseReleaseOrder
                         | CustomAction::CancelMacroOnRelease => {}
                     }
                 }
@@ -1695,6 +2119,12 @@ impl Kanata {
                             );
                             pbtn
                         }
+                        CustomAction::DragScroll { distance } => {
+                            if self.drag_scroll == Some(*distance) {
+                                self.drag_scroll = None;
+                            }
+                            pbtn
+                        }
                         CustomAction::Delay(delay) => {
                             log::debug!("on-press: sleeping for {delay} ms");
                             std::thread::sleep(time::Duration::from_millis((*delay).into()));
@@ -1743,6 +2173,7 @@ impl Kanata {
                     })
                     .map(|btn| {
                         log::debug!("unclick   {:?}", btn);
+                        self.event_stream.mouse_release(*btn);
                         self.kbd_out.release_btn(*btn)
                     })
                 {
@@ -1756,14 +2187,123 @@ impl Kanata {
         Ok(live_reload_requested)
     }
 
+    /// Switch the base layer to the layer with the given name. Returns false if there is no
+    /// such layer.
     #[cfg(feature = "tcp_server")]
-    pub fn change_layer(&mut self, layer_name: String) {
+    pub fn change_layer(&mut self, layer_name: &str) -> bool {
         for (i, l) in self.layer_info.iter().enumerate() {
             if l.name == layer_name {
                 self.layout.bm().set_default_layer(i);
-                return;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Stop the timer with the given name that was started by an `after` or `every` action.
+    /// Returns false if no such timer is running.
+    pub fn cancel_timer(&mut self, name: &str) -> bool {
+        self.timers.cancel(name)
+    }
+
+    /// Returns the current values of the `defstate` variables by name.
+    pub fn state_var_values(&mut self) -> BTreeMap<String, i16> {
+        let layout = self.layout.bm();
+        self.state_vars
+            .iter()
+            .zip(layout.variables.iter())
+            .map(|(var, value)| (var.name.clone(), *value))
+            .collect()
+    }
+
+    /// Set the value of the `defstate` variable with the given name. Returns false if there is no
+    /// such variable.
+    pub fn set_state_var(&mut self, name: &str, value: i16) -> bool {
+        match self.state_vars.iter().position(|var| var.name == name) {
+            Some(i) => {
+                self.layout.bm().variables[i] = value;
+                true
             }
+            None => false,
+        }
+    }
+
+    /// Set a context value, e.g. the class of the focused window, as provided by a client. An
+    /// empty value removes the key from the context.
+    pub fn set_context(&mut self, key: &str, value: &str) {
+        log::debug!("context {key}={value}");
+        if value.is_empty() {
+            self.context_values.remove(key);
+        } else {
+            self.context_values
+                .insert(key.to_string(), value.to_string());
         }
+        self.apply_context();
+    }
+
+    /// Update the active context conditions of the layout and activate the base layer selected
+    /// by `defcontextlayers`, if any.
+    fn apply_context(&mut self) {
+        let layout = self.layout.bm();
+        layout.active_contexts.clear();
+        for id in self.contexts.matching(&self.context_values) {
+            if layout.active_contexts.push(id).is_err() {
+                log::warn!("too many active context conditions, ignoring the rest");
+                break;
+            }
+        }
+        if !self.contexts.has_layer_rules() {
+            return;
+        }
+        match self.contexts.layer_for(&layout.active_contexts) {
+            Some(layer) => {
+                let restore = match self.context_layer_restore {
+                    Some((restore, _)) => restore,
+                    None => layout.default_layer,
+                };
+                self.context_layer_restore = Some((restore, layer));
+                if layout.default_layer != layer {
+                    log::info!("context activates layer {}", self.layer_info[layer].name);
+                    layout.set_default_layer(layer);
+                }
+            }
+            None => {
+                if let Some((restore, layer)) = self.context_layer_restore.take() {
+                    if layout.default_layer == layer {
+                        layout.set_default_layer(restore);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Convert the recorded dynamic macro with the given id into a `macro` action.
+    #[cfg(feature = "tcp_server")]
+    pub fn dynamic_macro_config(&self, macro_id: u16) -> Result<String> {
+        match self.dynamic_macros.get(&macro_id) {
+            Some(items) => macro_to_config(items),
+            None => bail!("dynamic macro {macro_id} has not been recorded"),
+        }
+    }
+
+    /// Request a live reload of the configuration file at `cfg_idx` within `cfg_paths`, or of
+    /// `text` if it is given. The reload happens once all keys are released, after which its
+    /// result is sent to `reply`. Returns an error if another requested reload has not finished.
+    #[cfg(feature = "tcp_server")]
+    pub fn request_live_reload(
+        &mut self,
+        cfg_idx: usize,
+        text: Option<String>,
+        reply: LiveReloadReply,
+    ) -> Result<()> {
+        if self.live_reload_reply.is_some() {
+            bail!("another live reload is in progress");
+        }
+        self.live_reload_cfg_idx = Some(cfg_idx);
+        self.live_reload_text = text;
+        self.live_reload_reply = Some(reply);
+        self.live_reload_requested = true;
+        Ok(())
     }
 
     #[allow(unused_variables)]
@@ -1773,12 +2313,14 @@ impl Kanata {
         let cur_layer = self.layout.bm().current_layer();
         if cur_layer != self.prev_layer {
             let new = self.layer_info[cur_layer].name.clone();
+            self.tap_layer_change_actions(self.prev_layer, cur_layer);
             self.prev_layer = cur_layer;
             self.print_layer(cur_layer);
 
             #[cfg(feature = "tcp_server")]
             if let Some(tx) = tx {
-                match tx.try_send(ServerMessage::LayerChange { new }) {
+                let metadata = self.layer_info[cur_layer].metadata.clone();
+                match tx.try_send(ServerMessage::LayerChange { new, metadata }) {
                     Ok(_) => {}
                     Err(error) => {
                         log::error!("could not send event notification: {}", error);
@@ -1790,6 +2332,17 @@ impl Kanata {
         }
     }
 
+    /// Taps the fake keys of the `on-exit` action of the previous layer and of the `on-enter`
+    /// action of the new layer.
+    fn tap_layer_change_actions(&mut self, prev_layer: usize, cur_layer: usize) {
+        let layout = self.layout.bm();
+        let on_exit = self.layer_info[prev_layer].on_exit;
+        let on_enter = self.layer_info[cur_layer].on_enter;
+        for Coord { x, y } in [on_exit, on_enter].into_iter().flatten() {
+            handle_fakekey_action(FakeKeyAction::Tap, layout, x, y);
+        }
+    }
+
     fn print_layer(&self, layer: usize) {
         if self.log_layer_changes {
             log::info!("Entered layer:\n\n{}", self.layer_info[layer].cfg_text);
@@ -1801,38 +2354,14 @@ impl Kanata {
         rx: Receiver<ServerMessage>,
         clients: crate::tcp_server::Connections,
     ) {
-        use std::io::Write;
         info!("listening for event notifications to relay to connected clients");
-        std::thread::spawn(move || {
-            loop {
-                match rx.recv() {
-                    Err(_) => {
-                        panic!("channel disconnected")
-                    }
-                    Ok(event) => {
-                        let notification = event.as_bytes();
-                        let mut clients = clients.lock();
-                        let mut stale_clients = vec![];
-                        for (id, client) in &mut *clients {
-                            match client.write_all(&notification) {
-                                Ok(_) => {
-                                    log::debug!("layer change notification sent");
-                                }
-                                Err(e) => {
-                                    log::warn!(
-                                        "removing tcp client where write failed: {id}, {e:?}"
-                                    );
-                                    // the client is no longer connected, let's remove them
-                                    stale_clients.push(id.clone());
-                                }
-                            }
-                        }
-
-                        for id in &stale_clients {
-                            log::warn!("removing disconnected tcp client: {id}");
-                            clients.remove(id);
-                        }
-                    }
+        std::thread::spawn(move || loop {
+            match rx.recv() {
+                Err(_) => {
+                    panic!("channel disconnected")
+                }
+                Ok(event) => {
+                    crate::tcp_server::relay_to_clients(&event, &mut clients.lock());
                 }
             }
         });
@@ -2123,7 +2652,12 @@ impl Kanata {
             .as_ref()
             .map(|cv2| cv2.accepts_chords_chv2())
             .unwrap_or(true);
-        is_idle && !counting_idle_ticks && passed_max_switch_timing_check && chordsv2_accepts_chords
+        is_idle
+            && !counting_idle_ticks
+            && passed_max_switch_timing_check
+            && chordsv2_accepts_chords
+            && k.timers.is_empty()
+            && k.layer_info[k.layout.b().current_layer()].timeout.is_none()
     }
 
     pub fn is_idle(&self) -> bool {
@@ -2145,6 +2679,7 @@ impl Kanata {
             && self.move_mouse_state_horizontal.is_none()
             && self.dynamic_macro_replay_state.is_none()
             && self.caps_word.is_none()
+            && self.autoshift.as_ref().map(|a| a.is_idle()).unwrap_or(true)
             && self.vkeys_pending_release.is_empty()
             && !self.layout.b().states.iter().any(|s| {
                 matches!(s, State::SeqCustomPending(_) | State::SeqCustomActive(_))
@@ -2265,6 +2800,12 @@ fn check_for_exit(_event: &KeyEvent) {
         if IS_ESC_PRESSED.load(SeqCst) && IS_SPC_PRESSED.load(SeqCst) && IS_LCL_PRESSED.load(SeqCst)
         {
             log::info!("{EXIT_MSG}");
+            // On Linux the raised signal saves the statistics and dynamic macros.
+            #[cfg(not(target_os = "linux"))]
+            {
+                flush_statistics_on_exit();
+                flush_macro_file();
+            }
             #[cfg(all(target_os = "windows", feature = "gui"))]
             {
                 #[cfg(not(feature = "interception_driver"))]
@@ -2290,6 +2831,26 @@ fn check_for_exit(_event: &KeyEvent) {
     }
 }
 
+/// Resolve a path from a `defcfg` option relative to the directory of the configuration file,
+/// the same way that included files are.
+fn cfg_relative_path(cfg_path: &Path, path: Option<&str>) -> Option<PathBuf> {
+    let path = Path::new(path?);
+    if path.is_absolute() {
+        return Some(path.to_owned());
+    }
+    Some(match cfg_path.parent() {
+        Some(dir) => dir.join(path),
+        None => path.to_owned(),
+    })
+}
+
+/// Tap the `on-enter` action of the layer that kanata starts in.
+fn tap_startup_on_enter(layer_info: &[LayerInfo], layout: &mut cfg::KanataLayout) {
+    if let Some(Coord { x, y }) = layer_info[0].on_enter {
+        handle_fakekey_action(FakeKeyAction::Tap, layout.bm(), x, y);
+    }
+}
+
 fn update_kbd_out(_cfg: &CfgOptions, _kbd_out: &KbdOut) -> Result<()> {
     #[cfg(all(not(feature = "simulated_output"), target_os = "linux"))]
     {
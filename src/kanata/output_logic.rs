@@ -35,9 +35,31 @@ pub(super) const KEY_IGNORE_MAX: u16 = 0x2ad; // KEY_MACRO30
 pub(super) fn write_key(kb: &mut KbdOut, osc: OsCode, val: KeyValue) -> Result<(), std::io::Error> {
     match u16::from(osc) {
         KEY_IGNORE_MIN..=KEY_IGNORE_MAX => Ok(()),
-        _ => kb.write_key(osc, val),
+        _ => {
+            kb.write_key(osc, val)?;
+            match val {
+                KeyValue::Press => key_output(osc, KeyEventAction::Press),
+                KeyValue::Release => key_output(osc, KeyEventAction::Release),
+                KeyValue::Repeat => key_output(osc, KeyEventAction::Repeat),
+                KeyValue::Tap | KeyValue::WakeUp => {}
+            }
+            Ok(())
+        }
     }
 }
+/// Press a key on the output device and report it to clients subscribed to key output. Keys
+/// should be written with this or [`kbd_release_key`] instead of calling `KbdOut` directly.
+pub(super) fn kbd_press_key(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error> {
+    kb.press_key(osc)?;
+    key_output(osc, KeyEventAction::Press);
+    Ok(())
+}
+/// Release a key on the output device and report it to clients subscribed to key output.
+pub(super) fn kbd_release_key(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error> {
+    kb.release_key(osc)?;
+    key_output(osc, KeyEventAction::Release);
+    Ok(())
+}
 pub(super) fn press_key(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error> {
     use OsCode::*;
     match u16::from(osc) {
@@ -51,6 +73,12 @@ pub(super) fn press_key(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Err
                 let direction = osc_to_wheel_direction(osc);
                 kb.scroll(direction, HI_RES_SCROLL_UNITS_IN_LO_RES)
             }
+            MouseMoveUp | MouseMoveDown | MouseMoveLeft | MouseMoveRight => {
+                kb.move_mouse(CalculatedMouseMove {
+                    direction: osc_to_move_direction(osc),
+                    distance: MOUSE_MOVEMENT_KEY_THRESHOLD.load(Ordering::SeqCst),
+                })
+            }
             _ => post_filter_press(kb, osc),
         },
     }
@@ -64,9 +92,10 @@ pub(super) fn release_key(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::E
                 let btn = osc_to_btn(osc);
                 kb.release_btn(btn)
             }
-            MouseWheelUp | MouseWheelDown | MouseWheelLeft | MouseWheelRight => {
-                // no-op: these are handled as scroll events in the press but scroll has no notion
-                // of release.
+            MouseWheelUp | MouseWheelDown | MouseWheelLeft | MouseWheelRight | MouseMoveUp
+            | MouseMoveDown | MouseMoveLeft | MouseMoveRight => {
+                // no-op: these are handled as scroll or movement events in the press but those
+                // have no notion of release.
                 Ok(())
             }
             _ => post_filter_release(kb, osc),
@@ -96,11 +125,51 @@ fn osc_to_wheel_direction(osc: OsCode) -> MWheelDirection {
         _ => unreachable!("called osc_to_wheel_direction with bad value {osc}"),
     }
 }
+fn osc_to_move_direction(osc: OsCode) -> MoveDirection {
+    use MoveDirection::*;
+    use OsCode::*;
+    match osc {
+        MouseMoveUp => Up,
+        MouseMoveDown => Down,
+        MouseMoveLeft => Left,
+        MouseMoveRight => Right,
+        _ => unreachable!("called osc_to_move_direction with bad value {osc}"),
+    }
+}
+
+/// The kind of output device that sends a key, for when keyboard and pointer output are split
+/// into separate devices.
+#[cfg(all(
+    target_os = "linux",
+    not(feature = "simulated_output"),
+    not(feature = "passthru_ahk")
+))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OutputDeviceClass {
+    Keyboard,
+    Pointer,
+}
+#[cfg(all(
+    target_os = "linux",
+    not(feature = "simulated_output"),
+    not(feature = "passthru_ahk")
+))]
+pub(crate) fn output_device_class(osc: OsCode) -> OutputDeviceClass {
+    use OsCode::*;
+    match osc {
+        BTN_LEFT | BTN_RIGHT | BTN_MIDDLE | BTN_SIDE | BTN_EXTRA | BTN_FORWARD | BTN_BACK
+        | BTN_TASK | MouseWheelUp | MouseWheelDown | MouseWheelLeft | MouseWheelRight
+        | MouseMoveUp | MouseMoveDown | MouseMoveLeft | MouseMoveRight => {
+            OutputDeviceClass::Pointer
+        }
+        _ => OutputDeviceClass::Keyboard,
+    }
+}
 
 fn post_filter_press(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error> {
     #[cfg(not(feature = "zippychord"))]
     {
-        kb.press_key(osc)
+        kbd_press_key(kb, osc)
     }
     #[cfg(feature = "zippychord")]
     {
@@ -111,7 +180,7 @@ fn post_filter_press(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error>
 fn post_filter_release(kb: &mut KbdOut, osc: OsCode) -> Result<(), std::io::Error> {
     #[cfg(not(feature = "zippychord"))]
     {
-        kb.release_key(osc)
+        kbd_release_key(kb, osc)
     }
     #[cfg(feature = "zippychord")]
     {
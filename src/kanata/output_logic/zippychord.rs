@@ -260,23 +260,23 @@ impl ZchState {
         osc: OsCode,
     ) -> Result<(), std::io::Error> {
         if self.zch_chords.is_empty() {
-            return kb.press_key(osc);
+            return kbd_press_key(kb, osc);
         }
         match osc {
             OsCode::KEY_LEFTSHIFT => {
                 self.zchd.zchd_is_lsft_active = true;
-                return kb.press_key(osc);
+                return kbd_press_key(kb, osc);
             }
             OsCode::KEY_RIGHTSHIFT => {
                 self.zchd.zchd_is_rsft_active = true;
-                return kb.press_key(osc);
+                return kbd_press_key(kb, osc);
             }
             OsCode::KEY_RIGHTALT => {
                 self.zchd.zchd_is_altgr_active = true;
-                return kb.press_key(osc);
+                return kbd_press_key(kb, osc);
             }
             osc if osc.is_zippy_ignored() => {
-                return kb.press_key(osc);
+                return kbd_press_key(kb, osc);
             }
             _ => {}
         }
@@ -295,12 +295,12 @@ impl ZchState {
                 })
         {
             self.zchd.zchd_characters_to_delete_on_next_activation -= 1;
-            kb.press_key(OsCode::KEY_BACKSPACE)?;
-            kb.release_key(OsCode::KEY_BACKSPACE)?;
+            kbd_press_key(kb, OsCode::KEY_BACKSPACE)?;
+            kbd_release_key(kb, OsCode::KEY_BACKSPACE)?;
         }
         self.zchd.zchd_smart_space_state = ZchSmartSpaceState::Inactive;
         if self.zchd.zchd_enabled_state != ZchEnabledState::Enabled {
-            return kb.press_key(osc);
+            return kbd_press_key(kb, osc);
         }
 
         // Zippychording is enabled. Ensure the deadline to disable it if no chord activates is
@@ -388,8 +388,8 @@ impl ZchState {
                         }
                         - common_prefix_len_from_past_activation)
                     {
-                        kb.press_key(OsCode::KEY_BACKSPACE)?;
-                        kb.release_key(OsCode::KEY_BACKSPACE)?;
+                        kbd_press_key(kb, OsCode::KEY_BACKSPACE)?;
+                        kbd_release_key(kb, OsCode::KEY_BACKSPACE)?;
                     }
                     self.zchd.zchd_characters_to_delete_on_next_activation = 0;
                     self.zchd.zchd_prior_activation_output_count =
@@ -401,7 +401,7 @@ impl ZchState {
                     self.zchd.zchd_characters_to_delete_on_next_activation += 1;
                     self.zchd.zchd_prior_activation_output_count +=
                         self.zchd.zchd_input_keys.zchik_keys().len() as i16;
-                    kb.press_key(osc)?;
+                    kbd_press_key(kb, osc)?;
                 }
 
                 self.zchd
@@ -411,7 +411,7 @@ impl ZchState {
                 #[cfg(feature = "interception_driver")]
                 let mut send_count = 0;
                 if self.zchd.zchd_is_altgr_active && !a.zch_output.is_empty() {
-                    kb.release_key(OsCode::KEY_RIGHTALT)?;
+                    kbd_release_key(kb, OsCode::KEY_RIGHTALT)?;
                 }
                 for key_to_send in a
                     .zch_output
@@ -449,16 +449,16 @@ impl ZchState {
                             // does not seem useful to keep held on the first typed output so it is
                             // always released at the beginning and pressed at the end if it was
                             // previously being held.
-                            kb.press_key(OsCode::KEY_RIGHTALT)?;
+                            kbd_press_key(kb, OsCode::KEY_RIGHTALT)?;
                             type_osc(osc, kb, &self.zchd)?;
-                            kb.release_key(OsCode::KEY_RIGHTALT)?;
+                            kbd_release_key(kb, OsCode::KEY_RIGHTALT)?;
                         }
                         ZchOutput::ShiftAltGr(osc) | ZchOutput::NoEraseShiftAltGr(osc) => {
-                            kb.press_key(OsCode::KEY_RIGHTALT)?;
+                            kbd_press_key(kb, OsCode::KEY_RIGHTALT)?;
                             maybe_press_sft_during_activation(released_sft, kb, &self.zchd)?;
                             type_osc(osc, kb, &self.zchd)?;
                             maybe_release_sft_during_activation(released_sft, kb, &self.zchd)?;
-                            kb.release_key(OsCode::KEY_RIGHTALT)?;
+                            kbd_release_key(kb, OsCode::KEY_RIGHTALT)?;
                         }
                     };
 
@@ -468,10 +468,10 @@ impl ZchState {
                     if !released_sft && !self.zchd.zchd_is_caps_word_active {
                         released_sft = true;
                         if self.zchd.zchd_is_lsft_active {
-                            kb.release_key(OsCode::KEY_LEFTSHIFT)?;
+                            kbd_release_key(kb, OsCode::KEY_LEFTSHIFT)?;
                         }
                         if self.zchd.zchd_is_rsft_active {
-                            kb.release_key(OsCode::KEY_RIGHTSHIFT)?;
+                            kbd_release_key(kb, OsCode::KEY_RIGHTSHIFT)?;
                         }
                     }
                 }
@@ -501,21 +501,21 @@ impl ZchState {
                     self.zchd.zchd_prior_activation_output_count += 1;
                     self.zchd.zchd_characters_to_delete_on_next_activation += 1;
 
-                    kb.press_key(OsCode::KEY_SPACE)?;
-                    kb.release_key(OsCode::KEY_SPACE)?;
+                    kbd_press_key(kb, OsCode::KEY_SPACE)?;
+                    kbd_release_key(kb, OsCode::KEY_SPACE)?;
                 }
 
                 if !self.zchd.zchd_is_caps_word_active {
                     // When expanding, lsft/rsft will be released after the first press.
                     if self.zchd.zchd_is_lsft_active {
-                        kb.press_key(OsCode::KEY_LEFTSHIFT)?;
+                        kbd_press_key(kb, OsCode::KEY_LEFTSHIFT)?;
                     }
                     if self.zchd.zchd_is_rsft_active {
-                        kb.press_key(OsCode::KEY_RIGHTSHIFT)?;
+                        kbd_press_key(kb, OsCode::KEY_RIGHTSHIFT)?;
                     }
                 }
                 if self.zchd.zchd_is_altgr_active && !a.zch_output.is_empty() {
-                    kb.press_key(OsCode::KEY_RIGHTALT)?;
+                    kbd_press_key(kb, OsCode::KEY_RIGHTALT)?;
                 }
 
                 // Note: it is incorrect to clear input keys.
@@ -539,12 +539,12 @@ impl ZchState {
             IsSubset => {
                 self.zchd.zchd_last_press = ZchLastPressClassification::NotChord;
                 self.zchd.zchd_characters_to_delete_on_next_activation += 1;
-                kb.press_key(osc)
+                kbd_press_key(kb, osc)
             }
 
             Neither => {
                 self.zchd.zchd_soft_reset();
-                kb.press_key(osc)
+                kbd_press_key(kb, osc)
             }
         }
     }
@@ -556,7 +556,7 @@ impl ZchState {
         osc: OsCode,
     ) -> Result<(), std::io::Error> {
         if self.zch_chords.is_empty() {
-            return kb.release_key(osc);
+            return kbd_release_key(kb, osc);
         }
         match osc {
             OsCode::KEY_LEFTSHIFT => {
@@ -571,11 +571,11 @@ impl ZchState {
             _ => {}
         }
         if osc.is_zippy_ignored() {
-            return kb.release_key(osc);
+            return kbd_release_key(kb, osc);
         }
         self.zchd.zchd_state_change(&self.zch_cfg);
         self.zchd.zchd_release_key(osc);
-        kb.release_key(osc)
+        kbd_release_key(kb, osc)
     }
 
     /// Tick the zch output state.
@@ -592,11 +592,11 @@ impl ZchState {
 
 fn type_osc(osc: OsCode, kb: &mut KbdOut, zchd: &ZchDynamicState) -> Result<(), std::io::Error> {
     if zchd.zchd_input_keys.zchik_contains(osc) {
-        kb.release_key(osc)?;
-        kb.press_key(osc)?;
+        kbd_release_key(kb, osc)?;
+        kbd_press_key(kb, osc)?;
     } else {
-        kb.press_key(osc)?;
-        kb.release_key(osc)?;
+        kbd_press_key(kb, osc)?;
+        kbd_release_key(kb, osc)?;
     }
     Ok(())
 }
@@ -609,7 +609,7 @@ fn maybe_press_sft_during_activation(
     if !zchd.zchd_is_caps_word_active
         && (sft_already_released || !zchd.zchd_is_lsft_active && !zchd.zchd_is_rsft_active)
     {
-        kb.press_key(OsCode::KEY_LEFTSHIFT)?;
+        kbd_press_key(kb, OsCode::KEY_LEFTSHIFT)?;
     }
     Ok(())
 }
@@ -622,7 +622,7 @@ fn maybe_release_sft_during_activation(
     if !zchd.zchd_is_caps_word_active
         && (sft_already_released || !zchd.zchd_is_lsft_active && !zchd.zchd_is_rsft_active)
     {
-        kb.release_key(OsCode::KEY_LEFTSHIFT)?;
+        kbd_release_key(kb, OsCode::KEY_LEFTSHIFT)?;
     }
     Ok(())
 }
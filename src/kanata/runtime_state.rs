@@ -0,0 +1,127 @@
+//! Snapshot of the processing state, sent to TCP clients for debugging.
+
+use super::*;
+
+use kanata_keyberon::layout::WaitingState;
+use kanata_tcp_protocol as proto;
+
+impl Kanata {
+    pub fn runtime_state(&self) -> proto::RuntimeState {
+        let layout = self.layout.b();
+        let layer_name = |layer: usize| {
+            self.layer_info
+                .get(layer)
+                .map(|info| info.name.clone())
+                .unwrap_or_else(|| layer.to_string())
+        };
+        let waiting = layout
+            .waiting
+            .iter()
+            .chain(layout.extra_waiting.iter())
+            .map(|w| self.waiting_state(w))
+            .collect();
+        let pending_chord_inputs = layout
+            .chords_v2
+            .iter()
+            .flat_map(|chv2| chv2.pending_inputs_chv2())
+            .map(|column| self.key_position((NORMAL_KEY_ROW, column)))
+            .collect();
+        proto::RuntimeState {
+            base_layer: layer_name(layout.default_layer),
+            current_layer: layer_name(layout.current_layer()),
+            held_layers: layout
+                .active_held_layers()
+                .map(|layer| layer_name(usize::from(layer)))
+                .collect(),
+            active_states: layout
+                .states
+                .iter()
+                .filter_map(|state| self.active_state(state, &layer_name))
+                .collect(),
+            waiting,
+            pending_chord_inputs,
+            one_shot: proto::OneShotState {
+                keys: layout
+                    .oneshot
+                    .keys
+                    .iter()
+                    .map(|coord| self.key_position(*coord))
+                    .collect(),
+                released_keys: layout
+                    .oneshot
+                    .released_keys
+                    .iter()
+                    .map(|coord| self.key_position(*coord))
+                    .collect(),
+                ticks_until_timeout: layout.oneshot.timeout,
+            },
+            caps_word_ticks_remaining: self.caps_word.as_ref().map(|cw| cw.timeout_ticks),
+            sequence: self
+                .sequence_state
+                .is_active()
+                .then(|| proto::SequenceState {
+                    keys: self
+                        .sequence_state
+                        .raw_oscs
+                        .iter()
+                        .map(|osc| osc.to_string())
+                        .collect(),
+                    ticks_until_timeout: self.sequence_state.ticks_until_timeout,
+                }),
+            dynamic_macro_recording: self.dynamic_macro_record_state.as_ref().map(|state| {
+                proto::DynamicMacroRecording {
+                    macro_id: state.macro_id(),
+                    recorded_events: state.recorded_events(),
+                }
+            }),
+        }
+    }
+
+    pub(crate) fn key_position(&self, (row, column): (u8, u16)) -> proto::KeyPosition {
+        let name = match row {
+            NORMAL_KEY_ROW => OsCode::from_u16(column).map(|osc| osc.to_string()),
+            FAKE_KEY_ROW => self
+                .virtual_keys
+                .iter()
+                .find(|(_, idx)| **idx == usize::from(column))
+                .map(|(name, _)| name.clone()),
+            _ => None,
+        };
+        proto::KeyPosition { row, column, name }
+    }
+
+    fn active_state(
+        &self,
+        state: &State<'_, &&[&CustomAction]>,
+        layer_name: &impl Fn(usize) -> String,
+    ) -> Option<proto::ActiveState> {
+        let kind = match state {
+            State::NormalKey { .. } => "NormalKey",
+            State::LayerModifier { .. } => "LayerModifier",
+            State::Custom { .. } => "Custom",
+            State::FakeKey { .. } => "FakeKey",
+            State::RepeatingSequence { .. } => "RepeatingSequence",
+            State::SeqCustomPending(_) => "SeqCustomPending",
+            State::SeqCustomActive(_) => "SeqCustomActive",
+            State::Tombstone => return None,
+        };
+        Some(proto::ActiveState {
+            kind: kind.to_string(),
+            position: state.coord().map(|coord| self.key_position(coord)),
+            key: state.keycode().map(|kc| OsCode::from(kc).to_string()),
+            layer: match state {
+                State::LayerModifier { value, .. } => Some(layer_name(*value)),
+                _ => None,
+            },
+        })
+    }
+
+    fn waiting_state(&self, w: &WaitingState<'_, &&[&CustomAction]>) -> proto::WaitingState {
+        proto::WaitingState {
+            kind: format!("{:?}", w.kind()),
+            position: self.key_position(w.coord()),
+            ticks_elapsed: w.ticks(),
+            ticks_until_timeout: w.timeout(),
+        }
+    }
+}
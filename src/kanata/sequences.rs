@@ -28,6 +28,9 @@ pub struct SequenceState {
     pub sequence_timeout: u16,
     /// Whether the sequence is active or not.
     pub activity: SequenceActivity,
+    /// Set when a sequence completes successfully, for the statistics to pick up. The keys of
+    /// the completed sequence remain in `raw_oscs`.
+    pub completed: bool,
 }
 
 impl SequenceState {
@@ -40,6 +43,7 @@ impl SequenceState {
             ticks_until_timeout: 0,
             sequence_timeout: 0,
             activity: Inactive,
+            completed: false,
         }
     }
 
@@ -277,6 +281,7 @@ pub(super) fn do_successful_sequence_termination(
 ) -> Result<(), anyhow::Error> {
     log::debug!("sequence complete; tapping fake key");
     state.activity = Inactive;
+    state.completed = true;
     let sequence = match seq_type {
         EndSequenceType::Standard => &state.sequence,
         EndSequenceType::Overlap => &state.overlapped_sequence,
@@ -320,8 +325,8 @@ pub(super) fn do_successful_sequence_termination(
                     osc if osc.is_modifier() => continue,
                     osc if matches!(u16::from(osc), KEY_IGNORE_MIN..=KEY_IGNORE_MAX) => continue,
                     _ => {
-                        kbd_out.press_key(OsCode::KEY_BACKSPACE)?;
-                        kbd_out.release_key(OsCode::KEY_BACKSPACE)?;
+                        kbd_press_key(kbd_out, OsCode::KEY_BACKSPACE)?;
+                        kbd_release_key(kbd_out, OsCode::KEY_BACKSPACE)?;
                     }
                 }
             }
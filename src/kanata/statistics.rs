@@ -0,0 +1,430 @@
+//! Opt-in collection of usage statistics, for tuning a layout based on how it is really used.
+
+use super::*;
+
+use kanata_keyberon::action::Action;
+use kanata_keyberon::layout::WaitingAction;
+use kanata_parser::keys::oscode_to_str;
+use kanata_tcp_protocol::Statistics;
+
+type LayoutAction<'a> = Action<'a, &'a &'a [&'a CustomAction]>;
+
+/// The running kanata, so that its statistics can be saved when kanata exits without going
+/// through the processing loop, e.g. on a signal or the emergency exit.
+static EXIT_KANATA: Mutex<Option<std::sync::Weak<Mutex<Kanata>>>> = Mutex::new(None);
+
+pub(crate) fn save_statistics_on_exit(kanata: &Arc<Mutex<Kanata>>) {
+    *EXIT_KANATA.lock() = Some(Arc::downgrade(kanata));
+}
+
+/// Write the statistics file of the running kanata before exiting.
+pub(crate) fn flush_statistics_on_exit() {
+    let Some(kanata) = EXIT_KANATA
+        .lock()
+        .as_ref()
+        .and_then(std::sync::Weak::upgrade)
+    else {
+        return;
+    };
+    // The lock might be held by a thread that is stuck, so don't wait on it forever.
+    let Some(mut k) = kanata.try_lock_for(time::Duration::from_millis(500)) else {
+        log::warn!("could not save statistics before exiting");
+        return;
+    };
+    if let Some(collector) = &mut k.statistics {
+        collector.flush();
+    }
+}
+
+pub(crate) struct StatisticsCollector {
+    pub(crate) stats: Statistics,
+    /// File that statistics are periodically written to, if configured.
+    file: Option<PathBuf>,
+    flush_interval: time::Duration,
+    last_flush: instant::Instant,
+    /// Whether anything was counted since the statistics were last written to the file.
+    unsaved: bool,
+}
+
+impl StatisticsCollector {
+    /// Returns a collector if statistics are enabled by the configuration. Statistics saved in
+    /// the statistics file are loaded so that counting continues from where it left off.
+    pub(crate) fn from_cfg(options: &CfgOptions, cfg_path: &Path) -> Option<Self> {
+        if !options.statistics && options.statistics_file.is_none() {
+            return None;
+        }
+        let file = cfg_relative_path(cfg_path, options.statistics_file.as_deref());
+        let stats = file.as_deref().map(read_statistics).unwrap_or_default();
+        Some(Self {
+            stats,
+            file,
+            flush_interval: time::Duration::from_secs(options.statistics_flush_interval.into()),
+            last_flush: instant::Instant::now(),
+            unsaved: false,
+        })
+    }
+
+    /// Apply the statistics options of a live reloaded configuration. The statistics collected
+    /// so far are kept unless the statistics file changed, in which case the previous file is
+    /// written and the new file is loaded.
+    pub(crate) fn update_from_cfg(
+        collector: &mut Option<Self>,
+        options: &CfgOptions,
+        cfg_path: &Path,
+    ) {
+        let Some(mut new) = Self::from_cfg(options, cfg_path) else {
+            if let Some(mut prev) = collector.take() {
+                prev.flush();
+            }
+            return;
+        };
+        if let Some(mut prev) = collector.take() {
+            if prev.file == new.file {
+                new.stats = prev.stats;
+                new.unsaved = prev.unsaved;
+            } else {
+                prev.flush();
+            }
+        }
+        *collector = Some(new);
+    }
+
+    /// Write the statistics to the file if one is configured and anything changed.
+    pub(crate) fn flush(&mut self) {
+        self.last_flush = instant::Instant::now();
+        let Some(file) = &self.file else {
+            return;
+        };
+        if !self.unsaved {
+            return;
+        }
+        let text = if is_csv(file) {
+            statistics_to_csv(&self.stats)
+        } else {
+            self.stats.to_json()
+        };
+        match std::fs::write(file, text) {
+            Ok(()) => {
+                log::debug!("saved statistics to {}", file.display());
+                self.unsaved = false;
+            }
+            Err(e) => log::error!("failed to save statistics to {}: {e}", file.display()),
+        }
+    }
+
+    fn flush_if_due(&mut self) {
+        if self.last_flush.elapsed() >= self.flush_interval {
+            self.flush();
+        }
+    }
+
+    fn key_press(&mut self, key: String, layer: &str, action: String) {
+        *self.stats.key_presses.entry(key.clone()).or_default() += 1;
+        *self
+            .stats
+            .layer_key_presses
+            .entry(layer.to_string())
+            .or_default()
+            .entry(key)
+            .or_default() += 1;
+        *self.stats.action_presses.entry(action).or_default() += 1;
+        self.unsaved = true;
+    }
+
+    pub(crate) fn hold_tap(&mut self, key: String, outcome: WaitingAction) {
+        let counts = self.stats.hold_taps.entry(key).or_default();
+        match outcome {
+            WaitingAction::Tap => counts.tap += 1,
+            WaitingAction::Hold => counts.hold += 1,
+            WaitingAction::Timeout => counts.timeout += 1,
+            WaitingAction::NoOp => return,
+        }
+        self.unsaved = true;
+    }
+}
+
+/// Statistics files are written as JSON unless their name ends with `.csv`.
+fn is_csv(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"))
+}
+
+/// Write the statistics as CSV with one count per row, e.g. `layer_key_press,base,a,12`.
+/// The layer column is only filled for `layer_key_press` rows.
+fn statistics_to_csv(stats: &Statistics) -> String {
+    let mut rows = vec![];
+    let mut row = |statistic: &str, layer: &str, name: &str, count: u64| {
+        rows.push(format!(
+            "{statistic},{},{},{count}",
+            csv_field(layer),
+            csv_field(name)
+        ));
+    };
+    for (key, count) in &stats.key_presses {
+        row("key_press", "", key, *count);
+    }
+    for (layer, presses) in &stats.layer_key_presses {
+        for (key, count) in presses {
+            row("layer_key_press", layer, key, *count);
+        }
+    }
+    for (action, count) in &stats.action_presses {
+        row("action_press", "", action, *count);
+    }
+    for (key, counts) in &stats.hold_taps {
+        row("hold_tap_tap", "", key, counts.tap);
+        row("hold_tap_hold", "", key, counts.hold);
+        row("hold_tap_timeout", "", key, counts.timeout);
+    }
+    row("chord_hit", "", "", stats.chords.hits);
+    row("chord_miss", "", "", stats.chords.misses);
+    for (keys, count) in &stats.sequences {
+        row("sequence", "", keys, *count);
+    }
+    let mut csv = String::from("statistic,layer,name,count\n");
+    for row in rows {
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Read statistics written by `statistics_to_csv`.
+fn statistics_from_csv(csv: &str) -> Result<Statistics, String> {
+    let mut stats = Statistics::default();
+    for (i, line) in csv.lines().enumerate().skip(1) {
+        if line.is_empty() {
+            continue;
+        }
+        let fields = csv_record(line);
+        let [statistic, layer, name, count] = fields.as_slice() else {
+            return Err(format!("line {}: expected 4 fields", i + 1));
+        };
+        let count: u64 = count
+            .parse()
+            .map_err(|e| format!("line {}: invalid count: {e}", i + 1))?;
+        let (name, layer) = (name.clone(), layer.clone());
+        match statistic.as_str() {
+            "key_press" => *stats.key_presses.entry(name).or_default() += count,
+            "layer_key_press" => {
+                *stats
+                    .layer_key_presses
+                    .entry(layer)
+                    .or_default()
+                    .entry(name)
+                    .or_default() += count
+            }
+            "action_press" => *stats.action_presses.entry(name).or_default() += count,
+            "hold_tap_tap" => stats.hold_taps.entry(name).or_default().tap += count,
+            "hold_tap_hold" => stats.hold_taps.entry(name).or_default().hold += count,
+            "hold_tap_timeout" => stats.hold_taps.entry(name).or_default().timeout += count,
+            "chord_hit" => stats.chords.hits += count,
+            "chord_miss" => stats.chords.misses += count,
+            "sequence" => *stats.sequences.entry(name).or_default() += count,
+            _ => return Err(format!("line {}: unknown statistic {statistic}", i + 1)),
+        }
+    }
+    Ok(stats)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+fn csv_record(line: &str) -> Vec<String> {
+    let mut fields = vec![String::new()];
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        let field = fields.last_mut().expect("has a field");
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => fields.push(String::new()),
+            c => field.push(c),
+        }
+    }
+    fields
+}
+
+fn read_statistics(path: &Path) -> Statistics {
+    let parse = |text: &str| {
+        if is_csv(path) {
+            statistics_from_csv(text)
+        } else {
+            text.parse::<Statistics>().map_err(|e| e.to_string())
+        }
+    };
+    match std::fs::read_to_string(path) {
+        Ok(text) => parse(&text).unwrap_or_else(|e| {
+            log::error!(
+                "failed to parse statistics in {}, starting from zero: {e}",
+                path.display()
+            );
+            Statistics::default()
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Statistics::default(),
+        Err(e) => {
+            log::error!("failed to read {}, starting from zero: {e}", path.display());
+            Statistics::default()
+        }
+    }
+}
+
+pub(crate) fn key_name(osc: OsCode) -> String {
+    oscode_to_str(osc)
+        .map(str::to_string)
+        .unwrap_or_else(|| osc.to_string())
+}
+
+impl Kanata {
+    /// Count a key press along with the layer and action it will resolve from. The layer is the
+    /// top of the active layer stack for the device, i.e. a held or locked layer, the device's
+    /// layer or the default layer.
+    pub(crate) fn record_press_statistics(&mut self, event: &KeyEvent) {
+        let Some(collector) = &mut self.statistics else {
+            return;
+        };
+        let layout = self.layout.b();
+        let idx = usize::from(event.code);
+        let layer_name = |layer: usize| {
+            self.layer_info
+                .get(layer)
+                .map(|info| info.name.as_str())
+                .unwrap_or_default()
+        };
+        let layer_order = layout.input_layer_order(event.device);
+        let layer = layer_order
+            .first()
+            .map(|layer| usize::from(*layer))
+            .unwrap_or(layout.default_layer);
+        let action = layer_order
+            .iter()
+            .map(|layer| &layout.layers[usize::from(*layer)][0][idx])
+            .find(|action| !matches!(action, Action::Trans))
+            .filter(|action| !matches!(action, Action::Src))
+            .unwrap_or(&layout.src_keys[idx]);
+        let action = describe_action(action, &layer_name);
+        collector.key_press(key_name(event.code), layer_name(layer), action);
+    }
+
+    /// Count the outcomes that were resolved during the tick and write the statistics file if
+    /// it is time to.
+    pub(crate) fn tick_statistics(&mut self) {
+        let Some(collector) = &mut self.statistics else {
+            return;
+        };
+        let layout = self.layout.bm();
+        if let Some(chords_v2) = &mut layout.chords_v2 {
+            let (hits, misses) = chords_v2.take_hits_and_misses_chv2();
+            if hits > 0 || misses > 0 {
+                collector.stats.chords.hits += u64::from(hits);
+                collector.stats.chords.misses += u64::from(misses);
+                collector.unsaved = true;
+            }
+        }
+        if self.sequence_state.completed {
+            self.sequence_state.completed = false;
+            let keys = self
+                .sequence_state
+                .raw_oscs
+                .iter()
+                .map(|osc| key_name(*osc))
+                .collect::<Vec<_>>()
+                .join(" ");
+            *collector.stats.sequences.entry(keys).or_default() += 1;
+            collector.unsaved = true;
+        }
+        collector.flush_if_due();
+    }
+}
+
+/// Describe an action briefly in configuration syntax, e.g. `(tap-hold a lsft)`.
+fn describe_action<'a>(action: &LayoutAction, layer_name: &impl Fn(usize) -> &'a str) -> String {
+    match action {
+        Action::NoOp => "XX".into(),
+        Action::Trans => "_".into(),
+        Action::Src => "use-defsrc".into(),
+        Action::KeyCode(kc) => key_name(kc.into()),
+        Action::MultipleKeyCodes(kcs) => format!(
+            "(multi {})",
+            kcs.iter()
+                .map(|kc| key_name(kc.into()))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        Action::MultipleActions(acs) => format!(
+            "(multi {})",
+            acs.iter()
+                .map(|ac| describe_action(ac, layer_name))
+                .collect::<Vec<_>>()
+                .join(" ")
+        ),
+        Action::Layer(layer) => format!("(layer-while-held {})", layer_name(*layer)),
+        Action::DefaultLayer(layer) => format!("(layer-switch {})", layer_name(*layer)),
+        Action::Sequence { .. } => "(macro)".into(),
+        Action::RepeatableSequence { .. } => "(macro-repeat)".into(),
+        Action::CancelSequences => "(macro-cancel)".into(),
+        Action::ReleaseState(_) => "(release)".into(),
+        Action::HoldTap(ht) => format!(
+            "(tap-hold {} {})",
+            describe_action(&ht.tap, layer_name),
+            describe_action(&ht.hold, layer_name)
+        ),
+        Action::OneShot(os) => format!("(one-shot {})", describe_action(os.action, layer_name)),
+        Action::OneShotIgnoreEventsTicks(_) => "(one-shot-pause-processing)".into(),
+        Action::TapDance(_) => "(tap-dance)".into(),
+        Action::Chords(_) => "(chord)".into(),
+        Action::Repeat => "rpt".into(),
+        Action::Fork(fork) => format!(
+            "(fork {} {})",
+            describe_action(&fork.left, layer_name),
+            describe_action(&fork.right, layer_name)
+        ),
+        Action::Switch(_) => "(switch)".into(),
+        Action::Custom(cacs) => cacs
+            .iter()
+            .map(|cac| describe_custom_action(cac))
+            .collect::<Vec<_>>()
+            .join(" "),
+    }
+}
+
+/// Only the action name, since the contents of custom actions can be large.
+fn describe_custom_action(cac: &CustomAction) -> &'static str {
+    use CustomAction::*;
+    match cac {
+        Cmd(_) | CmdLog(..) => "(cmd)",
+        CmdOutputKeys(_) => "(cmd-output-keys)",
+        PushMessage(_) => "(push-msg)",
+        Unicode(_) => "(unicode)",
+        Mouse(_) | MouseTap(_) => "(mouse-button)",
+        FakeKey { .. } | FakeKeyOnRelease { .. } | FakeKeyOnIdle(_) => "(on-press)",
+        FakeKeyHoldForDuration(_) => "(hold-for-duration)",
+        MWheel { .. } | MWheelNotch { .. } => "(mwheel)",
+        MoveMouse { .. } | MoveMouseAccel { .. } | MoveMouseSpeed { .. } | SetMouse { .. } => {
+            "(movemouse)"
+        }
+        SequenceLeader(..) => "sldr",
+        LiveReload | LiveReloadNext | LiveReloadPrev | LiveReloadNum(_) | LiveReloadFile(_) => {
+            "lrld"
+        }
+        Repeat => "rpt",
+        DynamicMacroRecord(_) | DynamicMacroRecordStop(_) => "(dynamic-macro-record)",
+        DynamicMacroPlay(_) => "(dynamic-macro-play)",
+        CapsWord(_) => "(caps-word)",
+        Unmodded { .. } | Unshifted { .. } => "(unmod)",
+        StateSet { .. } | StateAdd { .. } | StateToggle(_) => "(state)",
+        TimerStart { .. } | TimerCancel(_) => "(timer)",
+        LayerLock => "layer-lock",
+        SetLeds(_) => "(set-leds)",
+        _ => "(custom)",
+    }
+}
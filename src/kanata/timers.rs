@@ -0,0 +1,66 @@
+//! State of the timers started by the `after` and `every` actions.
+
+use kanata_parser::custom_action::Coord;
+
+#[derive(Debug)]
+struct ActiveTimer {
+    name: String,
+    /// Fake key to tap when the timer fires.
+    coord: Coord,
+    /// Milliseconds until the timer fires.
+    remaining: u32,
+    /// Milliseconds between firings of a periodic timer.
+    repeat_interval: Option<u32>,
+}
+
+#[derive(Debug, Default)]
+pub struct Timers {
+    active: Vec<ActiveTimer>,
+}
+
+impl Timers {
+    /// Starts a timer, replacing a running timer with the same name.
+    pub fn start(&mut self, name: &str, coord: Coord, interval: u32, repeat: bool) {
+        self.cancel(name);
+        self.active.push(ActiveTimer {
+            name: name.to_string(),
+            coord,
+            remaining: interval,
+            repeat_interval: repeat.then_some(interval),
+        });
+    }
+
+    /// Stops the timer with the given name. Returns false if no such timer is running.
+    pub fn cancel(&mut self, name: &str) -> bool {
+        let len = self.active.len();
+        self.active.retain(|t| t.name != name);
+        self.active.len() != len
+    }
+
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.active.is_empty()
+    }
+
+    /// Advances the timers by one millisecond and calls `fire` with the fake key of each timer
+    /// that fires. Timers that do not repeat are removed once they fire.
+    pub fn tick(&mut self, mut fire: impl FnMut(Coord)) {
+        self.active.retain_mut(|t| {
+            t.remaining = t.remaining.saturating_sub(1);
+            if t.remaining > 0 {
+                return true;
+            }
+            fire(t.coord);
+            match t.repeat_interval {
+                Some(interval) => {
+                    t.remaining = interval;
+                    true
+                }
+                None => false,
+            }
+        });
+    }
+}
@@ -63,7 +63,7 @@ impl Kanata {
                                 false => KeyValue::Press,
                                 true => KeyValue::Release,
                             };
-                            KeyEvent { code, value }
+                            KeyEvent::new(code, value)
                         }
                         ic::Stroke::Mouse { state, rolling, .. } => {
                             if mouse_to_intercept_hwids.is_some()
@@ -191,55 +191,25 @@ fn mouse_state_to_event(
     }
 
     if state.contains(ic::MouseState::RIGHT_BUTTON_DOWN) {
-        Some(KeyEvent {
-            code: OsCode::BTN_RIGHT,
-            value: KeyValue::Press,
-        })
+        Some(KeyEvent::new(OsCode::BTN_RIGHT, KeyValue::Press))
     } else if state.contains(ic::MouseState::RIGHT_BUTTON_UP) {
-        Some(KeyEvent {
-            code: OsCode::BTN_RIGHT,
-            value: KeyValue::Release,
-        })
+        Some(KeyEvent::new(OsCode::BTN_RIGHT, KeyValue::Release))
     } else if state.contains(ic::MouseState::LEFT_BUTTON_DOWN) {
-        Some(KeyEvent {
-            code: OsCode::BTN_LEFT,
-            value: KeyValue::Press,
-        })
+        Some(KeyEvent::new(OsCode::BTN_LEFT, KeyValue::Press))
     } else if state.contains(ic::MouseState::LEFT_BUTTON_UP) {
-        Some(KeyEvent {
-            code: OsCode::BTN_LEFT,
-            value: KeyValue::Release,
-        })
+        Some(KeyEvent::new(OsCode::BTN_LEFT, KeyValue::Release))
     } else if state.contains(ic::MouseState::MIDDLE_BUTTON_DOWN) {
-        Some(KeyEvent {
-            code: OsCode::BTN_MIDDLE,
-            value: KeyValue::Press,
-        })
+        Some(KeyEvent::new(OsCode::BTN_MIDDLE, KeyValue::Press))
     } else if state.contains(ic::MouseState::MIDDLE_BUTTON_UP) {
-        Some(KeyEvent {
-            code: OsCode::BTN_MIDDLE,
-            value: KeyValue::Release,
-        })
+        Some(KeyEvent::new(OsCode::BTN_MIDDLE, KeyValue::Release))
     } else if state.contains(ic::MouseState::BUTTON_4_DOWN) {
-        Some(KeyEvent {
-            code: OsCode::BTN_SIDE,
-            value: KeyValue::Press,
-        })
+        Some(KeyEvent::new(OsCode::BTN_SIDE, KeyValue::Press))
     } else if state.contains(ic::MouseState::BUTTON_4_UP) {
-        Some(KeyEvent {
-            code: OsCode::BTN_SIDE,
-            value: KeyValue::Release,
-        })
+        Some(KeyEvent::new(OsCode::BTN_SIDE, KeyValue::Release))
     } else if state.contains(ic::MouseState::BUTTON_5_DOWN) {
-        Some(KeyEvent {
-            code: OsCode::BTN_EXTRA,
-            value: KeyValue::Press,
-        })
+        Some(KeyEvent::new(OsCode::BTN_EXTRA, KeyValue::Press))
     } else if state.contains(ic::MouseState::BUTTON_5_UP) {
-        Some(KeyEvent {
-            code: OsCode::BTN_EXTRA,
-            value: KeyValue::Release,
-        })
+        Some(KeyEvent::new(OsCode::BTN_EXTRA, KeyValue::Release))
     } else if state.contains(ic::MouseState::WHEEL) {
         let osc = if rolling >= 0 {
             OsCode::MouseWheelUp
@@ -247,10 +217,7 @@ fn mouse_state_to_event(
             OsCode::MouseWheelDown
         };
         if MAPPED_KEYS.lock().contains(&osc) {
-            Some(KeyEvent {
-                code: osc,
-                value: KeyValue::Tap,
-            })
+            Some(KeyEvent::new(osc, KeyValue::Tap))
         } else {
             None
         }
@@ -261,10 +228,7 @@ fn mouse_state_to_event(
             OsCode::MouseWheelLeft
         };
         if MAPPED_KEYS.lock().contains(&osc) {
-            Some(KeyEvent {
-                code: osc,
-                value: KeyValue::Tap,
-            })
+            Some(KeyEvent::new(osc, KeyValue::Tap))
         } else {
             None
         }
@@ -191,6 +191,8 @@ mod cli {
 
     pub(crate) fn main_impl() -> Result<()> {
         let args = cli_init()?;
+        #[cfg(target_os = "linux")]
+        let _led_guard = oskbd::LedRestoreGuard::new();
         let kanata_arc = Kanata::new_arc(&args)?;
 
         if !args.nodelay {
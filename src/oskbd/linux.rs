@@ -3,7 +3,10 @@
 #![cfg_attr(feature = "simulated_output", allow(dead_code, unused_imports))]
 
 pub use evdev::BusType;
-use evdev::{uinput, Device, EventType, InputEvent, Key, PropType, RelativeAxisType};
+use evdev::{
+    uinput, AbsInfo, AbsoluteAxisType, Device, EventType, InputEvent, Key, PropType,
+    RelativeAxisType, UinputAbsSetup,
+};
 use inotify::{Inotify, WatchMask};
 use mio::{unix::SourceFd, Events, Interest, Poll, Token};
 use nix::ioctl_read_buf;
@@ -19,12 +22,16 @@ use std::io;
 use std::os::unix::io::AsRawFd;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 use std::thread;
 
 use super::*;
+#[cfg(all(not(feature = "simulated_output"), not(feature = "passthru_ahk")))]
+use crate::kanata::{output_device_class, OutputDeviceClass};
 use crate::{kanata::CalculatedMouseMove, oskbd::KeyEvent};
-use kanata_parser::cfg::DeviceDetectMode;
-use kanata_parser::cfg::UnicodeTermination;
+use kanata_parser::cfg::{find_input_device_id, DeviceDetectMode, InputDeviceCfg};
+use kanata_parser::cfg::{find_linux_dev_rule, LinuxDevProps, LinuxDevRule, LinuxDevRuleAction};
+use kanata_parser::cfg::{LinuxOutputDevice, LinuxOutputDevices, UnicodeTermination};
 use kanata_parser::custom_action::*;
 use kanata_parser::keys::*;
 
@@ -39,7 +46,15 @@ pub struct KbdIn {
     _inotify: Inotify,
     include_names: Option<Vec<String>>,
     exclude_names: Option<Vec<String>>,
+    /// Rules from `linux-dev-rules`, which take priority over the names and detect mode.
+    dev_rules: Vec<LinuxDevRule>,
     device_detect_mode: DeviceDetectMode,
+    /// Devices defined in `defdevice`.
+    input_devices: Arc<Vec<InputDeviceCfg>>,
+    /// The `defdevice` identifier of each registered device.
+    device_ids: HashMap<Token, u8>,
+    /// Receives the devices defined in `defdevice` after a live reload.
+    input_devices_rx: Option<std::sync::mpsc::Receiver<Arc<Vec<InputDeviceCfg>>>>,
 }
 
 const INOTIFY_TOKEN_VALUE: usize = 0;
@@ -47,13 +62,206 @@ const INOTIFY_TOKEN: Token = Token(INOTIFY_TOKEN_VALUE);
 
 pub static WAIT_DEVICE_MS: AtomicU64 = AtomicU64::new(200);
 
+/// Names of output devices created by kanata besides the `kanata` device. Input is never read
+/// from these, since kanata would then process its own output.
+static OUTPUT_DEVICE_NAMES: parking_lot::Mutex<Vec<String>> = parking_lot::Mutex::new(Vec::new());
+
+fn is_kanata_output_device(name: &str) -> bool {
+    name == "kanata" || OUTPUT_DEVICE_NAMES.lock().iter().any(|n| n == name)
+}
+
+/// Input devices with LEDs, which are opened a second time so that LEDs can be set from outside
+/// of the thread reading from `KbdIn`.
+static LED_DEVICES: parking_lot::Mutex<LedDevices> = parking_lot::Mutex::new(LedDevices {
+    overrides: [None; 3],
+    devices: Vec::new(),
+});
+
+struct LedDevices {
+    overrides: LedOverrides,
+    devices: Vec<LedDevice>,
+}
+
+struct LedDevice {
+    path: String,
+    device: Device,
+    /// The lock states of the LEDs that kanata overrides, which are shown again once kanata
+    /// stops overriding them.
+    saved: LedOverrides,
+}
+
+impl LedDevice {
+    fn apply(&mut self, overrides: &LedOverrides) -> Result<(), io::Error> {
+        let current = self.device.get_led_state()?;
+        let mut events = vec![];
+        for led in Led::ALL {
+            let i = led.index();
+            let led_type = evdev::LedType(i as u16);
+            let on = match (overrides[i], self.saved[i]) {
+                (Some(on), saved) => {
+                    if saved.is_none() {
+                        self.saved[i] = Some(current.contains(led_type));
+                    }
+                    on
+                }
+                (None, Some(saved)) => {
+                    self.saved[i] = None;
+                    saved
+                }
+                (None, None) => continue,
+            };
+            events.push(InputEvent::new(EventType::LED, led_type.0, i32::from(on)));
+        }
+        if !events.is_empty() {
+            self.device.send_events(&events)?;
+        }
+        Ok(())
+    }
+}
+
+impl LedDevices {
+    fn apply_all(&mut self) {
+        let overrides = self.overrides;
+        for dev in self.devices.iter_mut() {
+            if let Err(e) = dev.apply(&overrides) {
+                log::warn!("failed to set LEDs of {}: {e}", dev.path);
+            }
+        }
+    }
+
+    /// The OS sets the LEDs of a keyboard when a lock key toggles, which undoes kanata's
+    /// overrides, so set the LEDs of a device again when it reports an LED change. An LED
+    /// that changed away from its override shows the new lock state.
+    fn handle_led_events(&mut self, led_events: &[(String, InputEvent)]) {
+        if self.overrides.iter().all(Option::is_none) {
+            return;
+        }
+        let overrides = self.overrides;
+        for dev in self.devices.iter_mut() {
+            let mut changed = false;
+            for (_, ev) in led_events.iter().filter(|(path, _)| *path == dev.path) {
+                changed = true;
+                let i = usize::from(ev.code());
+                let on = ev.value() != 0;
+                if i < dev.saved.len() && overrides[i].is_some_and(|o| o != on) {
+                    dev.saved[i] = Some(on);
+                }
+            }
+            if !changed {
+                continue;
+            }
+            if let Err(e) = dev.apply(&overrides) {
+                log::warn!("failed to set LEDs of {}: {e}", dev.path);
+            }
+        }
+    }
+
+    /// A lock key sent by kanata toggles the lock state of the LEDs that kanata overrides.
+    fn toggle_saved(&mut self, led: Led) {
+        for dev in self.devices.iter_mut() {
+            if let Some(on) = &mut dev.saved[led.index()] {
+                *on = !*on;
+            }
+        }
+    }
+}
+
+/// Keep track of the lock states of overridden LEDs from the lock keys that kanata sends.
+fn track_lock_keys(events: &[InputEvent]) {
+    for ev in events {
+        if ev.event_type() != EventType::KEY || ev.value() != KeyValue::Press as i32 {
+            continue;
+        }
+        let led = match OsCode::from_u16(ev.code()) {
+            Some(OsCode::KEY_CAPSLOCK) => Led::Caps,
+            Some(OsCode::KEY_NUMLOCK) => Led::Num,
+            Some(OsCode::KEY_SCROLLLOCK) => Led::Scroll,
+            _ => continue,
+        };
+        LED_DEVICES.lock().toggle_saved(led);
+    }
+}
+
+fn register_led_device(dev: &Device, path: &str) {
+    if dev
+        .supported_leds()
+        .is_none_or(|leds| leds.iter().next().is_none())
+    {
+        return;
+    }
+    // The device handle used for reading is owned by `KbdIn`, so open another one for the LEDs.
+    let device = match Device::open(path) {
+        Ok(device) => device,
+        Err(e) => {
+            log::warn!("failed to open {path} for setting LEDs: {e}");
+            return;
+        }
+    };
+    let mut leds = LED_DEVICES.lock();
+    let overrides = leds.overrides;
+    let mut led_device = LedDevice {
+        path: path.to_owned(),
+        device,
+        saved: [None; 3],
+    };
+    if let Err(e) = led_device.apply(&overrides) {
+        log::warn!("failed to set LEDs of {path}: {e}");
+    }
+    leds.devices.push(led_device);
+}
+
+fn unregister_led_device(path: &str) {
+    LED_DEVICES.lock().devices.retain(|dev| dev.path != path);
+}
+
+/// Give control of the LEDs back to their lock keys, showing the current lock states.
+fn restore_leds() {
+    // Use a timeout in case a panic happened while the lock was held.
+    let Some(mut leds) = LED_DEVICES.try_lock_for(std::time::Duration::from_millis(500)) else {
+        log::warn!("failed to restore LEDs: lock is held");
+        return;
+    };
+    leds.overrides = [None; 3];
+    leds.apply_all();
+}
+
+/// Gives control of the LEDs back to their lock keys when dropped or when any thread panics,
+/// e.g. when kanata exits with an error or the processing loop panics. Exits by signal are
+/// handled in the signal handler instead, since destructors do not run in that case.
+pub struct LedRestoreGuard(());
+
+impl LedRestoreGuard {
+    pub fn new() -> Self {
+        let default_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_leds();
+            default_hook(info);
+        }));
+        Self(())
+    }
+}
+
+impl Default for LedRestoreGuard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for LedRestoreGuard {
+    fn drop(&mut self) {
+        restore_leds();
+    }
+}
+
 impl KbdIn {
     pub fn new(
         dev_paths: &[String],
         continue_if_no_devices: bool,
         include_names: Option<Vec<String>>,
         exclude_names: Option<Vec<String>>,
+        dev_rules: Vec<LinuxDevRule>,
         device_detect_mode: DeviceDetectMode,
+        input_devices: Arc<Vec<InputDeviceCfg>>,
     ) -> Result<Self, io::Error> {
         let poll = Poll::new()?;
 
@@ -68,6 +276,7 @@ impl KbdIn {
             discover_devices(
                 include_names.as_deref(),
                 exclude_names.as_deref(),
+                &dev_rules,
                 device_detect_mode,
             )
         };
@@ -100,7 +309,11 @@ impl KbdIn {
             token_counter: INOTIFY_TOKEN_VALUE + 1,
             include_names,
             exclude_names,
+            dev_rules,
             device_detect_mode,
+            input_devices,
+            device_ids: HashMap::default(),
+            input_devices_rx: None,
         };
 
         for (device, dev_path) in devices.into_iter() {
@@ -130,11 +343,41 @@ impl KbdIn {
         self.poll
             .registry()
             .register(&mut SourceFd(&fd), tok, Interest::READABLE)?;
+        let device_id = input_device_id(&self.input_devices, &dev, &path);
+        if device_id > 0 {
+            log::info!(
+                "{path} matches defdevice {}",
+                self.input_devices[usize::from(device_id) - 1].name
+            );
+        }
+        self.device_ids.insert(tok, device_id);
+        register_led_device(&dev, &path);
         self.devices.insert(tok, (dev, path));
         Ok(())
     }
 
-    pub fn read(&mut self) -> Result<Vec<InputEvent>, io::Error> {
+    /// Returns a sender for the devices defined in `defdevice` after a live reload. They are
+    /// applied in `read` before fetching events, so that no events after the reload are
+    /// identified with the old devices.
+    pub fn input_devices_sender(&mut self) -> std::sync::mpsc::Sender<Arc<Vec<InputDeviceCfg>>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.input_devices_rx = Some(rx);
+        tx
+    }
+
+    /// Update the devices defined in `defdevice` after a live reload and re-identify the
+    /// registered devices.
+    fn set_input_devices(&mut self, input_devices: Arc<Vec<InputDeviceCfg>>) {
+        self.input_devices = input_devices;
+        for (tok, (dev, path)) in self.devices.iter() {
+            self.device_ids
+                .insert(*tok, input_device_id(&self.input_devices, dev, path));
+        }
+    }
+
+    /// Read input events along with the `defdevice` identifier of the device that each event
+    /// came from.
+    pub fn read(&mut self) -> Result<Vec<(InputEvent, u8)>, io::Error> {
         let mut input_events = vec![];
         loop {
             log::trace!("polling");
@@ -143,16 +386,28 @@ impl KbdIn {
                 log::error!("failed poll: {:?}", e);
                 return Ok(vec![]);
             }
+            let reloaded_input_devices = self
+                .input_devices_rx
+                .as_ref()
+                .and_then(|rx| rx.try_iter().last());
+            if let Some(input_devices) = reloaded_input_devices {
+                self.set_input_devices(input_devices);
+            }
 
             const EVENT_LIMIT: usize = 48;
 
             let mut do_rediscover = false;
+            let mut led_events = vec![];
             for event in &self.events {
-                if let Some((device, _)) = self.devices.get_mut(&event.token()) {
+                if let Some((device, path)) = self.devices.get_mut(&event.token()) {
+                    let device_id = self.device_ids.get(&event.token()).copied().unwrap_or(0);
                     if let Err(e) = device.fetch_events().map(|evs| {
-                        evs.into_iter()
-                            .take(EVENT_LIMIT)
-                            .for_each(|ev| input_events.push(ev))
+                        evs.into_iter().take(EVENT_LIMIT).for_each(|ev| {
+                            if ev.event_type() == EventType::LED {
+                                led_events.push((path.clone(), ev));
+                            }
+                            input_events.push((ev, device_id))
+                        })
                     }) {
                         // Currently the kind() is uncategorized... not helpful, need to match
                         // on os error. code 19 is ENODEV, "no such device".
@@ -161,8 +416,10 @@ impl KbdIn {
                                 self.poll
                                     .registry()
                                     .deregister(&mut SourceFd(&device.as_raw_fd()))?;
+                                self.device_ids.remove(&event.token());
                                 if let Some((_, path)) = self.devices.remove(&event.token()) {
                                     log::warn!("removing kbd device: {path}");
+                                    unregister_led_device(&path);
                                     if let Some(ref mut missing) = self.missing_device_paths {
                                         missing.push(path);
                                     }
@@ -180,6 +437,9 @@ impl KbdIn {
                     panic!("encountered unexpected epoll event {event:?}");
                 }
             }
+            if !led_events.is_empty() {
+                LED_DEVICES.lock().handle_led_events(&led_events);
+            }
             if do_rediscover {
                 log::info!("watch found file changes, looking for new devices");
                 self.rediscover_devices()?;
@@ -230,6 +490,7 @@ impl KbdIn {
             discover_devices(
                 self.include_names.as_deref(),
                 self.exclude_names.as_deref(),
+                &self.dev_rules,
                 self.device_detect_mode,
             )
             .into_iter()
@@ -249,6 +510,17 @@ impl KbdIn {
     }
 }
 
+fn input_device_id(input_devices: &[InputDeviceCfg], dev: &Device, path: &str) -> u8 {
+    let input_id = dev.input_id();
+    find_input_device_id(
+        input_devices,
+        dev.name().unwrap_or(""),
+        path,
+        input_id.vendor(),
+        input_id.product(),
+    )
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 enum DeviceType {
     Keyboard,
@@ -258,7 +530,7 @@ enum DeviceType {
 }
 
 pub fn is_input_device(device: &Device, detect_mode: DeviceDetectMode) -> bool {
-    if device.name() == Some("kanata") {
+    if is_kanata_output_device(device.name().unwrap_or("")) {
         return false;
     }
     let is_keyboard = device.supported_keys().is_some_and(has_keyboard_keys);
@@ -321,10 +593,10 @@ impl TryFrom<InputEvent> for KeyEvent {
     fn try_from(item: InputEvent) -> Result<Self, Self::Error> {
         use OsCode::*;
         match item.kind() {
-            evdev::InputEventKind::Key(k) => Ok(Self {
-                code: OsCode::from_u16(k.0).ok_or(())?,
-                value: KeyValue::from(item.value()),
-            }),
+            evdev::InputEventKind::Key(k) => Ok(KeyEvent::new(
+                OsCode::from_u16(k.0).ok_or(())?,
+                KeyValue::from(item.value()),
+            )),
             evdev::InputEventKind::RelAxis(axis_type) => {
                 let dist = item.value();
                 let code: OsCode = match axis_type {
@@ -342,12 +614,23 @@ impl TryFrom<InputEvent> for KeyEvent {
                             MouseWheelLeft
                         }
                     }
+                    RelativeAxisType::REL_X => {
+                        if dist > 0 {
+                            MouseMoveRight
+                        } else {
+                            MouseMoveLeft
+                        }
+                    }
+                    RelativeAxisType::REL_Y => {
+                        if dist > 0 {
+                            MouseMoveDown
+                        } else {
+                            MouseMoveUp
+                        }
+                    }
                     _ => return Err(()),
                 };
-                Ok(KeyEvent {
-                    code,
-                    value: KeyValue::Tap,
-                })
+                Ok(KeyEvent::new(code, KeyValue::Tap))
             }
             _ => Err(()),
         }
@@ -365,9 +648,15 @@ use std::cell::Cell;
 #[cfg(all(not(feature = "simulated_output"), not(feature = "passthru_ahk")))]
 pub struct KbdOut {
     device: uinput::VirtualDevice,
+    /// Device for mouse buttons, scrolling and mouse movement when `linux-output-devices` splits
+    /// them from `device`. Otherwise everything is sent from `device`.
+    pointer_device: Option<uinput::VirtualDevice>,
+    /// Absolute pointer device for `setmouse`, with the screen width and height.
+    abs_device: Option<(uinput::VirtualDevice, u16, u16)>,
     accumulated_scroll: u16,
     accumulated_hscroll: u16,
     raw_buf: Vec<InputEvent>,
+    pointer_raw_buf: Vec<InputEvent>,
     pub unicode_termination: Cell<UnicodeTermination>,
     pub unicode_u_code: Cell<OsCode>,
 }
@@ -378,6 +667,8 @@ impl KbdOut {
         symlink_path: &Option<String>,
         trackpoint: bool,
         bus_type: BusType,
+        setmouse_screen_size: Option<(u16, u16)>,
+        output_devices: Option<&LinuxOutputDevices>,
     ) -> Result<Self, io::Error> {
         // Support pretty much every feature of a Keyboard or a Mouse in a VirtualDevice so that no event from the original input devices gets lost
         // TODO investigate the rare possibility that a device is e.g. a Joystick and a Keyboard or a Mouse at the same time, which could lead to lost events
@@ -399,19 +690,50 @@ impl KbdOut {
             RelativeAxisType::REL_HWHEEL_HI_RES,
         ]);
 
-        let device = uinput::VirtualDeviceBuilder::new()?
-            .name("kanata")
-            // libinput's "disable while typing" feature don't work when bus_type
-            // is set to BUS_USB, but appears to work when it's set to BUS_I8042.
-            .input_id(evdev::InputId::new(bus_type, 1, 1, 1))
-            .with_keys(&keys)?
-            .with_relative_axes(&relative_axes)?;
-        let device = if trackpoint {
-            device.with_properties(&evdev::AttributeSet::from_iter([PropType::POINTING_STICK]))?
-        } else {
-            device
+        let (mut device, pointer_device) = match output_devices {
+            None => {
+                let device = uinput::VirtualDeviceBuilder::new()?
+                    .name("kanata")
+                    // libinput's "disable while typing" feature don't work when bus_type
+                    // is set to BUS_USB, but appears to work when it's set to BUS_I8042.
+                    .input_id(evdev::InputId::new(bus_type, 1, 1, 1))
+                    .with_keys(&keys)?
+                    .with_relative_axes(&relative_axes)?;
+                let device = if trackpoint {
+                    device.with_properties(&evdev::AttributeSet::from_iter([
+                        PropType::POINTING_STICK,
+                    ]))?
+                } else {
+                    device
+                };
+                (device.build()?, None)
+            }
+            Some(LinuxOutputDevices { keyboard, pointer }) => {
+                let is_pointer_key = |key: &evdev::Key| {
+                    OsCode::from_u16(key.code()).map(output_device_class)
+                        == Some(OutputDeviceClass::Pointer)
+                };
+                let keyboard_keys = keys.iter().filter(|key| !is_pointer_key(key));
+                let pointer_keys = keys.iter().filter(is_pointer_key);
+                let keyboard_device = output_device_builder(keyboard, bus_type)?
+                    .with_keys(&evdev::AttributeSet::from_iter(keyboard_keys))?;
+                let pointer_device = output_device_builder(pointer, bus_type)?
+                    .with_keys(&evdev::AttributeSet::from_iter(pointer_keys))?
+                    .with_relative_axes(&relative_axes)?;
+                let pointer_device = if trackpoint {
+                    pointer_device.with_properties(&evdev::AttributeSet::from_iter([
+                        PropType::POINTING_STICK,
+                    ]))?
+                } else {
+                    pointer_device
+                };
+                let mut pointer_device = pointer_device.build()?;
+                if let Some(devnode) = pointer_device.enumerate_dev_nodes_blocking()?.next() {
+                    log::info!("Created pointer device {:#?}", devnode?);
+                }
+                (keyboard_device.build()?, Some(pointer_device))
+            }
         };
-        let mut device = device.build()?;
         let devnode = device
             .enumerate_dev_nodes_blocking()?
             .next() // Expect only one. Using fold or calling next again blocks indefinitely
@@ -426,11 +748,23 @@ impl KbdOut {
         };
         handle_signals(symlink);
 
+        let abs_device = match setmouse_screen_size {
+            Some((width, height)) => Some((
+                build_abs_pointer_device(bus_type, width, height)?,
+                width,
+                height,
+            )),
+            None => None,
+        };
+
         Ok(KbdOut {
             device,
+            pointer_device,
+            abs_device,
             accumulated_scroll: 0,
             accumulated_hscroll: 0,
             raw_buf: vec![],
+            pointer_raw_buf: vec![],
 
             // historically was the only option, so make Enter the default
             unicode_termination: Cell::new(UnicodeTermination::Enter),
@@ -460,29 +794,57 @@ impl KbdOut {
             //     this correctly.
             //
             // With this knowledge, seems fine to not bother checking.
-            self.device.emit(&self.raw_buf)?;
-            self.raw_buf.clear();
+            if self.pointer_device.is_none() || !self.raw_buf.is_empty() {
+                self.device.emit(&self.raw_buf)?;
+                self.raw_buf.clear();
+            }
+            if let Some(pointer_device) = self.pointer_device.as_mut() {
+                if !self.pointer_raw_buf.is_empty() {
+                    pointer_device.emit(&self.pointer_raw_buf)?;
+                    self.pointer_raw_buf.clear();
+                }
+            }
+        } else if self.pointer_device.is_some()
+            && event_output_device_class(&event) == OutputDeviceClass::Pointer
+        {
+            self.pointer_raw_buf.push(event);
         } else {
+            track_lock_keys(&[event]);
             self.raw_buf.push(event);
         }
         Ok(())
     }
 
     pub fn write(&mut self, event: InputEvent) -> Result<(), io::Error> {
-        if !self.raw_buf.is_empty() {
-            self.device.emit(&self.raw_buf)?;
-            self.raw_buf.clear();
-        }
-        self.device.emit(&[event])?;
-        Ok(())
+        self.write_many(&[event])
     }
 
+    /// Writes events, each one to the output device of its class.
     pub fn write_many(&mut self, events: &[InputEvent]) -> Result<(), io::Error> {
         if !self.raw_buf.is_empty() {
             self.device.emit(&self.raw_buf)?;
             self.raw_buf.clear();
         }
-        self.device.emit(events)?;
+        if let Some(pointer_device) = self.pointer_device.as_mut() {
+            if !self.pointer_raw_buf.is_empty() {
+                pointer_device.emit(&self.pointer_raw_buf)?;
+                self.pointer_raw_buf.clear();
+            }
+        }
+        track_lock_keys(events);
+        let Some(pointer_device) = self.pointer_device.as_mut() else {
+            self.device.emit(events)?;
+            return Ok(());
+        };
+        let (pointer_events, keyboard_events): (Vec<_>, Vec<_>) = events
+            .iter()
+            .partition(|ev| event_output_device_class(ev) == OutputDeviceClass::Pointer);
+        if !keyboard_events.is_empty() {
+            self.device.emit(&keyboard_events)?;
+        }
+        if !pointer_events.is_empty() {
+            pointer_device.emit(&pointer_events)?;
+        }
         Ok(())
     }
 
@@ -490,16 +852,27 @@ impl KbdOut {
         let key_ev = KeyEvent::new(key, value);
         let input_ev = key_ev.into();
         log::debug!("send to uinput: {:?}", input_ev);
-        self.device.emit(&[input_ev])?;
+        track_lock_keys(&[input_ev]);
+        self.device_for(output_device_class(key))
+            .emit(&[input_ev])?;
         Ok(())
     }
 
     pub fn write_code(&mut self, code: u32, value: KeyValue) -> Result<(), io::Error> {
         let event = InputEvent::new(EventType::KEY, code as u16, value as i32);
-        self.device.emit(&[event])?;
+        track_lock_keys(&[event]);
+        self.device_for(event_output_device_class(&event))
+            .emit(&[event])?;
         Ok(())
     }
 
+    fn device_for(&mut self, class: OutputDeviceClass) -> &mut uinput::VirtualDevice {
+        match (class, self.pointer_device.as_mut()) {
+            (OutputDeviceClass::Pointer, Some(pointer_device)) => pointer_device,
+            _ => &mut self.device,
+        }
+    }
+
     pub fn press_key(&mut self, key: OsCode) -> Result<(), io::Error> {
         self.write_key(key, KeyValue::Press)
     }
@@ -646,10 +1019,90 @@ impl KbdOut {
         self.write_many(&events)
     }
 
-    pub fn set_mouse(&mut self, _x: u16, _y: u16) -> Result<(), io::Error> {
-        log::warn!("setmouse does not work in Linux yet. Maybe try out warpd:\n\thttps://github.com/rvaiya/warpd");
+    /// Sets the LEDs of the input devices. LEDs that are `None` are given back to their lock keys
+    /// and show the current lock states.
+    pub fn set_leds(&mut self, overrides: LedOverrides) -> Result<(), io::Error> {
+        let mut leds = LED_DEVICES.lock();
+        leds.overrides = overrides;
+        leds.apply_all();
         Ok(())
     }
+
+    /// Moves the mouse to the position, where 0,0 is the upper-left corner of the screen and
+    /// 65535,65535 is the lower-right corner.
+    pub fn set_mouse(&mut self, x: u16, y: u16) -> Result<(), io::Error> {
+        let Some((abs_device, width, height)) = self.abs_device.as_mut() else {
+            log::warn!("setmouse requires linux-setmouse-screen-size to be set in defcfg");
+            return Ok(());
+        };
+        let x = scale_abs_position(x, *width);
+        let y = scale_abs_position(y, *height);
+        log::debug!("setting mouse {x} {y}");
+        abs_device.emit(&[
+            InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_X.0, x),
+            InputEvent::new(EventType::ABSOLUTE, AbsoluteAxisType::ABS_Y.0, y),
+        ])
+    }
+}
+
+#[cfg(all(not(feature = "simulated_output"), not(feature = "passthru_ahk")))]
+fn output_device_builder<'a>(
+    cfg: &'a LinuxOutputDevice,
+    bus_type: BusType,
+) -> Result<uinput::VirtualDeviceBuilder<'a>, io::Error> {
+    OUTPUT_DEVICE_NAMES.lock().push(cfg.name.clone());
+    Ok(uinput::VirtualDeviceBuilder::new()?
+        .name(&cfg.name)
+        .input_id(evdev::InputId::new(bus_type, cfg.vendor, cfg.product, 1)))
+}
+
+/// Relative axis events, e.g. mouse movement and scrolling, are sent from the pointer device.
+/// Other events are classified by their key code.
+#[cfg(all(not(feature = "simulated_output"), not(feature = "passthru_ahk")))]
+fn event_output_device_class(event: &InputEvent) -> OutputDeviceClass {
+    match event.event_type() {
+        EventType::RELATIVE => OutputDeviceClass::Pointer,
+        EventType::KEY => OsCode::from_u16(event.code())
+            .map(output_device_class)
+            .unwrap_or(OutputDeviceClass::Keyboard),
+        _ => OutputDeviceClass::Keyboard,
+    }
+}
+
+/// Creates the device that `setmouse` writes absolute positions to. The axes span the screen
+/// size so that a position maps to a pixel. This is separate from the main output device so that
+/// the relative mouse movements of the main device keep working as before.
+#[cfg(all(not(feature = "simulated_output"), not(feature = "passthru_ahk")))]
+fn build_abs_pointer_device(
+    bus_type: BusType,
+    width: u16,
+    height: u16,
+) -> Result<uinput::VirtualDevice, io::Error> {
+    let abs_axis = |axis, size: u16| {
+        UinputAbsSetup::new(axis, AbsInfo::new(0, 0, i32::from(size) - 1, 0, 0, 0))
+    };
+    // libinput only treats the device as a pointer if it has a button.
+    let buttons = evdev::AttributeSet::from_iter([Key::BTN_LEFT, Key::BTN_RIGHT, Key::BTN_MIDDLE]);
+    OUTPUT_DEVICE_NAMES
+        .lock()
+        .push("kanata setmouse".to_owned());
+    let mut device = uinput::VirtualDeviceBuilder::new()?
+        .name("kanata setmouse")
+        .input_id(evdev::InputId::new(bus_type, 1, 2, 1))
+        .with_keys(&buttons)?
+        .with_absolute_axis(&abs_axis(AbsoluteAxisType::ABS_X, width))?
+        .with_absolute_axis(&abs_axis(AbsoluteAxisType::ABS_Y, height))?
+        .build()?;
+    if let Some(devnode) = device.enumerate_dev_nodes_blocking()?.next() {
+        log::info!("Created setmouse device {:#?}", devnode?);
+    }
+    Ok(device)
+}
+
+/// Scales a `setmouse` position in the range 0-65535 to the range of an axis spanning `size`
+/// pixels.
+fn scale_abs_position(pos: u16, size: u16) -> i32 {
+    (u32::from(pos) * (u32::from(size) - 1) / u32::from(u16::MAX)) as i32
 }
 
 fn devices_from_input_paths(
@@ -673,6 +1126,7 @@ fn devices_from_input_paths(
 fn discover_devices(
     include_names: Option<&[String]>,
     exclude_names: Option<&[String]>,
+    dev_rules: &[LinuxDevRule],
     device_detect_mode: DeviceDetectMode,
 ) -> Vec<(Device, String)> {
     log::info!("looking for devices in /dev/input");
@@ -686,36 +1140,72 @@ fn discover_devices(
             )
         })
         .filter(|pd| {
-            let is_input = is_input_device(&pd.0, device_detect_mode);
-            (match include_names {
-                None => is_input,
-                Some(include_names) => {
-                    let name = pd.0.name().unwrap_or("");
-                    if include_names.iter().any(|include| name == include) {
-                        log::info!("device [{}:{name}] is included", &pd.1);
-                        true
-                    } else {
-                        log::info!("device [{}:{name}] is ignored", &pd.1);
-                        false
-                    }
-                }
-            }) && (match exclude_names {
-                None => true,
-                Some(exclude_names) => {
-                    let name = pd.0.name().unwrap_or("");
-                    if exclude_names.iter().any(|exclude| name == exclude) {
-                        log::info!("device [{}:{name}] is excluded", &pd.1);
-                        false
-                    } else {
-                        true
-                    }
-                }
-            })
+            use_discovered_device(
+                &pd.0,
+                &pd.1,
+                include_names,
+                exclude_names,
+                dev_rules,
+                device_detect_mode,
+            )
         })
         .collect();
     devices
 }
 
+/// Decides whether an autodetected device is used for input, both at startup and when devices
+/// are plugged in. The first matching rule of `linux-dev-rules` decides, otherwise the device
+/// names and the detect mode do.
+fn use_discovered_device(
+    device: &Device,
+    path: &str,
+    include_names: Option<&[String]>,
+    exclude_names: Option<&[String]>,
+    dev_rules: &[LinuxDevRule],
+    device_detect_mode: DeviceDetectMode,
+) -> bool {
+    let name = device.name().unwrap_or("");
+    // Never read kanata's own output, whatever the rules say.
+    if is_kanata_output_device(name) {
+        return false;
+    }
+    let input_id = device.input_id();
+    let props = LinuxDevProps {
+        name,
+        vendor: input_id.vendor(),
+        product: input_id.product(),
+        bus: input_id.bus_type().0,
+        phys: device.physical_path().unwrap_or(""),
+        uniq: device.unique_name().unwrap_or(""),
+    };
+    if let Some(rule) = find_linux_dev_rule(dev_rules, &props) {
+        let is_included = rule.action == LinuxDevRuleAction::Include;
+        log::info!(
+            "device [{path}:{name}] is {} by linux-dev-rules; {props:?}",
+            if is_included { "included" } else { "excluded" },
+        );
+        return is_included;
+    }
+    let is_input = is_input_device(device, device_detect_mode);
+    let is_included = match include_names {
+        None => is_input,
+        Some(include_names) => {
+            if include_names.iter().any(|include| name == include) {
+                log::info!("device [{path}:{name}] is included");
+                true
+            } else {
+                log::info!("device [{path}:{name}] is ignored");
+                false
+            }
+        }
+    };
+    if is_included && exclude_names.is_some_and(|names| names.iter().any(|n| name == n)) {
+        log::info!("device [{path}:{name}] is excluded");
+        return false;
+    }
+    is_included
+}
+
 fn watch_devinput() -> Result<Inotify, io::Error> {
     let inotify = Inotify::init().expect("Failed to initialize inotify");
     inotify.watches().add("/dev/input", WatchMask::CREATE)?;
@@ -754,12 +1244,18 @@ fn handle_signals(symlink: Option<Symlink>) {
         if let Some(signal) = (&mut signals).into_iter().next() {
             match signal {
                 SIGINT | SIGTERM => {
+                    restore_leds();
+                    crate::kanata::flush_statistics_on_exit();
+                    crate::kanata::flush_macro_file();
                     drop(symlink);
                     signal_hook::low_level::emulate_default_handler(signal)
                         .expect("run original sighandlers");
                     unreachable!();
                 }
                 SIGTSTP => {
+                    restore_leds();
+                    crate::kanata::flush_statistics_on_exit();
+                    crate::kanata::flush_macro_file();
                     drop(symlink);
                     log::warn!("got SIGTSTP, exiting instead of pausing so keyboards don't hang");
                     std::process::exit(SIGTSTP);
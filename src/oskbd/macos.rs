@@ -191,14 +191,14 @@ impl TryFrom<InputEvent> for KeyEvent {
             page: item.page,
             code: item.code,
         }) {
-            Ok(KeyEvent {
-                code: oscode,
-                value: if item.value == 1 {
+            Ok(KeyEvent::new(
+                oscode,
+                if item.value == 1 {
                     KeyValue::Press
                 } else {
                     KeyValue::Release
                 },
-            })
+            ))
         } else {
             Err(())
         }
@@ -242,7 +242,7 @@ impl KbdOut {
     }
 
     pub fn write_key(&mut self, key: OsCode, value: KeyValue) -> Result<(), io::Error> {
-        if let Ok(event) = InputEvent::try_from(KeyEvent { value, code: key }) {
+        if let Ok(event) = InputEvent::try_from(KeyEvent::new(key, value)) {
             self.write(event)
         } else {
             log::debug!("couldn't write unrecognized {key:?}");
@@ -254,10 +254,9 @@ impl KbdOut {
     }
 
     pub fn write_code(&mut self, code: u32, value: KeyValue) -> Result<(), io::Error> {
-        if let Ok(event) = InputEvent::try_from(KeyEvent {
-            value,
-            code: OsCode::from_u16(code as u16).unwrap(),
-        }) {
+        if let Ok(event) =
+            InputEvent::try_from(KeyEvent::new(OsCode::from_u16(code as u16).unwrap(), value))
+        {
             self.write(event)
         } else {
             log::debug!("couldn't write unrecognized OsCode {code}");
@@ -424,6 +423,11 @@ impl KbdOut {
         Ok(())
     }
 
+    /// Keyboard LEDs are only supported on Linux.
+    pub fn set_leds(&mut self, _leds: LedOverrides) -> Result<(), io::Error> {
+        Ok(())
+    }
+
     fn make_event_source() -> Result<CGEventSource, Error> {
         CGEventSource::new(CGEventSourceStateID::CombinedSessionState).map_err(|_| {
             Error::new(
@@ -105,12 +105,27 @@ use kanata_parser::keys::OsCode;
 pub struct KeyEvent {
     pub code: OsCode,
     pub value: KeyValue,
+    /// Identifies the `defdevice` that the event came from. The value is the 1-based position
+    /// of the matching `defdevice` within the configuration. A value of 0 means that the event
+    /// did not come from a device matched by any `defdevice`, or that the platform does not
+    /// support identifying devices.
+    pub device: u8,
 }
 
 #[allow(dead_code, unused)]
 impl KeyEvent {
     pub fn new(code: OsCode, value: KeyValue) -> Self {
-        Self { code, value }
+        Self {
+            code,
+            value,
+            device: 0,
+        }
+    }
+
+    /// Set the device that this event originated from.
+    pub fn with_device(mut self, device: u8) -> Self {
+        self.device = device;
+        self
     }
 }
 
@@ -138,6 +153,7 @@ impl fmt::Debug for KeyEvent {
                 &format_args!("{:?} ({})", self.code, self.code.as_u16()),
             )
             .field("value", &self.value)
+            .field("device", &self.device)
             .finish()
     }
 }
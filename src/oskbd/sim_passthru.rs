@@ -38,6 +38,8 @@ impl KbdOut {
         _s: &Option<String>,
         _tp: bool,
         _bustype: evdev::BusType,
+        _setmouse_screen_size: Option<(u16, u16)>,
+        _output_devices: Option<&kanata_parser::cfg::LinuxOutputDevices>,
     ) -> Result<Self, io::Error> {
         Ok(Self { tx_kout: None })
     }
@@ -125,7 +127,12 @@ impl KbdOut {
         log::info!("out🖰:@{x},{y}");
         Ok(())
     }
+    pub fn set_leds(&mut self, leds: LedOverrides) -> Result<(), io::Error> {
+        log::info!("led:{leds:?}");
+        Ok(())
+    }
     pub fn tick(&mut self) {}
+    pub fn hold_tap_decision(&mut self, _decision: &str) {}
 }
 
 #[cfg(not(any(target_os = "windows", target_os = "macos")))]
@@ -160,13 +167,13 @@ impl InputEvent {
 impl TryFrom<InputEvent> for KeyEvent {
     type Error = ();
     fn try_from(item: InputEvent) -> Result<Self, Self::Error> {
-        Ok(Self {
-            code: OsCode::from_u16(item.code as u16).ok_or(())?,
-            value: match item.up {
+        Ok(KeyEvent::new(
+            OsCode::from_u16(item.code as u16).ok_or(())?,
+            match item.up {
                 true => KeyValue::Release,
                 false => KeyValue::Press,
             },
-        })
+        ))
     }
 }
 
@@ -357,6 +357,8 @@ impl KbdOut {
         _s: &Option<String>,
         _tp: bool,
         _bustype: evdev::BusType,
+        _setmouse_screen_size: Option<(u16, u16)>,
+        _output_devices: Option<&kanata_parser::cfg::LinuxOutputDevices>,
     ) -> Result<Self, io::Error> {
         Self::new_actual()
     }
@@ -439,6 +441,27 @@ impl KbdOut {
         log::info!("out🖰:@{x},{y}");
         Ok(())
     }
+    pub fn set_leds(&mut self, leds: LedOverrides) -> Result<(), io::Error> {
+        let leds = Led::ALL
+            .iter()
+            .filter_map(|led| {
+                let on = leds[led.index()]?;
+                Some(format!("{led}:{}", if on { "on" } else { "off" }))
+            })
+            .collect::<Vec<_>>();
+        let leds = if leds.is_empty() {
+            "auto".to_string()
+        } else {
+            leds.join(",")
+        };
+        log::info!("led:{leds}");
+        self.outputs.push(format!("led:{leds}"));
+        Ok(())
+    }
+    /// Record a tap-hold decision in the outputs. Only used if `log-hold-tap-decisions` is on.
+    pub fn hold_tap_decision(&mut self, decision: &str) {
+        self.outputs.push(format!("ht:{decision}"));
+    }
     pub fn tick(&mut self) {
         self.outputs.ticks += 1;
         self.log.ticks += 1;
@@ -476,13 +499,13 @@ impl InputEvent {
 impl TryFrom<InputEvent> for KeyEvent {
     type Error = ();
     fn try_from(item: InputEvent) -> Result<Self, Self::Error> {
-        Ok(Self {
-            code: OsCode::from_u16(item.code as u16).ok_or(())?,
-            value: match item.up {
+        Ok(KeyEvent::new(
+            OsCode::from_u16(item.code as u16).ok_or(())?,
+            match item.up {
                 true => KeyValue::Release,
                 false => KeyValue::Press,
             },
-        })
+        ))
     }
 }
 
@@ -242,4 +242,9 @@ impl KbdOut {
         write_interception(InputEvent::from_mouse_set(x, y));
         Ok(())
     }
+
+    /// Keyboard LEDs are only supported on Linux.
+    pub fn set_leds(&mut self, _leds: LedOverrides) -> Result<(), io::Error> {
+        Ok(())
+    }
 }
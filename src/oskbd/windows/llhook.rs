@@ -130,13 +130,13 @@ impl InputEvent {
 impl TryFrom<InputEvent> for KeyEvent {
     type Error = ();
     fn try_from(item: InputEvent) -> Result<Self, Self::Error> {
-        Ok(Self {
-            code: OsCode::from_u16(item.code as u16).ok_or(())?,
-            value: match item.up {
+        Ok(KeyEvent::new(
+            OsCode::from_u16(item.code as u16).ok_or(())?,
+            match item.up {
                 true => KeyValue::Release,
                 false => KeyValue::Press,
             },
-        })
+        ))
     }
 }
 
@@ -315,6 +315,11 @@ impl KbdOut {
         set_mouse_xy(i32::from(x), i32::from(y));
         Ok(())
     }
+
+    /// Keyboard LEDs are only supported on Linux.
+    pub fn set_leds(&mut self, _leds: LedOverrides) -> Result<(), io::Error> {
+        Ok(())
+    }
 }
 
 fn send_btn(flag: u32) {
@@ -18,7 +18,90 @@ use std::io::Write;
 use std::net::{TcpListener, TcpStream};
 
 #[cfg(feature = "tcp_server")]
-pub type Connections = Arc<Mutex<HashMap<String, TcpStream>>>;
+use std::sync::atomic::{AtomicU32, Ordering};
+#[cfg(feature = "tcp_server")]
+use std::sync::OnceLock;
+
+#[cfg(feature = "tcp_server")]
+pub struct TcpClient {
+    pub stream: TcpStream,
+    /// Event topics that the client subscribed to.
+    pub topics: Vec<SubscriptionTopic>,
+}
+
+#[cfg(feature = "tcp_server")]
+pub type Connections = Arc<Mutex<HashMap<String, TcpClient>>>;
+
+/// Bitmask of the topics that at least one connected client is subscribed to. This allows
+/// skipping the creation of event messages that no client would receive.
+#[cfg(feature = "tcp_server")]
+static SUBSCRIBED_TOPICS: AtomicU32 = AtomicU32::new(0);
+
+#[cfg(feature = "tcp_server")]
+static SERVER_START: OnceLock<instant::Instant> = OnceLock::new();
+
+#[cfg(feature = "tcp_server")]
+fn topic_bit(topic: SubscriptionTopic) -> u32 {
+    1 << topic as u32
+}
+
+/// Returns true if any connected client is subscribed to the topic.
+#[cfg(feature = "tcp_server")]
+pub fn is_subscribed(topic: SubscriptionTopic) -> bool {
+    SUBSCRIBED_TOPICS.load(Ordering::Relaxed) & topic_bit(topic) != 0
+}
+
+/// Recompute the topics that any client is subscribed to. Must be called after clients are
+/// added, removed, or change their subscriptions.
+#[cfg(feature = "tcp_server")]
+pub fn refresh_subscribed_topics(clients: &HashMap<String, TcpClient>) {
+    let topics = clients
+        .values()
+        .flat_map(|client| client.topics.iter())
+        .fold(0, |bits, topic| bits | topic_bit(*topic));
+    SUBSCRIBED_TOPICS.store(topics, Ordering::Relaxed);
+}
+
+/// Monotonic timestamp in microseconds since the TCP server started, used in event messages.
+#[cfg(feature = "tcp_server")]
+pub fn timestamp_us() -> u64 {
+    SERVER_START
+        .get_or_init(instant::Instant::now)
+        .elapsed()
+        .as_micros() as u64
+}
+
+/// Write a notification to the clients that should receive it and remove the clients that can
+/// no longer be written to. Messages with a topic are only sent to clients subscribed to it.
+#[cfg(feature = "tcp_server")]
+pub(crate) fn relay_to_clients(msg: &ServerMessage, clients: &mut HashMap<String, TcpClient>) {
+    let notification = msg.as_bytes();
+    let topic = msg.topic();
+    let mut stale_clients = vec![];
+    for (id, client) in clients.iter_mut() {
+        if topic.is_some_and(|topic| !client.topics.contains(&topic)) {
+            continue;
+        }
+        match client.stream.write_all(&notification) {
+            Ok(_) => {
+                log::debug!("event notification sent");
+            }
+            Err(e) => {
+                log::warn!("removing tcp client where write failed: {id}, {e:?}");
+                // the client is no longer connected, let's remove them
+                stale_clients.push(id.clone());
+            }
+        }
+    }
+
+    for id in &stale_clients {
+        log::warn!("removing disconnected tcp client: {id}");
+        clients.remove(id);
+    }
+    if !stale_clients.is_empty() {
+        refresh_subscribed_topics(clients);
+    }
+}
 
 #[cfg(not(feature = "tcp_server"))]
 pub type Connections = ();
@@ -65,11 +148,10 @@ impl TcpServer {
 
     #[cfg(feature = "tcp_server")]
     pub fn start(&mut self, kanata: Arc<Mutex<Kanata>>) {
-        use kanata_parser::cfg::FAKE_KEY_ROW;
-
-        use crate::kanata::handle_fakekey_action;
-
         let listener = TcpListener::bind(self.address).expect("TCP server starts");
+        SERVER_START.get_or_init(instant::Instant::now);
+
+        Kanata::start_event_stream(self.connections.clone());
 
         let connections = self.connections.clone();
         let wakeup_channel = self.wakeup_channel.clone();
@@ -86,6 +168,9 @@ impl TcpServer {
                             if let Err(e) = stream.write(
                                 &ServerMessage::LayerChange {
                                     new: k.layer_info[k.layout.b().current_layer()].name.clone(),
+                                    metadata: k.layer_info[k.layout.b().current_layer()]
+                                        .metadata
+                                        .clone(),
                                 }
                                 .as_bytes(),
                             ) {
@@ -101,12 +186,15 @@ impl TcpServer {
 
                         connections.lock().insert(
                             addr.clone(),
-                            stream.try_clone().expect("stream is clonable"),
+                            TcpClient {
+                                stream: stream.try_clone().expect("stream is clonable"),
+                                topics: vec![],
+                            },
                         );
                         let reader = serde_json::Deserializer::from_reader(
                             stream.try_clone().expect("stream is clonable"),
                         )
-                        .into_iter::<ClientMessage>();
+                        .into_iter::<ClientRequest>();
 
                         log::info!("listening for incoming messages {addr}");
 
@@ -116,120 +204,31 @@ impl TcpServer {
                         std::thread::spawn(move || {
                             for v in reader {
                                 match v {
-                                    Ok(event) => {
-                                        match event {
-                                            ClientMessage::ChangeLayer { new } => {
-                                                kanata.lock().change_layer(new);
-                                            }
-                                            ClientMessage::RequestLayerNames {} => {
-                                                let msg = ServerMessage::LayerNames {
-                                                    names: kanata
-                                                        .lock()
-                                                        .layer_info
-                                                        .iter()
-                                                        .step_by(2) // skip every other name,
-                                                        // which is a duplicate
-                                                        .map(|info| info.name.clone())
-                                                        .collect::<Vec<_>>(),
-                                                };
-                                                match stream.write_all(&msg.as_bytes()) {
-                                                    Ok(_) => {}
-                                                    Err(err) => log::error!(
-                                                        "server could not send response: {err}"
-                                                    ),
-                                                }
-                                            }
-                                            ClientMessage::ActOnFakeKey { name, action } => {
-                                                let mut k = kanata.lock();
-                                                let index = match k.virtual_keys.get(&name) {
-                                                    Some(index) => Some(*index as u16),
-                                                    None => {
-                                                        if let Err(e) = stream.write_all(
-                                                            &ServerMessage::Error {
-                                                                msg: format!(
-                                                                "unknown virtual/fake key: {name}"
-                                                            ),
-                                                            }
-                                                            .as_bytes(),
-                                                        ) {
-                                                            log::error!("stream write error: {e}");
-                                                            connections.lock().remove(&addr);
-                                                            break;
-                                                        }
-                                                        continue;
-                                                    }
-                                                };
-                                                if let Some(index) = index {
-                                                    log::info!("tcp server fake-key action: {name},{action:?}");
-                                                    handle_fakekey_action(
-                                                        to_action(action),
-                                                        k.layout.bm(),
-                                                        FAKE_KEY_ROW,
-                                                        index,
-                                                    );
-                                                }
-                                                drop(k);
-                                            }
-                                            ClientMessage::SetMouse { x, y } => {
-                                                log::info!(
-                                                    "tcp server SetMouse action: x {x} y {y}"
-                                                );
-                                                match kanata.lock().kbd_out.set_mouse(x, y) {
-                                                    Ok(_) => {
-                                                        log::info!("sucessfully did set mouse position to: x {x} y {y}");
-                                                        // Optionally send a success message to the
-                                                        // client
-                                                    }
-                                                    Err(e) => {
-                                                        log::error!(
-                                                            "Failed to set mouse position: {}",
-                                                            e
-                                                        );
-                                                        // Implement any error handling logic here,
-                                                        // such as sending an error response to
-                                                        // the client
-                                                    }
-                                                }
-                                            }
-                                            ClientMessage::RequestCurrentLayerInfo {} => {
-                                                let mut k = kanata.lock();
-                                                let cur_layer = k.layout.bm().current_layer();
-                                                let msg = ServerMessage::CurrentLayerInfo {
-                                                    name: k.layer_info[cur_layer].name.clone(),
-                                                    cfg_text: k.layer_info[cur_layer]
-                                                        .cfg_text
-                                                        .clone(),
-                                                };
-                                                drop(k);
-                                                match stream.write_all(&msg.as_bytes()) {
-                                                Ok(_) => {}
-                                                Err(err) => log::error!(
-                                                    "Error writing response to RequestCurrentLayerInfo: {err}"
-                                                ),
-                                            }
-                                            }
-                                            ClientMessage::RequestCurrentLayerName {} => {
-                                                let mut k = kanata.lock();
-                                                let cur_layer = k.layout.bm().current_layer();
-                                                let msg = ServerMessage::CurrentLayerName {
-                                                    name: k.layer_info[cur_layer].name.clone(),
-                                                };
-                                                drop(k);
-                                                match stream.write_all(&msg.as_bytes()) {
-                                                Ok(_) => {}
-                                                Err(err) => log::error!(
-                                                    "Error writing response to RequestCurrentLayerName: {err}"
-                                                ),
+                                    Ok(ClientRequest { id, msg }) => {
+                                        let reply = match handle_client_message(
+                                            msg,
+                                            id,
+                                            &kanata,
+                                            &addr,
+                                            &connections,
+                                            &wakeup_channel,
+                                        ) {
+                                            Ok(Some(reply)) => Some(reply),
+                                            Ok(None) => {
+                                                id.map(|id| ServerMessage::Ok { id: Some(id) })
                                             }
+                                            Err(msg) => Some(ServerMessage::Error { msg, id }),
+                                        };
+                                        if let Some(reply) = reply {
+                                            if let Err(e) = stream.write_all(&reply.as_bytes()) {
+                                                log::error!("stream write error: {e}");
+                                                let mut conns = connections.lock();
+                                                conns.remove(&addr);
+                                                refresh_subscribed_topics(&conns);
+                                                break;
                                             }
                                         }
-                                        use kanata_parser::keys::*;
-                                        wakeup_channel
-                                            .send(KeyEvent {
-                                                code: OsCode::KEY_RESERVED,
-                                                value: KeyValue::WakeUp,
-                                            })
-                                            .expect("write key event");
+                                        wake_processing_loop(&wakeup_channel);
                                     }
                                     Err(e) => {
                                         log::warn!(
@@ -241,10 +240,13 @@ impl TcpServer {
                                             &ServerMessage::Error {
                                                 msg: "disconnecting - you sent an invalid message"
                                                     .into(),
+                                                id: None,
                                             }
                                             .as_bytes(),
                                         );
-                                        connections.lock().remove(&addr);
+                                        let mut conns = connections.lock();
+                                        conns.remove(&addr);
+                                        refresh_subscribed_topics(&conns);
                                         break;
                                     }
                                 }
@@ -261,6 +263,262 @@ impl TcpServer {
     pub fn start(&mut self, _kanata: Arc<Mutex<Kanata>>) {}
 }
 
+/// Handle a message from the client at `addr`. Returns the reply to the message, if there is
+/// one, or an error description.
+#[cfg(feature = "tcp_server")]
+pub(crate) fn handle_client_message(
+    msg: ClientMessage,
+    id: Option<u64>,
+    kanata: &Mutex<Kanata>,
+    addr: &str,
+    connections: &Connections,
+    wakeup_channel: &Sender<KeyEvent>,
+) -> Result<Option<ServerMessage>, String> {
+    use crate::kanata::handle_fakekey_action;
+    use kanata_parser::cfg::FAKE_KEY_ROW;
+
+    match msg {
+        ClientMessage::Hello {} => Ok(Some(ServerMessage::Hello {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            features: enabled_features(),
+            id,
+        })),
+        ClientMessage::ChangeLayer { new } => match kanata.lock().change_layer(&new) {
+            true => Ok(None),
+            false => Err(format!("unknown layer: {new}")),
+        },
+        ClientMessage::RequestLayerNames {} => Ok(Some(ServerMessage::LayerNames {
+            names: kanata
+                .lock()
+                .layer_info
+                .iter()
+                .step_by(2) // skip every other name, which is a duplicate
+                .map(|info| info.name.clone())
+                .collect::<Vec<_>>(),
+            id,
+        })),
+        ClientMessage::ActOnFakeKey { name, action } => {
+            let mut k = kanata.lock();
+            let Some(index) = k.virtual_keys.get(&name).map(|index| *index as u16) else {
+                return Err(format!("unknown virtual/fake key: {name}"));
+            };
+            log::info!("tcp server fake-key action: {name},{action:?}");
+            handle_fakekey_action(to_action(action), k.layout.bm(), FAKE_KEY_ROW, index);
+            Ok(None)
+        }
+        ClientMessage::SetMouse { x, y } => {
+            log::info!("tcp server SetMouse action: x {x} y {y}");
+            match kanata.lock().kbd_out.set_mouse(x, y) {
+                Ok(_) => {
+                    log::info!("sucessfully did set mouse position to: x {x} y {y}");
+                    Ok(None)
+                }
+                Err(e) => {
+                    log::error!("Failed to set mouse position: {}", e);
+                    Err(format!("failed to set mouse position: {e}"))
+                }
+            }
+        }
+        ClientMessage::RequestStateVariables {} => Ok(Some(ServerMessage::StateVariables {
+            variables: kanata.lock().state_var_values(),
+            id,
+        })),
+        ClientMessage::SetStateVariable { name, value } => {
+            match kanata.lock().set_state_var(&name, value) {
+                true => Ok(None),
+                false => Err(format!("unknown defstate variable: {name}")),
+            }
+        }
+        ClientMessage::CancelTimer { name } => match kanata.lock().cancel_timer(&name) {
+            true => Ok(None),
+            false => Err(format!("no running timer named: {name}")),
+        },
+        ClientMessage::SetContext { key, value } => {
+            log::info!("tcp server SetContext: {key}={value}");
+            kanata.lock().set_context(&key, &value);
+            Ok(None)
+        }
+        ClientMessage::RequestCurrentLayerInfo {} => {
+            let mut k = kanata.lock();
+            let cur_layer = k.layout.bm().current_layer();
+            Ok(Some(ServerMessage::CurrentLayerInfo {
+                name: k.layer_info[cur_layer].name.clone(),
+                cfg_text: k.layer_info[cur_layer].cfg_text.clone(),
+                metadata: k.layer_info[cur_layer].metadata.clone(),
+                id,
+            }))
+        }
+        ClientMessage::Subscribe { topics } => {
+            log::info!("tcp client {addr} subscribed to: {topics:?}");
+            let mut conns = connections.lock();
+            if let Some(client) = conns.get_mut(addr) {
+                client.topics = topics;
+            }
+            refresh_subscribed_topics(&conns);
+            Ok(None)
+        }
+        ClientMessage::RequestCurrentLayerName {} => {
+            let mut k = kanata.lock();
+            let cur_layer = k.layout.bm().current_layer();
+            Ok(Some(ServerMessage::CurrentLayerName {
+                name: k.layer_info[cur_layer].name.clone(),
+                id,
+            }))
+        }
+        ClientMessage::RequestState {} => Ok(Some(ServerMessage::State {
+            state: kanata.lock().runtime_state(),
+            id,
+        })),
+        ClientMessage::RequestStatistics {} => match &kanata.lock().statistics {
+            Some(collector) => Ok(Some(ServerMessage::Statistics {
+                statistics: collector.stats.clone(),
+                id,
+            })),
+            None => Err("statistics are not enabled in the configuration".to_string()),
+        },
+        ClientMessage::RequestDynamicMacroConfig { macro_id } => kanata
+            .lock()
+            .dynamic_macro_config(macro_id)
+            .map(|config| {
+                Some(ServerMessage::DynamicMacroConfig {
+                    macro_id,
+                    config,
+                    id,
+                })
+            })
+            .map_err(|e| e.to_string()),
+        ClientMessage::Reload {} => {
+            let cfg_idx = kanata.lock().cur_cfg_idx;
+            request_live_reload(kanata, cfg_idx, None, id, wakeup_channel)
+        }
+        ClientMessage::ReloadNum { index } => {
+            let cfg_count = kanata.lock().cfg_paths.len();
+            if index >= cfg_count {
+                return Err(format!(
+                    "requested config file index {index}, but only {cfg_count} config files were passed"
+                ));
+            }
+            request_live_reload(kanata, index, None, id, wakeup_channel)
+        }
+        ClientMessage::ReloadFile { path } => {
+            let cfg_idx = kanata
+                .lock()
+                .cfg_paths
+                .iter()
+                .position(|cfg_path| *cfg_path == std::path::Path::new(&path));
+            match cfg_idx {
+                Some(cfg_idx) => request_live_reload(kanata, cfg_idx, None, id, wakeup_channel),
+                None => Err(format!(
+                    "no config file with path {path} was passed as an argument to kanata"
+                )),
+            }
+        }
+        ClientMessage::LoadConfig { text } => {
+            let cfg_idx = kanata.lock().cur_cfg_idx;
+            request_live_reload(kanata, cfg_idx, Some(text), id, wakeup_channel)
+        }
+    }
+}
+
+/// Have kanata live reload the configuration file at `cfg_idx`, or `text` if it is given, and
+/// wait for the result. The configuration is parsed by the processing loop, which owns the parser
+/// state. Replies with the configuration error if the configuration is invalid.
+#[cfg(feature = "tcp_server")]
+fn request_live_reload(
+    kanata: &Mutex<Kanata>,
+    cfg_idx: usize,
+    text: Option<String>,
+    id: Option<u64>,
+    wakeup_channel: &Sender<KeyEvent>,
+) -> Result<Option<ServerMessage>, String> {
+    let (reply_tx, reply_rx) = std::sync::mpsc::channel();
+    {
+        let mut k = kanata.lock();
+        match text {
+            Some(_) => log::info!("tcp server requested live reload of configuration text"),
+            None => log::info!(
+                "tcp server requested live reload of file: {}",
+                k.cfg_paths[cfg_idx].display()
+            ),
+        }
+        k.request_live_reload(cfg_idx, text, reply_tx)
+            .map_err(|e| e.to_string())?;
+    }
+    wake_processing_loop(wakeup_channel);
+    match reply_rx.recv() {
+        Ok(Ok(())) => Ok(None),
+        Ok(Err(e)) => Ok(Some(config_error(e, id))),
+        Err(_) => Err("live reload did not complete, see the kanata log".to_string()),
+    }
+}
+
+/// Wake the processing loop if it is waiting for input, so that it handles changes made by a
+/// client message.
+#[cfg(feature = "tcp_server")]
+fn wake_processing_loop(wakeup_channel: &Sender<KeyEvent>) {
+    use kanata_parser::keys::*;
+    if let Err(e) = wakeup_channel.send(KeyEvent::new(OsCode::KEY_RESERVED, KeyValue::WakeUp)) {
+        log::error!("could not wake the processing loop: {e}");
+    }
+}
+
+#[cfg(feature = "tcp_server")]
+pub(crate) fn config_error(report: miette::Error, id: Option<u64>) -> ServerMessage {
+    let msg = report
+        .help()
+        .map(|help| help.to_string())
+        .unwrap_or_else(|| report.to_string());
+    // Drop the link to the configuration guide that the parser appends to its help text.
+    let msg = msg
+        .split("\n\nFor more info")
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let span = report
+        .labels()
+        .and_then(|mut labels| labels.next())
+        .and_then(|label| {
+            let contents = report.source_code()?.read_span(label.inner(), 0, 0).ok()?;
+            Some(ConfigErrorSpan {
+                file_name: contents.name().unwrap_or_default().to_string(),
+                start: label.offset(),
+                end: label.offset() + label.len(),
+                line: contents.line() + 1,
+                column: contents.column() + 1,
+            })
+        });
+    ServerMessage::ConfigError { msg, span, id }
+}
+
+/// The cargo features that kanata was built with, reported in `ServerMessage::Hello`.
+#[cfg(feature = "tcp_server")]
+fn enabled_features() -> Vec<String> {
+    [
+        ("tcp_server", cfg!(feature = "tcp_server")),
+        ("cmd", cfg!(feature = "cmd")),
+        ("interception_driver", cfg!(feature = "interception_driver")),
+        (
+            "win_sendinput_send_scancodes",
+            cfg!(feature = "win_sendinput_send_scancodes"),
+        ),
+        (
+            "win_llhook_read_scancodes",
+            cfg!(feature = "win_llhook_read_scancodes"),
+        ),
+        ("simulated_output", cfg!(feature = "simulated_output")),
+        ("simulated_input", cfg!(feature = "simulated_input")),
+        ("passthru_ahk", cfg!(feature = "passthru_ahk")),
+        ("gui", cfg!(feature = "gui")),
+        ("zippychord", cfg!(feature = "zippychord")),
+        ("perf_logging", cfg!(feature = "perf_logging")),
+    ]
+    .into_iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(name, _)| name.to_string())
+    .collect()
+}
+
 #[cfg(feature = "tcp_server")]
 pub fn simple_sexpr_to_json_array(exprs: &[SimpleSExpr]) -> serde_json::Value {
     let mut result = Vec::new();
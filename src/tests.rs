@@ -136,3 +136,82 @@ fn sizeof_state() {
         2 * std::mem::size_of::<usize>()
     );
 }
+
+#[test]
+#[cfg(feature = "tcp_server")]
+fn tcp_config_error_has_span() {
+    use kanata_tcp_protocol::*;
+    init_log();
+    let _lk = match CFG_PARSE_LOCK.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let Err(err) = new_from_str("(defsrc a)\n(deflayer base b c)", Default::default()) else {
+        panic!("config should be invalid");
+    };
+    let ServerMessage::ConfigError { msg, span, id } =
+        crate::tcp_server::config_error(err, Some(1))
+    else {
+        panic!("expected ConfigError");
+    };
+    assert_eq!(
+        msg,
+        "Layer base has 2 item(s), but requires 1 to match defsrc"
+    );
+    assert_eq!(id, Some(1));
+    assert_eq!(
+        span,
+        Some(ConfigErrorSpan {
+            file_name: "configuration".into(),
+            start: 11,
+            end: 30,
+            line: 2,
+            column: 1,
+        })
+    );
+}
+
+/// Reads back the output of the `setmouse` device. Needs write access to `/dev/uinput` and read
+/// access to `/dev/input`, so it is not run by default.
+#[test]
+#[ignore]
+#[cfg(all(
+    target_os = "linux",
+    not(feature = "simulated_output"),
+    not(feature = "passthru_ahk")
+))]
+fn linux_setmouse_loopback() {
+    use evdev::{AbsoluteAxisType, InputEventKind};
+    let mut kbd_out = crate::oskbd::KbdOut::new(
+        &None,
+        false,
+        evdev::BusType::BUS_USB,
+        Some((1920, 1080)),
+        None,
+    )
+    .expect("uinput devices can be created");
+    // Give udev time to create the device node.
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    let (_, mut reader) = evdev::enumerate()
+        .find(|(_, dev)| dev.name() == Some("kanata setmouse"))
+        .expect("setmouse device exists");
+    kbd_out.set_mouse(u16::MAX, 0).expect("set_mouse succeeds");
+    kbd_out.set_mouse(32768, 32768).expect("set_mouse succeeds");
+    let mut positions = vec![];
+    while positions.len() < 4 {
+        for ev in reader.fetch_events().expect("events can be read") {
+            if let InputEventKind::AbsAxis(axis) = ev.kind() {
+                positions.push((axis, ev.value()));
+            }
+        }
+    }
+    assert_eq!(
+        positions,
+        vec![
+            (AbsoluteAxisType::ABS_X, 1919),
+            (AbsoluteAxisType::ABS_Y, 0),
+            (AbsoluteAxisType::ABS_X, 959),
+            (AbsoluteAxisType::ABS_Y, 539),
+        ]
+    );
+}
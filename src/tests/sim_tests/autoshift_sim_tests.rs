@@ -0,0 +1,72 @@
+use super::*;
+
+#[test]
+fn sim_autoshift_tap_and_hold() {
+    let result = simulate(
+        "
+         (defcfg autoshift yes autoshift-timeout 100)
+         (defsrc a b)
+         (deflayer base a b)
+        ",
+        "d:a t:50 u:a t:50 d:b t:150 u:b t:10",
+    )
+    .to_ascii();
+    assert_eq!(
+        "t:50ms dn:A t:1ms up:A t:148ms dn:LShift dn:B t:1ms up:LShift up:B",
+        result
+    );
+}
+
+#[test]
+fn sim_autoshift_other_key_pressed_before_timeout() {
+    let result = simulate(
+        "
+         (defcfg autoshift yes autoshift-timeout 100)
+         (defsrc a b)
+         (deflayer base a b)
+        ",
+        "d:a t:20 d:b t:20 u:a t:20 u:b t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:A up:A dn:B up:B", result);
+}
+
+#[test]
+fn sim_autoshift_key_lists_and_custom() {
+    let result = simulate(
+        "
+         (defcfg
+           autoshift yes
+           autoshift-timeout 100
+           autoshift-keys (a b)
+           autoshift-exclude-keys (b)
+           autoshift-custom (. S-1))
+         (defsrc a b c .)
+         (deflayer base a b c .)
+        ",
+        "d:b t:150 u:b t:10 d:c t:150 u:c t:10 d:. t:150 u:. t:10 d:lctl d:a t:150 u:a u:lctl t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!(
+        "dn:B up:B dn:C up:C dn:LShift dn:Kb1 up:LShift up:Kb1 \
+         dn:LCtrl dn:A up:A up:LCtrl",
+        result
+    );
+}
+
+#[test]
+fn sim_autoshift_repeat() {
+    let result = simulate(
+        "
+         (defcfg autoshift yes autoshift-timeout 100 autoshift-repeat yes)
+         (defsrc a)
+         (deflayer base a)
+        ",
+        "d:a t:50 r:a t:100 r:a t:10 u:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:LShift dn:A dn:A up:LShift up:A", result);
+}
@@ -430,3 +430,90 @@ fn sim_chord_timeout_events() {
         result
     );
 }
+
+static CHORD_OPTIONS_CFG: &str = "
+(defcfg concurrent-tap-hold yes)
+(defsrc a b c d)
+(deflayer base a b c d)
+(deflayer other a b c d)
+(defchordsv2
+  (a b) x 50 first-release () ((enabled-layers other))
+  (c d) y 50 first-release () ((min-idle 100))
+  (a c) z 50 all-released () ((hold 200 lctl))
+  (b d) (layer-switch other) 50 first-release (other)
+)";
+
+#[test]
+fn sim_chord_enabled_layers() {
+    let result = simulate(CHORD_OPTIONS_CFG, "d:a d:b t:100 u:a u:b t:100")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:A dn:B up:A up:B", result);
+    let result = simulate(
+        CHORD_OPTIONS_CFG,
+        "d:b d:d t:100 u:b u:d t:100 d:a d:b t:100 u:a u:b t:100",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X", result);
+}
+
+#[test]
+fn sim_chord_min_idle_option() {
+    let result = simulate(CHORD_OPTIONS_CFG, "d:c d:d t:100 u:c u:d t:100")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:Y up:Y", result);
+    let result = simulate(
+        CHORD_OPTIONS_CFG,
+        "d:b t:10 u:b t:10 d:c d:d t:100 u:c u:d t:100",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:B up:B dn:C dn:D up:C up:D", result);
+}
+
+#[test]
+fn sim_chord_hold_option() {
+    let result = simulate(CHORD_OPTIONS_CFG, "d:a d:c t:100 u:a u:c t:100")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:Z up:Z", result);
+    let result = simulate(CHORD_OPTIONS_CFG, "d:a d:c t:300 u:a u:c t:100").to_ascii();
+    assert_eq!("t:199ms dn:LCtrl t:104ms up:LCtrl", result);
+}
+
+#[test]
+fn sim_chord_empty_options() {
+    let cfg = "
+(defcfg concurrent-tap-hold yes)
+(defsrc a b c d)
+(deflayer base a b c d)
+(defchordsv2
+  (a b) x 50 first-release () ()
+  (c d) y 50 first-release ()
+)";
+    let result = simulate(
+        cfg,
+        "d:a d:b t:100 u:a u:b t:100 d:c d:d t:100 u:c u:d t:100",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:Y up:Y", result);
+}
+
+#[test]
+#[should_panic]
+fn sim_chord_error_on_bad_option() {
+    simulate(
+        "
+(defcfg concurrent-tap-hold yes)
+(defsrc)
+(deflayer base)
+(defchordsv2
+ (1 2) a 20 all-released () ((min-idle 10) (min-idle 20))
+)
+        ",
+        "",
+    );
+}
@@ -0,0 +1,64 @@
+use super::*;
+
+#[test]
+fn sim_switch_context() {
+    let result = simulate(
+        "
+         (defcfg)
+         (defsrc a)
+         (defalias a (switch
+            ((context app firefox)) x break
+            ((context app \"org.gnome.Terminal\")) y break
+            () z break))
+         (deflayer base @a)
+        ",
+        "d:a u:a t:10 ctx:app=firefox d:a u:a t:10 ctx:app=org.gnome.Terminal d:a u:a t:10 \
+         ctx:app= d:a u:a t:10",
+    )
+    .no_time();
+    assert_eq!(
+        "out:↓Z out:↑Z out:↓X out:↑X out:↓Y out:↑Y out:↓Z out:↑Z",
+        result
+    );
+}
+
+#[test]
+fn sim_context_layers() {
+    let result = simulate(
+        "
+         (defcfg)
+         (defsrc a b)
+         (deflayer base a (layer-switch other))
+         (deflayer other c d)
+         (deflayer browser x y)
+         (deflayer terminal z z)
+         (defcontextlayers
+            (app firefox) browser
+            (app kitty) terminal)
+        ",
+        "d:b u:b t:10 d:a u:a t:10 ctx:app=firefox d:a u:a t:10 \
+         ctx:app=kitty d:a u:a t:10 ctx:app=other d:a u:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:C up:C dn:X up:X dn:Z up:Z dn:C up:C", result);
+}
+
+#[test]
+fn sim_context_layers_keep_layer_switched_by_user() {
+    let result = simulate(
+        "
+         (defcfg)
+         (defsrc a b)
+         (deflayer base a (layer-switch other))
+         (deflayer other c d)
+         (deflayer browser x (layer-switch terminal))
+         (deflayer terminal z z)
+         (defcontextlayers (app firefox) browser)
+        ",
+        "d:b u:b t:10 ctx:app=firefox d:a u:a t:10 d:b u:b t:10 ctx:app=other d:a u:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:Z up:Z", result);
+}
@@ -0,0 +1,172 @@
+use super::*;
+
+use crate::kanata::set_event_channel;
+use crate::tcp_server::*;
+use kanata_tcp_protocol::*;
+
+use std::io::{BufRead, BufReader};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+
+/// Connect a client and add the server side of the connection to `clients`, like the TCP server
+/// does. Returns the client address and the client side of the connection.
+fn connect(listener: &TcpListener, clients: &Connections) -> (String, BufReader<TcpStream>) {
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    client
+        .set_read_timeout(Some(std::time::Duration::from_millis(100)))
+        .unwrap();
+    let (stream, addr) = listener.accept().unwrap();
+    let addr = addr.to_string();
+    clients.lock().insert(
+        addr.clone(),
+        TcpClient {
+            stream,
+            topics: vec![],
+        },
+    );
+    (addr, BufReader::new(client))
+}
+
+/// Read the messages that were sent to a client.
+fn received(client: &mut BufReader<TcpStream>) -> Vec<ServerMessage> {
+    let mut msgs = vec![];
+    let mut line = String::new();
+    while client.read_line(&mut line).is_ok_and(|n| n > 0) {
+        msgs.push(serde_json::from_str(&line).unwrap());
+        line.clear();
+    }
+    msgs
+}
+
+fn subscribe(
+    k: &parking_lot::Mutex<Kanata>,
+    addr: &str,
+    clients: &Connections,
+    topics: &[SubscriptionTopic],
+) {
+    let msg = ClientMessage::Subscribe {
+        topics: topics.to_vec(),
+    };
+    let (wakeup_tx, _wakeup_rx) = std::sync::mpsc::sync_channel(1);
+    handle_client_message(msg, None, k, addr, clients, &wakeup_tx).unwrap();
+}
+
+/// Run the simulated input with the events sent to `clients`.
+fn simulate_events(k: &parking_lot::Mutex<Kanata>, sim: &str, clients: &Connections) {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1000);
+    set_event_channel(Some(tx));
+    simulate_on(&mut k.lock(), sim);
+    set_event_channel(None);
+    for msg in rx.try_iter() {
+        relay_to_clients(&msg, &mut clients.lock());
+    }
+}
+
+fn key_outputs(msgs: &[ServerMessage]) -> String {
+    msgs.iter()
+        .filter_map(|msg| match msg {
+            ServerMessage::Event(EventMessage::KeyOutput { key, action, .. }) => {
+                Some(match action {
+                    KeyEventAction::Press => format!("dn:{key}"),
+                    KeyEventAction::Release => format!("up:{key}"),
+                    KeyEventAction::Repeat => format!("rp:{key}"),
+                })
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn subscriptions_filter_events_per_client() {
+    let (_lk, k) = new_kanata("(defsrc a) (deflayer base b)");
+    let k = parking_lot::Mutex::new(k);
+    let clients: Connections = Arc::new(parking_lot::Mutex::new(Default::default()));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let (output_addr, mut output_client) = connect(&listener, &clients);
+    let (input_addr, mut input_client) = connect(&listener, &clients);
+
+    subscribe(&k, &output_addr, &clients, &[SubscriptionTopic::KeyOutput]);
+    subscribe(&k, &input_addr, &clients, &[SubscriptionTopic::InputEvents]);
+    assert!(is_subscribed(SubscriptionTopic::KeyOutput));
+    assert!(is_subscribed(SubscriptionTopic::InputEvents));
+    assert!(!is_subscribed(SubscriptionTopic::MouseActions));
+
+    simulate_events(&k, "d:a t:10 u:a t:10", &clients);
+    // Messages without a topic go to every client.
+    let layer_change = ServerMessage::LayerChange {
+        new: "base".into(),
+        metadata: Default::default(),
+    };
+    relay_to_clients(&layer_change, &mut clients.lock());
+
+    let msgs = received(&mut output_client);
+    assert_eq!(key_outputs(&msgs), "dn:B up:B");
+    assert_eq!(msgs.len(), 3);
+    assert!(matches!(msgs[2], ServerMessage::LayerChange { .. }));
+    let msgs = received(&mut input_client);
+    assert_eq!(msgs.len(), 3);
+    assert!(msgs[..2].iter().all(|msg| matches!(
+        msg,
+        ServerMessage::Event(EventMessage::InputEvent { key, .. }) if key == "A"
+    )));
+    assert!(matches!(msgs[2], ServerMessage::LayerChange { .. }));
+
+    // Unsubscribe one client from everything and move the other one to a different topic.
+    subscribe(&k, &output_addr, &clients, &[]);
+    subscribe(&k, &input_addr, &clients, &[SubscriptionTopic::KeyOutput]);
+    assert!(is_subscribed(SubscriptionTopic::KeyOutput));
+    assert!(!is_subscribed(SubscriptionTopic::InputEvents));
+    simulate_events(&k, "d:a t:10 u:a t:10", &clients);
+    assert!(received(&mut output_client).is_empty());
+    assert_eq!(key_outputs(&received(&mut input_client)), "dn:B up:B");
+
+    subscribe(&k, &input_addr, &clients, &[]);
+    assert!(!is_subscribed(SubscriptionTopic::KeyOutput));
+}
+
+#[test]
+fn key_output_includes_repeat_sequence_and_macro_replay() {
+    let (_lk, k) = new_kanata(
+        "
+             (defcfg sequence-input-mode visible-backspaced)
+             (defsrc a b c d e f)
+             (deflayer base sldr rpt c
+              (dynamic-macro-record 0) dynamic-macro-record-stop (dynamic-macro-play 0))
+             (defvirtualkeys s1 z)
+             (defseq s1 (c c))
+            ",
+    );
+    let k = parking_lot::Mutex::new(k);
+    let clients: Connections = Arc::new(parking_lot::Mutex::new(Default::default()));
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let (addr, mut client) = connect(&listener, &clients);
+    subscribe(&k, &addr, &clients, &[SubscriptionTopic::KeyOutput]);
+
+    // Repeat key
+    simulate_events(&k, "d:c t:10 u:c t:10 d:b t:10 u:b t:10", &clients);
+    assert_eq!(
+        key_outputs(&received(&mut client)),
+        "dn:C up:C up:C dn:C up:C"
+    );
+    // Sequence with backspaces
+    simulate_events(
+        &k,
+        "d:a t:10 u:a t:10 d:c t:10 u:c t:10 d:c t:10 u:c t:500",
+        &clients,
+    );
+    assert_eq!(
+        key_outputs(&received(&mut client)),
+        "dn:C up:C dn:C dn:BACKSPACE up:BACKSPACE dn:BACKSPACE up:BACKSPACE up:C dn:Z up:Z"
+    );
+    // Dynamic macro replay
+    simulate_events(
+        &k,
+        "d:d t:10 u:d t:10 d:c t:10 u:c t:10 d:e t:10 u:e t:10 d:f t:10 u:f t:500",
+        &clients,
+    );
+    assert_eq!(key_outputs(&received(&mut client)), "dn:C up:C dn:C up:C");
+
+    subscribe(&k, &addr, &clients, &[]);
+}
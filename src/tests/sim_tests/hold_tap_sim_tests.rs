@@ -0,0 +1,193 @@
+use super::*;
+
+const CFG: &str = "
+(defcfg log-hold-tap-decisions yes concurrent-tap-hold yes)
+(defsrc a b c d e)
+(deflayer base
+  (tap-hold 200 200 a lsft)
+  (tap-hold-press 200 200 b lctl)
+  (tap-hold-release 200 200 c lalt)
+  (tap-hold-release-keys 200 200 d lmet (e))
+  e
+)
+";
+
+#[test]
+fn hold_tap_decision_release() {
+    let result = simulate(CFG, "d:a t:50 u:a t:50").no_time().to_ascii();
+    assert_eq!("dn:A ht:a:tap:release:51ms:↑a up:A", result);
+}
+
+#[test]
+fn hold_tap_decision_timeout() {
+    let result = simulate(CFG, "d:a t:250 u:a t:50").no_time().to_ascii();
+    assert_eq!("dn:LShift ht:a:timeout:timeout:200ms up:LShift", result);
+}
+
+#[test]
+fn hold_tap_decision_other_key_press() {
+    let result = simulate(CFG, "d:b t:50 d:e t:50 u:e t:50 u:b t:50")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:LCtrl ht:b:hold:other-key-press:51ms:↓e dn:E up:E up:LCtrl",
+        result
+    );
+}
+
+#[test]
+fn hold_tap_decision_permissive_hold() {
+    let result = simulate(CFG, "d:c t:50 d:e t:50 u:e t:50 u:c t:50")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:LAlt ht:c:hold:permissive-hold:101ms:↓e,↑e dn:E up:E up:LAlt",
+        result
+    );
+}
+
+#[test]
+fn hold_tap_decision_tap_keys() {
+    let result = simulate(CFG, "d:d t:50 d:e t:50 u:e t:50 u:d t:50")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:D ht:d:tap:tap-keys-press:51ms:↓e dn:E up:E up:D",
+        result
+    );
+}
+
+#[test]
+fn hold_tap_decision_concurrent_timeout() {
+    let result = simulate(CFG, "d:a t:50 d:b t:300 u:a u:b t:50")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:LShift ht:a:timeout:timeout:200ms:↓b dn:LCtrl ht:b:timeout:concurrent-timeout:200ms up:LShift up:LCtrl",
+        result
+    );
+}
+
+#[test]
+fn hold_tap_decision_quick_tap() {
+    let result = simulate(CFG, "d:a t:20 u:a t:20 d:a t:20 u:a t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:A ht:a:tap:release:21ms:↑a up:A dn:A ht:a:tap:quick-tap:0ms up:A",
+        result
+    );
+}
+
+#[test]
+fn hold_tap_decisions_are_not_in_output_by_default() {
+    let result = simulate(
+        "(defcfg) (defsrc a) (deflayer base (tap-hold 200 200 a lsft))",
+        "d:a t:50 u:a t:50",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:A up:A", result);
+}
+
+#[test]
+fn hold_tap_require_prior_idle() {
+    let cfg = "
+(defcfg log-hold-tap-decisions yes)
+(defsrc a b)
+(deflayer base (tap-hold-require-prior-idle 200 200 a lsft 100) b)
+";
+    let result = simulate(
+        cfg,
+        "d:b t:10 u:b t:40 d:a t:300 u:a t:200 d:a t:300 u:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!(
+        "dn:B up:B dn:A ht:a:tap:require-prior-idle:0ms up:A \
+         dn:LShift ht:a:timeout:timeout:201ms up:LShift",
+        result
+    );
+}
+
+#[test]
+fn tap_dance_hold() {
+    let cfg = "
+(defsrc a b)
+(deflayer base @td b)
+(deflayer nav x y)
+(defalias td (tap-dance-hold 100 ((a lctl) (b (layer-while-held nav)))))";
+    let result = simulate(cfg, "d:a t:10 u:a t:200").no_time().to_ascii();
+    assert_eq!("dn:A up:A", result);
+    let result = simulate(cfg, "d:a t:150 d:b t:10 u:b u:a t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:LCtrl dn:B up:B up:LCtrl", result);
+    let result = simulate(cfg, "d:a t:10 u:a t:10 d:a t:150 d:b t:10 u:b u:a t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:Y up:Y", result);
+    let result = simulate(cfg, "d:a t:10 u:a t:10 d:a t:10 u:a t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:B up:B", result);
+}
+
+const OPPOSITE_HAND_CFG: &str = "
+(defsrc a s j k spc)
+(defhands
+  (left a s)
+  (right j k)
+  (neutral spc))
+(deflayer base
+  (tap-hold-opposite-hand 200 200 a lsft)
+  s
+  (tap-hold-opposite-hand 200 200 j rsft)
+  k
+  spc
+)
+";
+
+#[test]
+fn tap_hold_opposite_hand_same_hand_taps() {
+    let result = simulate(OPPOSITE_HAND_CFG, "d:a t:20 d:s t:20 u:a t:20 u:s t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:A dn:S up:A up:S", result);
+}
+
+#[test]
+fn tap_hold_opposite_hand_other_hand_holds() {
+    let result = simulate(OPPOSITE_HAND_CFG, "d:a t:20 d:k t:20 u:k t:20 u:a t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:LShift dn:K up:K up:LShift", result);
+    let result = simulate(OPPOSITE_HAND_CFG, "d:j t:20 d:spc t:20 u:spc t:20 u:j t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:RShift dn:Space up:Space up:RShift", result);
+}
+
+#[test]
+fn tap_hold_opposite_hand_decision_reasons() {
+    let cfg = format!("(defcfg log-hold-tap-decisions yes) {OPPOSITE_HAND_CFG}");
+    let result = simulate(cfg.as_str(), "d:a t:20 d:s t:20 u:a t:20 u:s t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:A ht:a:tap:same-hand:21ms:↓s dn:S up:A up:S", result);
+    let result = simulate(cfg.as_str(), "d:a t:20 d:k t:20 u:k t:20 u:a t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:LShift ht:a:hold:opposite-hand:21ms:↓k dn:K up:K up:LShift",
+        result
+    );
+}
+
+#[test]
+fn tap_hold_opposite_hand_timeout() {
+    let result = simulate(OPPOSITE_HAND_CFG, "d:a t:20 u:a t:300 d:j t:300 u:j t:20")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:A up:A dn:RShift up:RShift", result);
+}
@@ -93,3 +93,110 @@ fn layer_holding() {
         result
     );
 }
+
+#[test]
+fn sim_defdevice_layer() {
+    let result = simulate(
+        "
+         (defcfg)
+         (defsrc a b c)
+         (defdevice pad name \"Macro Pad\" layer pad)
+         (deflayer base a b (layer-while-held other))
+         (deflayer pad 1 _ _)
+         (deflayer other _ z _)
+        ",
+        "d:a u:a d:b u:b t:10 dev:pad d:a u:a d:b u:b t:10 \
+         dev:none d:c dev:pad d:a u:a d:b u:b t:10",
+    )
+    .no_time()
+    .no_releases();
+    assert_eq!("out:↓A out:↓B out:↓Kb1 out:↓B out:↓Kb1 out:↓Z", result);
+}
+
+#[test]
+fn layer_lock() {
+    let result = simulate(
+        "
+         (defsrc a b c)
+         (deflayer base (layer-while-held nav) b c)
+         (deflayer nav _ layer-lock x)
+        ",
+        "d:a t:10 d:b t:10 u:b t:10 u:a t:10 d:c u:c t:10 d:b u:b t:10 d:c u:c t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:C up:C", result);
+}
+
+#[test]
+fn layer_timeout() {
+    let result = simulate(
+        "
+         (defsrc a b c)
+         (deflayer base (layer-switch nav) (layer-while-held locked) c)
+         (deflayer (nav timeout 100) _ _ x)
+         (deflayer (locked timeout 100) layer-lock _ y)
+        ",
+        "d:a u:a t:90 d:c u:c t:90 d:c u:c t:110 d:c u:c t:10 \
+         d:b t:10 d:a u:a t:10 u:b t:10 d:c u:c t:10 t:100 d:c u:c t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:X up:X dn:C up:C dn:Y up:Y dn:C up:C", result);
+}
+
+#[test]
+fn layer_timeout_unlocks_only_the_timed_out_layer() {
+    let result = simulate(
+        "
+         (defsrc a b c d)
+         (deflayer base (layer-while-held l1) b c d)
+         (deflayer l1 _ (layer-while-held l2) x layer-lock)
+         (deflayer (l2 timeout 100) _ _ y layer-lock)
+        ",
+        "d:a t:10 d:d u:d t:10 u:a t:10 d:b t:10 d:d u:d t:10 u:b t:10 \
+         d:c u:c t:10 t:100 d:c u:c t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:Y up:Y dn:X up:X", result);
+}
+
+#[test]
+fn layer_timeout_restores_previous_default_layer() {
+    let result = simulate(
+        "
+         (defsrc a b c)
+         (deflayer base (layer-switch qwerty) b c)
+         (deflayer qwerty (layer-switch nav) b z)
+         (deflayer (nav timeout 100) _ (layer-switch nav2) x)
+         (deflayer (nav2 timeout 100) _ _ y)
+        ",
+        "d:a u:a t:10 d:a u:a t:10 d:c u:c t:10 t:100 d:c u:c t:10 \
+         d:a u:a t:10 d:b u:b t:10 d:c u:c t:110 d:c u:c t:110 d:c u:c t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:Z up:Z dn:Y up:Y dn:X up:X dn:Z up:Z", result);
+}
+
+#[test]
+fn layer_on_enter_on_exit() {
+    let result = simulate(
+        "
+         (defsrc a b)
+         (deflayer (base on-enter (macro b a s e)) (layer-while-held nav) (layer-switch other))
+         (deflayer (nav on-enter x on-exit y) _ _)
+         (deflayer (other description \"Other layer\" colour red) _ _)
+        ",
+        "t:50 d:a t:10 u:a t:50 d:b u:b t:50",
+    )
+    .no_time()
+    .to_ascii();
+    // The on-enter action of the base layer also activates at startup.
+    assert_eq!(
+        "dn:B up:B dn:A up:A dn:S up:S dn:E up:E \
+         dn:X up:X dn:Y up:Y dn:B up:B dn:A up:A dn:S up:S dn:E up:E",
+        result
+    );
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn layer_sets_leds() {
+    let result = simulate(
+        "(defsrc a b)
+         (deflayer base (layer-while-held nav) b)
+         (deflayer (nav led-scroll on led-num off) _ c)",
+        "d:a t:10 d:b t:10 u:b t:10 u:a t:10",
+    )
+    .no_time();
+    assert_eq!("led:num:off,scroll:on out:↓C out:↑C led:auto", result);
+}
+
+#[test]
+fn caps_word_indicator_overrides_layer() {
+    let result = simulate(
+        "(defcfg led-caps-word caps)
+         (defsrc a b)
+         (deflayer (base led-caps off) (caps-word 100) b)",
+        "t:10 d:a t:10 u:a t:10 d:b t:10 u:b t:10 d:spc t:10 u:spc t:10",
+    )
+    .no_time();
+    assert_eq!(
+        "led:caps:off led:caps:on out:↓LShift out:↓B out:↑LShift out:↑B \
+         out:↓Space led:caps:off out:↑Space",
+        result
+    );
+}
+
+#[test]
+fn set_leds_action() {
+    let result = simulate(
+        "(defsrc a b c)
+         (deflayer (base led-num on)
+           (set-leds caps toggle num off)
+           (set-leds caps toggle)
+           (set-leds caps auto num auto))",
+        "d:a t:10 u:a t:10 d:b t:10 u:b t:10 d:a t:10 u:a t:10 d:c t:10 u:c t:10",
+    )
+    .no_time();
+    assert_eq!(
+        "led:num:off,caps:on led:num:off,caps:off led:num:off,caps:on led:num:on",
+        result
+    );
+}
@@ -143,3 +143,74 @@ fn test_release(cfg: &str) {
         result
     );
 }
+
+fn dynamic_macro_cfg(path: &std::path::Path) -> String {
+    format!(
+        "\
+(defcfg dynamic-macro-file \"{}\")
+(defsrc a b c d e f)
+(deflayer base
+  (dynamic-macro-record 3) (dynamic-macro-play 3) dynamic-macro-record-stop
+  (dynamic-macro-clear 3) (dynamic-macro-load 3) f)",
+        path.display()
+    )
+}
+
+#[test]
+fn dynamic_macro_persists_to_file() {
+    let path = std::env::temp_dir().join(format!(
+        "kanata-dynamic-macro-persist-{}.txt",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&path);
+    let cfg = dynamic_macro_cfg(&path);
+
+    // Recording is saved to the file once stopped.
+    simulate(
+        cfg.as_str(),
+        "d:a t:10 u:a t:10 d:f t:20 u:f t:10 d:c t:10 u:c t:10",
+    );
+    crate::kanata::flush_macro_file();
+    let content = std::fs::read_to_string(&path).expect("macro file written");
+    let macro_line = content.lines().find(|l| !l.starts_with(";;"));
+    assert_eq!(Some("3: u:a t:10 d:f t:20 u:f t:10"), macro_line);
+
+    // A new instance loads the file at startup.
+    let result = simulate(cfg.as_str(), "d:b u:b t:50").to_ascii().no_time();
+    assert_eq!("dn:F up:F", result);
+
+    // Hand edits with config key names are accepted.
+    std::fs::write(&path, "3: d:lsft d:f u:f u:lsft\n").unwrap();
+    let result = simulate(cfg.as_str(), "d:b u:b t:50").to_ascii().no_time();
+    assert_eq!("dn:LShift dn:F up:F up:LShift", result);
+
+    // Clearing removes the slot from memory and the file, loading restores nothing.
+    let result = simulate(cfg.as_str(), "d:d u:d t:10 d:e u:e t:10 d:b u:b t:50").to_ascii();
+    assert_eq!("", result);
+    crate::kanata::flush_macro_file();
+    let content = std::fs::read_to_string(&path).unwrap();
+    assert!(content.lines().all(|l| l.starts_with(";;")));
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+#[cfg(feature = "tcp_server")]
+fn dynamic_macro_to_config() {
+    let (_lk, mut k) = new_kanata(
+        "
+(defsrc f1 f2 lsft h i 1 lctl)
+(deflayer base (dynamic-macro-record 0) dynamic-macro-record-stop lsft h i 1 lctl)
+",
+    );
+    simulate_on(
+        &mut k,
+        "d:f1 t:10 u:f1 t:10 d:lsft t:5 d:h t:5 u:h u:lsft t:30 d:i t:10 u:i d:1 t:10 \
+         u:1 d:lctl t:10 u:lctl t:10 d:f2 t:10 u:f2 t:10",
+    );
+    assert_eq!(
+        "(macro S-h 35 i 10 Digit1 20 lctl)",
+        k.dynamic_macro_config(0).expect("converts")
+    );
+    assert!(k.dynamic_macro_config(1).is_err());
+}
@@ -12,18 +12,32 @@ use crate::{
 
 use rustc_hash::FxHashMap;
 
+mod autoshift_sim_tests;
 mod block_keys_tests;
 mod capsword_sim_tests;
 mod chord_sim_tests;
+mod context_sim_tests;
+#[cfg(feature = "tcp_server")]
+mod event_stream_sim_tests;
+mod hold_tap_sim_tests;
 mod layer_sim_tests;
+mod led_sim_tests;
 mod macro_sim_tests;
+mod mouse_sim_tests;
 mod oneshot_tests;
 mod override_tests;
 mod release_sim_tests;
 mod repeat_sim_tests;
+#[cfg(feature = "tcp_server")]
+mod runtime_state_tests;
 mod seq_sim_tests;
+mod state_sim_tests;
+mod statistics_sim_tests;
 mod switch_sim_tests;
+#[cfg(feature = "tcp_server")]
+mod tcp_reload_sim_tests;
 mod template_sim_tests;
+mod timer_sim_tests;
 mod unicode_sim_tests;
 mod unmod_sim_tests;
 mod use_defsrc_sim_tests;
@@ -45,7 +59,28 @@ fn simulate_with_file_content<S: AsRef<str>>(
         Err(poisoned) => poisoned.into_inner(),
     };
     let mut k = Kanata::new_from_str(cfg.as_ref(), file_content).expect("failed to parse cfg");
-    for pair in sim.as_ref().split_whitespace() {
+    simulate_on(&mut k, sim.as_ref());
+    drop(_lk);
+    k.kbd_out.outputs.events.join("\n")
+}
+
+/// Parse the configuration for a test that works with the kanata instance directly. The returned
+/// guard of `CFG_PARSE_LOCK` must be kept alive while the instance is used, e.g. by `simulate_on`.
+fn new_kanata(cfg: &str) -> (std::sync::MutexGuard<'static, ()>, Kanata) {
+    init_log();
+    let lk = match CFG_PARSE_LOCK.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    let k = Kanata::new_from_str(cfg, Default::default()).expect("failed to parse cfg");
+    (lk, k)
+}
+
+/// Run the simulated input on an existing kanata instance. The caller must hold
+/// `CFG_PARSE_LOCK`.
+fn simulate_on(k: &mut Kanata, sim: &str) {
+    let mut device = 0;
+    for pair in sim.split_whitespace() {
         match pair.split_once(':') {
             Some((kind, val)) => match kind {
                 "t" => {
@@ -54,35 +89,46 @@ fn simulate_with_file_content<S: AsRef<str>>(
                 }
                 "d" => {
                     let key_code = str_to_oscode(val).expect("valid keycode");
-                    k.handle_input_event(&KeyEvent {
-                        code: key_code,
-                        value: KeyValue::Press,
-                    })
+                    k.handle_input_event(
+                        &KeyEvent::new(key_code, KeyValue::Press).with_device(device),
+                    )
                     .expect("input handles fine");
                 }
                 "u" => {
                     let key_code = str_to_oscode(val).expect("valid keycode");
-                    k.handle_input_event(&KeyEvent {
-                        code: key_code,
-                        value: KeyValue::Release,
-                    })
+                    k.handle_input_event(
+                        &KeyEvent::new(key_code, KeyValue::Release).with_device(device),
+                    )
                     .expect("input handles fine");
                 }
                 "r" => {
                     let key_code = str_to_oscode(val).expect("valid keycode");
-                    k.handle_input_event(&KeyEvent {
-                        code: key_code,
-                        value: KeyValue::Repeat,
-                    })
+                    k.handle_input_event(
+                        &KeyEvent::new(key_code, KeyValue::Repeat).with_device(device),
+                    )
                     .expect("input handles fine");
                 }
+                "dev" => {
+                    device = match val {
+                        "none" => 0,
+                        _ => {
+                            k.input_devices
+                                .iter()
+                                .find(|dev| dev.name == val)
+                                .expect("valid defdevice name")
+                                .id
+                        }
+                    };
+                }
+                "ctx" => {
+                    let (key, value) = val.split_once('=').expect("ctx:key=value");
+                    k.set_context(key, value);
+                }
                 _ => panic!("invalid item {pair}"),
             },
             None => panic!("invalid item {pair}"),
         }
     }
-    drop(_lk);
-    k.kbd_out.outputs.events.join("\n")
 }
 
 #[allow(unused)]
@@ -0,0 +1,90 @@
+use super::*;
+
+#[test]
+fn mouse_movement_keys_remap() {
+    let result = simulate(
+        "(defsrc mml mmr mmu mmd)
+         (deflayer base left rght mmu _)",
+        "d:mml u:mml t:10 d:mmr u:mmr t:10 d:mmu u:mmu t:10 d:mmd u:mmd t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!(
+        "dn:Left up:Left dn:Right up:Right out🖰:move Up,16 out🖰:move Down,16",
+        result
+    );
+}
+
+#[test]
+fn mouse_movement_key_threshold_is_the_output_distance() {
+    let result = simulate(
+        "(defcfg mouse-movement-key-threshold 5)
+         (defsrc mmr)
+         (deflayer base _)",
+        "d:mmr u:mmr t:10",
+    )
+    .no_time();
+    assert_eq!("out🖰:move Right,5", result);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn mouse_movement_key_outputs_itself() {
+    let (_lk, mut k) = new_kanata(
+        "(defsrc mml mmr lctl)
+         (deflayer base _ mmr (layer-while-held gestures))
+         (deflayer gestures left _ _)",
+    );
+    let left = KeyEvent::new(str_to_oscode("mml").expect("key"), KeyValue::Tap);
+    let right = KeyEvent::new(str_to_oscode("mmr").expect("key"), KeyValue::Tap);
+    assert!(k.mouse_movement_key_outputs_itself(&left));
+    assert!(k.mouse_movement_key_outputs_itself(&right));
+    simulate_on(&mut k, "d:lctl t:10");
+    assert!(!k.mouse_movement_key_outputs_itself(&left));
+    assert!(k.mouse_movement_key_outputs_itself(&right));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn mouse_movement_drag_scroll_keeps_remainder() {
+    let mut movement = crate::kanata::MouseMovement::default();
+    // 120 hi-res units per notch, with a notch every 7 units of movement.
+    let scrolled: i32 = (0..7).map(|_| movement.drag_scroll(1, 1, 7)).sum();
+    assert_eq!(120, scrolled);
+    assert_eq!(0, movement.drag_scroll(1, 0, 7));
+    assert_eq!(-17, movement.drag_scroll(1, -1, 7));
+    assert_eq!(-34, movement.drag_scroll(1, -2, 7));
+    // Axes are independent.
+    assert_eq!(600, movement.drag_scroll(0, 35, 7));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn mouse_movement_key_taps() {
+    let mut movement = crate::kanata::MouseMovement::default();
+    assert_eq!(0, movement.key_taps(0, 10, 16));
+    assert_eq!(1, movement.key_taps(0, 10, 16));
+    assert_eq!(2, movement.key_taps(0, 28, 16));
+    // Movement in the opposite direction starts over.
+    assert_eq!(0, movement.key_taps(0, -15, 16));
+    assert_eq!(1, movement.key_taps(0, -1, 16));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn mouse_movement_speed_scaling() {
+    let mut movement = crate::kanata::MouseMovement::default();
+    assert_eq!(
+        vec![0, 0, 1, 1],
+        [1, 1, 1, 2]
+            .iter()
+            .map(|d| movement.scale_speed(0, *d, Some(40)))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(7, movement.scale_speed(1, 3, Some(250)));
+    assert_eq!(3, movement.scale_speed(1, 1, Some(250)));
+    assert_eq!(-2, movement.scale_speed(1, -1, Some(250)));
+    // Without a speed the movement passes through and the remainder is dropped.
+    assert_eq!(5, movement.scale_speed(1, 5, None));
+    assert_eq!(1, movement.scale_speed(1, 2, Some(50)));
+}
@@ -68,3 +68,54 @@ fn override_eagerly_releases() {
         result
     );
 }
+
+#[test]
+fn override_v2_action() {
+    let cfg = "
+(defsrc a b)
+(deflayer base a b)
+(deflayer other x y)
+(defoverridesv2
+  (lsft a) (macro c d) () ()
+  (lctl b) (layer-while-held other) () (lsft)
+)";
+    let result = simulate(cfg, "d:lsft t:10 d:a t:10 u:a t:10 u:lsft t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!(
+        "dn:LShift up:LShift dn:C up:C dn:D up:D dn:LShift up:LShift",
+        result
+    );
+    let result = simulate(
+        cfg,
+        "d:lctl t:10 d:b t:10 d:a t:10 u:a t:10 u:b t:10 d:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:LCtrl up:LCtrl dn:X up:X dn:LCtrl dn:A", result);
+    let result = simulate(cfg, "d:lctl t:10 d:lsft t:10 d:b t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:LCtrl dn:LShift dn:B", result);
+}
+
+#[test]
+fn override_v2_layer_scope() {
+    let cfg = "
+(defsrc a b)
+(deflayer base a @o)
+(deflayer other a b)
+(defalias o (layer-while-held other))
+(defvirtualkeys vk (unicode 🙂))
+(defoverridesv2
+  (ralt a) (on-press tap-vkey vk) (other) ()
+)";
+    let result = simulate(cfg, "d:ralt t:10 d:a t:10 u:a t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:RAlt dn:A up:A", result);
+    let result = simulate(cfg, "d:b t:10 d:ralt t:10 d:a t:10 u:a t:10")
+        .no_time()
+        .to_ascii();
+    assert_eq!("dn:RAlt up:RAlt outU:🙂 dn:RAlt", result);
+}
@@ -0,0 +1,37 @@
+use super::*;
+
+use kanata_parser::keys::OsCode;
+
+#[test]
+fn runtime_state_snapshot() {
+    let (_lk, mut k) = new_kanata(
+        "
+         (defsrc a b c)
+         (deflayer base (tap-hold 200 200 a lsft) (layer-while-held other) c)
+         (deflayer other _ _ x)
+        ",
+    );
+    k.handle_input_event(&KeyEvent::new(OsCode::KEY_B, KeyValue::Press))
+        .unwrap();
+    k.tick_ms(10, &None).unwrap();
+    k.handle_input_event(&KeyEvent::new(OsCode::KEY_A, KeyValue::Press))
+        .unwrap();
+    k.tick_ms(50, &None).unwrap();
+
+    let state = k.runtime_state();
+    assert_eq!(state.base_layer, "base");
+    assert_eq!(state.current_layer, "other");
+    assert_eq!(state.held_layers, vec!["other".to_string()]);
+    assert_eq!(state.waiting.len(), 1);
+    let waiting = &state.waiting[0];
+    assert_eq!(waiting.kind, "HoldTap");
+    assert_eq!(waiting.position.name.as_deref(), Some("A"));
+    assert!(waiting.ticks_elapsed > 0);
+    assert!(state
+        .active_states
+        .iter()
+        .any(|s| s.kind == "LayerModifier" && s.layer.as_deref() == Some("other")));
+    assert_eq!(state.sequence, None);
+    assert_eq!(state.caps_word_ticks_remaining, None);
+    assert_eq!(state.dynamic_macro_recording, None);
+}
@@ -0,0 +1,65 @@
+use super::*;
+
+#[test]
+fn sim_state_count_presses() {
+    let result = simulate(
+        "
+         (defsrc a b)
+         (defstate presses 0)
+         (deflayer base
+            (multi (state-inc presses)
+              (switch ((state presses >= 3)) x break () a break))
+            (state-set presses 0))
+        ",
+        "d:a u:a t:10 d:a u:a t:10 d:a u:a t:10 d:a u:a t:10 d:b u:b t:10 d:a u:a t:10",
+    )
+    .no_time()
+    .to_ascii();
+    // The switch sees the value from before the increment of the same press.
+    assert_eq!("dn:A up:A dn:A up:A dn:A up:A dn:X up:X dn:A up:A", result);
+}
+
+#[test]
+fn sim_state_toggle_and_cycle() {
+    let result = simulate(
+        "
+         (defsrc a b c)
+         (defstate gaming false mode 2)
+         (deflayer base
+            (state-toggle gaming)
+            (switch ((state mode = 0)) (state-inc mode) break () (state-dec mode 1) break)
+            (switch
+              ((and (state gaming = true) (state mode lt 1))) x break
+              ((state gaming eq true)) y break
+              () z break))
+        ",
+        "d:c u:c t:10 d:a u:a t:10 d:c u:c t:10 d:b u:b t:10 d:b u:b t:10 d:c u:c t:10 \
+         d:b u:b t:10 d:c u:c t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:Z up:Z dn:Y up:Y dn:X up:X dn:Y up:Y", result);
+}
+
+#[test]
+fn sim_state_external_access() {
+    let (_lk, mut k) = new_kanata(
+        "
+         (defsrc a)
+         (defstate flag false count -2)
+         (deflayer base (switch ((state flag = true)) x break () (state-dec count 32767) break))
+        ",
+    );
+    simulate_on(&mut k, "d:a u:a t:10 d:a u:a t:10");
+    let vars = k.state_var_values();
+    assert_eq!(vars.get("flag"), Some(&0));
+    assert_eq!(vars.get("count"), Some(&i16::MIN));
+    assert!(k.set_state_var("flag", 1));
+    assert!(!k.set_state_var("nope", 1));
+    simulate_on(&mut k, "d:a u:a t:10");
+    drop(_lk);
+    assert_eq!(
+        "dn:X up:X",
+        k.kbd_out.outputs.events.join("\n").no_time().to_ascii()
+    );
+}
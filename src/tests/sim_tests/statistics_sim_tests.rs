@@ -0,0 +1,138 @@
+use super::*;
+
+use kanata_tcp_protocol::{ChordCounts, HoldTapCounts};
+
+#[test]
+fn statistics_are_counted() {
+    let (_lk, mut k) = new_kanata(
+        "
+(defcfg statistics yes concurrent-tap-hold yes)
+(defsrc a b c d e f g)
+(deflayer base (tap-hold 100 100 a lsft) (layer-while-held other) c d e f sldr)
+(deflayer other _ _ x _ _ _ _)
+(defvirtualkeys s1 z)
+(defseq s1 (c f))
+(defchordsv2 (d e) y 50 all-released ())
+",
+    );
+    simulate_on(
+        &mut k,
+        "d:a t:20 u:a t:200 d:a t:200 u:a t:50 \
+         d:b t:10 d:c t:10 u:c t:10 u:b t:10 d:c t:10 u:c t:10 \
+         d:d d:e t:10 u:d u:e t:100 d:d t:100 u:d t:100 \
+         d:g t:10 u:g t:10 d:c t:10 u:c d:f t:10 u:f t:10",
+    );
+    let stats = &k.statistics.as_ref().expect("enabled").stats;
+    assert_eq!(stats.key_presses["a"], 2);
+    assert_eq!(stats.key_presses["c"], 3);
+    assert_eq!(stats.layer_key_presses["other"]["c"], 1);
+    assert_eq!(stats.layer_key_presses["base"]["c"], 2);
+    assert_eq!(stats.action_presses["(tap-hold a lsft)"], 2);
+    assert_eq!(stats.action_presses["(layer-while-held other)"], 1);
+    assert_eq!(stats.action_presses["x"], 1);
+    assert_eq!(
+        stats.hold_taps["a"],
+        HoldTapCounts {
+            tap: 1,
+            hold: 0,
+            timeout: 1
+        }
+    );
+    assert_eq!(stats.chords, ChordCounts { hits: 1, misses: 1 });
+    assert_eq!(stats.sequences["c f"], 1);
+}
+
+#[test]
+fn statistics_are_disabled_by_default() {
+    let (_lk, k) = new_kanata("(defsrc a) (deflayer base a)");
+    assert!(k.statistics.is_none());
+}
+
+#[test]
+fn statistics_are_saved_and_loaded() {
+    let path = std::env::temp_dir().join(format!("kanata-statistics-{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let cfg = format!(
+        "(defcfg statistics-file \"{}\") (defsrc a) (deflayer base a)",
+        path.display()
+    );
+    let (_lk, mut k) = new_kanata(&cfg);
+    simulate_on(&mut k, "d:a t:10 u:a t:10 d:a t:10 u:a t:10");
+    k.statistics.as_mut().expect("enabled").flush();
+    let saved: kanata_tcp_protocol::Statistics = std::fs::read_to_string(&path)
+        .expect("statistics file written")
+        .parse()
+        .expect("valid statistics");
+    assert_eq!(saved.key_presses["a"], 2);
+
+    // Counting continues from the saved statistics.
+    drop(_lk);
+    let (_lk, mut k) = new_kanata(&cfg);
+    simulate_on(&mut k, "d:a t:10 u:a t:10");
+    assert_eq!(k.statistics.as_ref().unwrap().stats.key_presses["a"], 3);
+
+    // Exiting saves the statistics.
+    let k = std::sync::Arc::new(parking_lot::Mutex::new(k));
+    crate::kanata::save_statistics_on_exit(&k);
+    crate::kanata::flush_statistics_on_exit();
+    let saved: kanata_tcp_protocol::Statistics = std::fs::read_to_string(&path)
+        .expect("statistics file written")
+        .parse()
+        .expect("valid statistics");
+    assert_eq!(saved.key_presses["a"], 3);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn statistics_are_saved_and_loaded_as_csv() {
+    let path = std::env::temp_dir().join(format!("kanata-statistics-{}.csv", std::process::id()));
+    // Fields with commas or quotes must survive a save and load.
+    let quoted = "action_press,,\"(multi , \"\"x\"\")\",1";
+    std::fs::write(&path, format!("statistic,layer,name,count\n{quoted}\n"))
+        .expect("statistics file written");
+    let cfg = format!(
+        "(defcfg statistics-file \"{}\") (defsrc a) (deflayer base a)",
+        path.display()
+    );
+    let (_lk, mut k) = new_kanata(&cfg);
+    assert_eq!(
+        k.statistics.as_ref().unwrap().stats.action_presses["(multi , \"x\")"],
+        1
+    );
+    simulate_on(&mut k, "d:a t:10 u:a t:10 d:a t:10 u:a t:10");
+    k.statistics.as_mut().expect("enabled").flush();
+    let csv = std::fs::read_to_string(&path).expect("statistics file written");
+    assert!(csv.contains("\nlayer_key_press,base,a,2\n"));
+    assert!(csv.contains(&format!("\n{quoted}\n")));
+
+    drop(_lk);
+    let (_lk, mut k) = new_kanata(&cfg);
+    simulate_on(&mut k, "d:a t:10 u:a t:10");
+    assert_eq!(k.statistics.as_ref().unwrap().stats.key_presses["a"], 3);
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn statistics_use_active_layer_stack() {
+    let (_lk, mut k) = new_kanata(
+        "
+(defcfg statistics yes)
+(defsrc a b c)
+(defdevice pad name \"Macro Pad\" layer pad)
+(deflayer base (layer-while-held l1) b c)
+(deflayer l1 _ (layer-while-held l2) x)
+(deflayer l2 _ _ _)
+(deflayer pad _ _ 1)
+",
+    );
+    simulate_on(
+        &mut k,
+        "d:a t:10 d:b t:10 d:c t:10 u:c u:b u:a t:10 dev:pad d:c t:10 u:c t:10",
+    );
+    let stats = &k.statistics.as_ref().expect("enabled").stats;
+    // The held layer on top of the stack is transparent, so the press resolves from below it.
+    assert_eq!(stats.layer_key_presses["l2"]["c"], 1);
+    assert_eq!(stats.action_presses["x"], 1);
+    assert_eq!(stats.layer_key_presses["pad"]["c"], 1);
+    assert_eq!(stats.action_presses["1"], 1);
+}
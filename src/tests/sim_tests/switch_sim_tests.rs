@@ -35,3 +35,18 @@ fn sim_switch_base_layer() {
     .no_time();
     assert_eq!("out:↓X out:↑X out:↓Y out:↑Y out:↓Y out:↑Y", result);
 }
+
+#[test]
+fn sim_switch_input_device() {
+    let result = simulate(
+        "
+         (defcfg)
+         (defsrc a)
+         (defdevice pad name \"Macro Pad\")
+         (deflayer base (switch ((input-device pad)) x break () y break))
+        ",
+        "d:a u:a t:10 dev:pad d:a u:a t:10 dev:none d:a u:a t:10",
+    )
+    .no_time();
+    assert_eq!("out:↓Y out:↑Y out:↓X out:↑X out:↓Y out:↑Y", result);
+}
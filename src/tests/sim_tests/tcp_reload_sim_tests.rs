@@ -0,0 +1,129 @@
+use super::*;
+
+use crate::tcp_server::*;
+use kanata_tcp_protocol::*;
+
+use std::sync::Arc;
+
+/// Send the message like a TCP client would and run the processing loop's live reload. Returns
+/// the reply to the client.
+fn reload(k: &Arc<parking_lot::Mutex<Kanata>>, msg: ClientMessage) -> Option<ServerMessage> {
+    let (wakeup_tx, wakeup_rx) = std::sync::mpsc::sync_channel(1);
+    let client = {
+        let k = k.clone();
+        std::thread::spawn(move || {
+            let clients: Connections = Arc::new(parking_lot::Mutex::new(Default::default()));
+            handle_client_message(msg, Some(1), &k, "client", &clients, &wakeup_tx)
+        })
+    };
+    wakeup_rx.recv().expect("client requests a reload");
+    k.lock().handle_time_ticks(&None).unwrap();
+    client.join().unwrap().expect("reload is handled")
+}
+
+fn reload_text(k: &Arc<parking_lot::Mutex<Kanata>>, text: &str) -> Option<ServerMessage> {
+    let text = text.to_string();
+    reload(k, ClientMessage::LoadConfig { text })
+}
+
+#[test]
+fn config_text_is_live_reloaded() {
+    let (_lk, k) = new_kanata("(defsrc a) (deflayer base b)");
+    let k = Arc::new(parking_lot::Mutex::new(k));
+
+    assert!(reload_text(&k, "(defsrc a) (deflayer base c)").is_none());
+    simulate_on(&mut k.lock(), "d:a t:10 u:a t:10");
+    assert_eq!(
+        "dn:C up:C",
+        k.lock()
+            .kbd_out
+            .outputs
+            .events
+            .join("\n")
+            .no_time()
+            .to_ascii()
+    );
+
+    // An invalid configuration is reported and the current one is kept.
+    let reply = reload_text(&k, "(defsrc a) (deflayer base d e)");
+    assert!(matches!(
+        reply,
+        Some(ServerMessage::ConfigError {
+            span: Some(_),
+            id: Some(1),
+            ..
+        })
+    ));
+    k.lock().kbd_out.outputs.events.clear();
+    simulate_on(&mut k.lock(), "d:a t:10 u:a t:10");
+    assert_eq!(
+        "dn:C up:C",
+        k.lock()
+            .kbd_out
+            .outputs
+            .events
+            .join("\n")
+            .no_time()
+            .to_ascii()
+    );
+}
+
+#[test]
+fn failed_reload_keeps_the_current_config_file() {
+    let (_lk, k) = new_kanata("(defsrc a) (deflayer base b)");
+    let k = Arc::new(parking_lot::Mutex::new(k));
+    let path = std::env::temp_dir().join(format!("kanata-reload-{}.kbd", std::process::id()));
+    std::fs::write(&path, "(defsrc a) (deflayer base d e)").expect("writes config");
+    k.lock().cfg_paths.push(path.clone());
+
+    let reply = reload(&k, ClientMessage::ReloadNum { index: 1 });
+    assert!(matches!(reply, Some(ServerMessage::ConfigError { .. })));
+    assert_eq!(0, k.lock().cur_cfg_idx);
+
+    std::fs::write(&path, "(defsrc a) (deflayer base c)").expect("writes config");
+    assert!(reload(&k, ClientMessage::ReloadNum { index: 1 }).is_none());
+    assert_eq!(1, k.lock().cur_cfg_idx);
+
+    let _ = std::fs::remove_file(&path);
+}
+
+#[test]
+fn reload_is_rejected_while_another_is_pending() {
+    let (_lk, mut k) = new_kanata("(defsrc a) (deflayer base b)");
+    let text = "(defsrc a) (deflayer base c)".to_string();
+    let (first_tx, first_rx) = std::sync::mpsc::channel();
+    k.request_live_reload(0, Some(text.clone()), first_tx)
+        .expect("first reload is requested");
+    let (second_tx, _second_rx) = std::sync::mpsc::channel();
+    assert!(k.request_live_reload(0, Some(text), second_tx).is_err());
+
+    k.handle_time_ticks(&None).unwrap();
+    assert!(matches!(first_rx.try_recv(), Ok(Ok(()))));
+    let (third_tx, _third_rx) = std::sync::mpsc::channel();
+    assert!(k.request_live_reload(0, None, third_tx).is_ok());
+}
+
+#[test]
+fn reload_releases_active_action_overrides() {
+    let cfg = "(defsrc lsft a) (deflayer base lsft a) (defoverridesv2 (lsft a) mlft () ())";
+    let (_lk, k) = new_kanata(cfg);
+    let k = Arc::new(parking_lot::Mutex::new(k));
+    simulate_on(&mut k.lock(), "d:lsft t:10 d:a t:10");
+    assert!(k
+        .lock()
+        .kbd_out
+        .outputs
+        .events
+        .join(" ")
+        .contains("out🖰:↓Left"));
+    // Keys that stay pressed only delay the reload for a while.
+    k.lock().ticks_since_idle = 1001;
+    assert!(reload_text(&k, cfg).is_none());
+    assert!(k
+        .lock()
+        .kbd_out
+        .outputs
+        .events
+        .join(" ")
+        .contains("out🖰:↑Left"));
+}
@@ -0,0 +1,90 @@
+use super::*;
+
+#[test]
+fn sim_timer_after() {
+    let result = simulate(
+        "
+         (defsrc a b)
+         (deflayer base (after t1 100 x) b)
+        ",
+        "d:a u:a t:50 d:b u:b t:49 t:1 t:100",
+    )
+    .to_ascii();
+    assert_eq!("t:50ms dn:B t:1ms up:B t:49ms dn:X t:1ms up:X", result);
+}
+
+#[test]
+fn sim_timer_after_within_defvirtualkeys() {
+    let result = simulate(
+        "
+         (defsrc a)
+         (deflayer base (on-press tap-vkey t))
+         (defvirtualkeys t (after x 100 c) v2 d)
+        ",
+        "d:a u:a t:200",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:C up:C", result);
+}
+
+#[test]
+fn sim_timer_after_restart_replaces() {
+    let result = simulate(
+        "
+         (defsrc a)
+         (deflayer base (after t1 100 x))
+        ",
+        "d:a u:a t:80 d:a u:a t:99 t:1 t:200",
+    )
+    .to_ascii();
+    assert_eq!("t:180ms dn:X t:1ms up:X", result);
+}
+
+#[test]
+fn sim_timer_every_and_cancel() {
+    let result = simulate(
+        "
+         (defsrc a b)
+         (deflayer base (every jiggle 50 y) (timer-cancel jiggle))
+        ",
+        "d:a u:a t:120 d:b u:b t:200",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:Y up:Y dn:Y up:Y", result);
+}
+
+#[test]
+fn sim_timer_layer_timeout() {
+    let result = simulate(
+        "
+         (defsrc a b)
+         (deflayer base (multi (layer-switch other) (after back 500 (layer-switch base))) b)
+         (deflayer other a x)
+        ",
+        "d:a u:a t:10 d:b u:b t:10 t:500 d:b u:b t:10",
+    )
+    .no_time()
+    .to_ascii();
+    assert_eq!("dn:X up:X dn:B up:B", result);
+}
+
+#[test]
+fn sim_timer_cancel_externally() {
+    let (_lk, mut k) = new_kanata(
+        "
+         (defsrc a)
+         (deflayer base (every tick 10 x))
+        ",
+    );
+    simulate_on(&mut k, "d:a u:a t:15");
+    assert!(k.cancel_timer("tick"));
+    assert!(!k.cancel_timer("tick"));
+    simulate_on(&mut k, "t:100");
+    drop(_lk);
+    assert_eq!(
+        "dn:X up:X",
+        k.kbd_out.outputs.events.join("\n").no_time().to_ascii()
+    );
+}
@@ -1,15 +1,99 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
+/// Version of the protocol, reported in `ServerMessage::Hello`. This is incremented when a
+/// change is made that existing clients may not be compatible with.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Replies to a `ClientRequest` contain the request's `id`, if one was given.
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ServerMessage {
-    LayerChange { new: String },
-    LayerNames { names: Vec<String> },
-    CurrentLayerInfo { name: String, cfg_text: String },
-    ConfigFileReload { new: String },
-    CurrentLayerName { name: String },
-    MessagePush { message: serde_json::Value },
-    Error { msg: String },
+    LayerChange {
+        new: String,
+        /// Metadata of the new layer from its free-form options, e.g. `description` and `color`.
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+    },
+    LayerNames {
+        names: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    CurrentLayerInfo {
+        name: String,
+        cfg_text: String,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        metadata: BTreeMap<String, String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    ConfigFileReload {
+        new: String,
+    },
+    CurrentLayerName {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    MessagePush {
+        message: serde_json::Value,
+    },
+    /// Reply to `ClientMessage::Hello`.
+    Hello {
+        /// Version of kanata.
+        version: String,
+        protocol_version: u32,
+        /// Cargo features that kanata was built with.
+        features: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to `ClientMessage::RequestState`.
+    State {
+        state: RuntimeState,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to `ClientMessage::RequestStatistics`.
+    Statistics {
+        statistics: Statistics,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to `ClientMessage::RequestDynamicMacroConfig`.
+    DynamicMacroConfig {
+        macro_id: u16,
+        /// The macro as a `macro` action, e.g. `(macro h 30 i)`.
+        config: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to `ClientMessage::RequestStateVariables`.
+    StateVariables {
+        /// Current values of the variables defined in `defstate`, by name.
+        variables: BTreeMap<String, i16>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to a successful request that has an `id` and no other reply.
+    Ok {
+        id: Option<u64>,
+    },
+    Error {
+        msg: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    /// Reply to a reload or validation request when the configuration is invalid.
+    ConfigError {
+        msg: String,
+        /// Location of the error, if it is known.
+        span: Option<ConfigErrorSpan>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u64>,
+    },
+    Event(EventMessage),
 }
 
 impl ServerMessage {
@@ -18,24 +102,351 @@ impl ServerMessage {
         msg.push(b'\n');
         msg
     }
+
+    /// The subscription topic this message belongs to. Messages without a topic are sent to
+    /// every client.
+    pub fn topic(&self) -> Option<SubscriptionTopic> {
+        match self {
+            ServerMessage::Event(event) => Some(event.topic()),
+            _ => None,
+        }
+    }
+}
+
+/// Events sent in `ServerMessage::Event` to the clients subscribed to their topic.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum EventMessage {
+    /// An input event received by kanata. Sent for `SubscriptionTopic::InputEvents`.
+    InputEvent {
+        key: String,
+        action: KeyEventAction,
+        timestamp_us: u64,
+    },
+    /// A key press or release that kanata output. Sent for `SubscriptionTopic::KeyOutput`.
+    KeyOutput {
+        key: String,
+        action: KeyEventAction,
+        timestamp_us: u64,
+    },
+    /// A mouse action that kanata output. Sent for `SubscriptionTopic::MouseActions`.
+    MouseAction {
+        action: MouseActionMessage,
+        timestamp_us: u64,
+    },
+    /// A unicode character that kanata output. Sent for `SubscriptionTopic::UnicodeOutput`.
+    UnicodeOutput { text: String, timestamp_us: u64 },
+    /// A virtual key was pressed or released. Sent for `SubscriptionTopic::VirtualKeys`.
+    VirtualKeyChange {
+        name: String,
+        pressed: bool,
+        timestamp_us: u64,
+    },
+    /// A tap-hold action was resolved. Sent for `SubscriptionTopic::HoldTapDecisions`.
+    HoldTapDecision {
+        decision: HoldTapDecision,
+        timestamp_us: u64,
+    },
+}
+
+impl EventMessage {
+    pub fn topic(&self) -> SubscriptionTopic {
+        match self {
+            EventMessage::InputEvent { .. } => SubscriptionTopic::InputEvents,
+            EventMessage::KeyOutput { .. } => SubscriptionTopic::KeyOutput,
+            EventMessage::MouseAction { .. } => SubscriptionTopic::MouseActions,
+            EventMessage::UnicodeOutput { .. } => SubscriptionTopic::UnicodeOutput,
+            EventMessage::VirtualKeyChange { .. } => SubscriptionTopic::VirtualKeys,
+            EventMessage::HoldTapDecision { .. } => SubscriptionTopic::HoldTapDecisions,
+        }
+    }
+}
+
+/// Event streams that a client can opt into with `ClientMessage::Subscribe`. Events carry a
+/// `timestamp_us` field, which is a monotonic time in microseconds since the TCP server started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum SubscriptionTopic {
+    InputEvents,
+    KeyOutput,
+    MouseActions,
+    UnicodeOutput,
+    VirtualKeys,
+    HoldTapDecisions,
+}
+
+/// Snapshot of kanata's processing state. Times are in ticks, which are approximately 1ms.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct RuntimeState {
+    /// The layer most recently switched to with `layer-switch`, or the first layer.
+    pub base_layer: String,
+    /// The layer that key presses are currently resolved from.
+    pub current_layer: String,
+    /// Layers activated by held keys, most recently activated first.
+    pub held_layers: Vec<String>,
+    /// Active key states, e.g. output keys and layer modifiers that are held.
+    pub active_states: Vec<ActiveState>,
+    /// Keys waiting on a decision, e.g. a tap-hold that is not yet a tap or hold.
+    pub waiting: Vec<WaitingState>,
+    /// Inputs waiting on a chord decision of `defchordsv2`.
+    pub pending_chord_inputs: Vec<KeyPosition>,
+    pub one_shot: OneShotState,
+    /// Ticks until caps-word ends, if caps-word is active.
+    pub caps_word_ticks_remaining: Option<u16>,
+    pub sequence: Option<SequenceState>,
+    pub dynamic_macro_recording: Option<DynamicMacroRecording>,
+}
+
+/// Position of a key in kanata's layout. Row 0 contains the physical input keys, where `name` is
+/// the key name. Other rows are for virtual keys and internal use, where `name` is the virtual
+/// key name if it is known.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct KeyPosition {
+    pub row: u8,
+    pub column: u16,
+    pub name: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ActiveState {
+    /// Kind of state, e.g. `NormalKey` or `LayerModifier`.
+    pub kind: String,
+    /// The key that activated the state, if any.
+    pub position: Option<KeyPosition>,
+    /// The output key, for states that output a key.
+    pub key: Option<String>,
+    /// The layer, for layer modifier states.
+    pub layer: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct WaitingState {
+    /// One of `HoldTap`, `TapDance` or `Chord`.
+    pub kind: String,
+    pub position: KeyPosition,
+    pub ticks_elapsed: u16,
+    pub ticks_until_timeout: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct OneShotState {
+    /// One-shot keys that are active.
+    pub keys: Vec<KeyPosition>,
+    /// Active one-shot keys that have been released.
+    pub released_keys: Vec<KeyPosition>,
+    pub ticks_until_timeout: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SequenceState {
+    /// Keys typed so far in the sequence.
+    pub keys: Vec<String>,
+    pub ticks_until_timeout: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DynamicMacroRecording {
+    pub macro_id: u16,
+    /// Number of press and release events recorded so far.
+    pub recorded_events: usize,
+}
+
+/// How a tap-hold action was resolved, for diagnosing misfires.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct HoldTapDecision {
+    /// The tap-hold key.
+    pub position: KeyPosition,
+    /// One of `tap`, `hold` or `timeout`, where `timeout` activates the timeout action of
+    /// `tap-hold-press-timeout` and `tap-hold-release-timeout`, and the hold action otherwise.
+    pub outcome: String,
+    /// Why the outcome was chosen. One of:
+    /// - `release`: the key was released before the hold timeout
+    /// - `release-after-timeout`: the key was released after the hold timeout expired
+    /// - `timeout`: the hold timeout expired with the key still held
+    /// - `concurrent-timeout`: as `timeout`, where the timeout was shortened by
+    ///   `concurrent-tap-hold`
+    /// - `other-key-press`: another key was pressed, for `tap-hold-press`
+    /// - `permissive-hold`: another key was pressed and released, for `tap-hold-release` and
+    ///   `tap-hold-release-keys`
+    /// - `tap-keys-press`: a key in the list of `tap-hold-release-keys` or
+    ///   `tap-hold-except-keys` was pressed
+    /// - `opposite-hand`: a key of the opposite hand or a neutral key was pressed, for
+    ///   `tap-hold-opposite-hand`
+    /// - `same-hand`: a key of the same hand was pressed, for `tap-hold-opposite-hand`
+    /// - `quick-tap`: the key was pressed again within the tap timeout of its previous press
+    /// - `require-prior-idle`: another key was pressed shortly before the key, for
+    ///   `tap-hold-require-prior-idle`
+    /// - `queue-overflow`: too many events were waiting on the decision, which holds right away
+    pub reason: String,
+    /// Time from the key press until the decision.
+    pub elapsed_ms: u16,
+    /// The configured hold timeout.
+    pub timeout_ms: u16,
+    /// Events that were waiting on the decision, oldest first.
+    pub queued: Vec<QueuedKeyEvent>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct QueuedKeyEvent {
+    pub position: KeyPosition,
+    pub action: KeyEventAction,
+    /// Time since the event happened.
+    pub age_ms: u16,
+}
+
+/// Usage statistics collected by kanata. Keys are named as in `defsrc` and layers by their
+/// names in the configuration. The same structure is saved to the `statistics-file`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Statistics {
+    /// Presses of each input key.
+    pub key_presses: BTreeMap<String, u64>,
+    /// Presses of each input key, grouped by the layer that was active for the press.
+    pub layer_key_presses: BTreeMap<String, BTreeMap<String, u64>>,
+    /// Presses grouped by a short description of the action the key resolved to, e.g. `a`,
+    /// `(tap-hold a lsft)` or `(layer-while-held nav)`.
+    pub action_presses: BTreeMap<String, u64>,
+    /// Outcomes of tap-hold actions for each input key.
+    pub hold_taps: BTreeMap<String, HoldTapCounts>,
+    /// Activations and misses of `defchordsv2` chords.
+    pub chords: ChordCounts,
+    /// Completions of each sequence, by the keys typed for the sequence.
+    pub sequences: BTreeMap<String, u64>,
+}
+
+impl Statistics {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("statistics are serializable")
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct HoldTapCounts {
+    pub tap: u64,
+    pub hold: u64,
+    /// Resolved by the timeout action, which is the hold action unless configured otherwise.
+    pub timeout: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ChordCounts {
+    /// Number of chords activated.
+    pub hits: u64,
+    /// Number of times chord keys were pressed without activating a chord.
+    pub misses: u64,
+}
+
+/// Location of a configuration error.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ConfigErrorSpan {
+    pub file_name: String,
+    /// Byte offset of the start of the error.
+    pub start: usize,
+    /// Byte offset of the end of the error.
+    pub end: usize,
+    /// 1-based line of the start of the error.
+    pub line: usize,
+    /// 1-based column of the start of the error.
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum KeyEventAction {
+    Press,
+    Release,
+    Repeat,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum MouseActionMessage {
+    Press { button: String },
+    Release { button: String },
+    Scroll { direction: String, distance: u16 },
+    Move { direction: String, distance: u16 },
+    SetPosition { x: u16, y: u16 },
+}
+
+/// A `ClientMessage` with an optional `id`. The `id` is placed alongside the message, e.g.
+/// `{"id":1,"ChangeLayer":{"new":"base"}}`. When an `id` is given, the server replies with the
+/// same `id` in the message answering the request, which is `ServerMessage::Ok` if the request
+/// has no other reply. Errors are replied with `ServerMessage::Error` regardless of the `id`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientRequest {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    #[serde(flatten)]
+    pub msg: ClientMessage,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ClientMessage {
+    /// Request the server's versions and features, replied with `ServerMessage::Hello`.
+    Hello {},
     ChangeLayer {
         new: String,
     },
     RequestLayerNames {},
     RequestCurrentLayerInfo {},
     RequestCurrentLayerName {},
+    /// Request a snapshot of kanata's processing state, replied with `ServerMessage::State`.
+    RequestState {},
+    /// Request the usage statistics collected so far, replied with
+    /// `ServerMessage::Statistics`. Statistics must be enabled in the configuration.
+    RequestStatistics {},
     ActOnFakeKey {
         name: String,
         action: FakeKeyActionMessage,
     },
+    /// Request a recorded dynamic macro written as configuration text, replied with
+    /// `ServerMessage::DynamicMacroConfig`.
+    RequestDynamicMacroConfig {
+        macro_id: u16,
+    },
     SetMouse {
         x: u16,
         y: u16,
     },
+    /// Request the values of the variables defined in `defstate`, replied with
+    /// `ServerMessage::StateVariables`.
+    RequestStateVariables {},
+    /// Sets the value of a variable defined in `defstate`.
+    SetStateVariable {
+        name: String,
+        value: i16,
+    },
+    /// Stops a timer started by an `after` or `every` action.
+    CancelTimer {
+        name: String,
+    },
+    /// Sets a context value that `switch` and `defcontextlayers` can react to, e.g. the class of
+    /// the focused window sent by a window manager watcher. An empty value removes the key.
+    SetContext {
+        key: String,
+        value: String,
+    },
+    /// Replaces the set of event topics this client receives. An empty list unsubscribes from
+    /// all topics.
+    Subscribe {
+        topics: Vec<SubscriptionTopic>,
+    },
+    /// Live reload the current configuration file, like the `lrld` action.
+    Reload {},
+    /// Live reload the configuration file with the given path, like the `lrld-file` action. The
+    /// path must be one of the configuration files that kanata was started with.
+    ReloadFile {
+        path: String,
+    },
+    /// Live reload the configuration file at the 0-based index of the files that kanata was
+    /// started with.
+    ReloadNum {
+        index: usize,
+    },
+    /// Load the configuration text in place of the current configuration, as a live reload does.
+    /// The text is validated before it is loaded: if it is invalid, the current configuration is
+    /// kept and the reply is a `ServerMessage::ConfigError`. Files cannot be included by the text.
+    LoadConfig {
+        text: String,
+    },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -53,3 +464,82 @@ impl FromStr for ClientMessage {
         serde_json::from_str(s)
     }
 }
+
+impl FromStr for Statistics {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+impl FromStr for ClientRequest {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        serde_json::from_str(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn client_request_without_id() {
+        let req: ClientRequest = r#"{"ChangeLayer":{"new":"base"}}"#.parse().unwrap();
+        assert_eq!(req.id, None);
+        assert!(matches!(req.msg, ClientMessage::ChangeLayer { new } if new == "base"));
+    }
+
+    #[test]
+    fn client_request_set_context() {
+        let req: ClientRequest =
+            r#"{"SetContext":{"key":"app","value":"firefox"}}"#.parse().unwrap();
+        assert!(matches!(
+            req.msg,
+            ClientMessage::SetContext { key, value } if key == "app" && value == "firefox"
+        ));
+    }
+
+    #[test]
+    fn client_request_with_id() {
+        let req: ClientRequest = r#"{"id":7,"Hello":{}}"#.parse().unwrap();
+        assert_eq!(req.id, Some(7));
+        assert!(matches!(req.msg, ClientMessage::Hello {}));
+    }
+
+    #[test]
+    fn layer_change_metadata() {
+        let msg = ServerMessage::LayerChange {
+            new: "base".into(),
+            metadata: BTreeMap::new(),
+        };
+        assert_eq!(
+            serde_json::to_string(&msg).unwrap(),
+            r#"{"LayerChange":{"new":"base"}}"#
+        );
+        let msg = ServerMessage::LayerChange {
+            new: "nav".into(),
+            metadata: [("color".to_string(), "#00f".to_string())].into(),
+        };
+        assert_eq!(
+            serde_json::to_string(&msg).unwrap(),
+            r##"{"LayerChange":{"new":"nav","metadata":{"color":"#00f"}}}"##
+        );
+    }
+
+    #[test]
+    fn server_reply_ids() {
+        let err = ServerMessage::Error {
+            msg: "bad".into(),
+            id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&err).unwrap(),
+            r#"{"Error":{"msg":"bad"}}"#
+        );
+        let ok = ServerMessage::Ok { id: Some(7) };
+        assert_eq!(serde_json::to_string(&ok).unwrap(), r#"{"Ok":{"id":7}}"#);
+    }
+}